@@ -0,0 +1,159 @@
+//!
+//! Results of analysis: scores in centipawns or mate in moves, principal variations and statistics of the search.
+//!
+
+use super::SearchInfo;
+use crate::{Board, Move};
+
+use std::fmt;
+use std::time::Duration;
+
+///
+/// Score of a position for the side to move.
+///
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Score
+{
+  /// Advantage in centipawns.
+  Centipawns(i32),
+  /// Mate in the number of moves, negative if the side to move is mated.
+  Mate(i32),
+}
+
+///
+/// Formats centipawns as pawns with a sign, "+0.35", and mates as "#3" or "#-2".
+///
+
+impl fmt::Display for Score
+{
+  fn fmt(&self, f : &mut fmt::Formatter<'_>) -> fmt::Result
+  {
+    let score = match *self
+    {
+      Score::Centipawns(centipawns) => format!("{:+.2}", centipawns as f64 / 100.0),
+      Score::Mate(moves) => format!("#{}", moves),
+    };
+    f.pad(&score)
+  }
+}
+
+///
+/// Line of play expected by the engine.
+///
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Line
+{
+  /// Score of the position for the side to move if the line is played.
+  pub score : Score,
+  /// Principal variation, the first move is the move of the side to move.
+  pub pv : Vec<Move>,
+}
+
+impl Line
+{
+  ///
+  /// Moves of the line in SAN, played from the board. Stops at the first move which is not legal.
+  ///
+  pub fn to_san(&self, board : &Board) -> Vec<String>
+  {
+    let mut board = board.clone();
+    let mut moves = Vec::new();
+    for m in &self.pv
+    {
+      match board.move_to_san(*m)
+      {
+        Ok(san) => moves.push(san),
+        Err(_) => break,
+      }
+      board.apply(*m);
+    }
+    moves
+  }
+}
+
+///
+/// Result of analysis of a position: best lines, best first, and statistics of the search.
+///
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Analysis
+{
+  /// Depth of the deepest finished iteration in half-moves, 0 if the algorithm doesn't report depth.
+  pub depth : u16,
+  /// Positions visited.
+  pub nodes : u64,
+  /// Time of the search.
+  pub time : Duration,
+  /// Best lines, best first. Empty if there are no legal moves.
+  pub lines : Vec<Line>,
+}
+
+impl Analysis
+{
+  ///
+  /// Collects the lines of the deepest iteration. Lines which were not finished at that depth are taken from shallower ones.
+  ///
+  pub(crate) fn from_infos(infos : &[SearchInfo], nodes : u64, time : Duration) -> Self
+  {
+    let depth = infos.iter().map(|info| info.depth).max().unwrap_or(0);
+    let mut latest : Vec<&SearchInfo> = Vec::new();
+    for info in infos
+    {
+      match latest.iter_mut().find(|line| line.multipv == info.multipv)
+      {
+        Some(line) => *line = info,
+        None => latest.push(info),
+      }
+    }
+    latest.sort_by_key(|info| (std::cmp::Reverse(info.depth), info.multipv));
+
+    let mut lines : Vec<Line> = Vec::new();
+    for info in latest
+    {
+      if lines.iter().all(|line| line.pv.first() != info.pv.first())
+      {
+        lines.push(Line { score : info.score, pv : info.pv.clone() });
+      }
+    }
+    Self { depth, nodes, time, lines }
+  }
+
+  ///
+  /// Speed of the search in positions per second.
+  ///
+  pub fn nps(&self) -> u64
+  {
+    let seconds = self.time.as_secs_f64();
+    if seconds > 0.0 { (self.nodes as f64 / seconds) as u64 } else { 0 }
+  }
+
+  ///
+  /// First move of the best line, null move if there are no legal moves.
+  ///
+  pub fn best_move(&self) -> Move { self.lines.first().and_then(|line| line.pv.first().copied()).unwrap_or_else(Move::null) }
+
+  ///
+  /// Score of the best line, `None` if there are no legal moves.
+  ///
+  pub fn score(&self) -> Option<Score> { self.lines.first().map(|line| line.score) }
+}
+
+///
+/// Prints statistics, then a line per row with its score and moves in UCI notation.
+///
+
+impl fmt::Display for Analysis
+{
+  fn fmt(&self, f : &mut fmt::Formatter<'_>) -> fmt::Result
+  {
+    writeln!(f, "depth {}, nodes {}, time {:.2}s, {} nodes/s", self.depth, self.nodes, self.time.as_secs_f64(), self.nps())?;
+    for (i, line) in self.lines.iter().enumerate()
+    {
+      let moves : Vec<String> = line.pv.iter().map(|m| m.stringify()).collect();
+      writeln!(f, "{}. {:>7} {}", i + 1, line.score, moves.join(" "))?;
+    }
+    Ok(())
+  }
+}
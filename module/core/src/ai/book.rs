@@ -0,0 +1,238 @@
+//!
+//! Polyglot opening books. An entry of a book is 16 bytes in big endian: key of the position, move, weight and learn data.
+//! Entries are sorted by key.
+//!
+
+use crate::{Board, Error, Move, PieceType};
+
+use serde::{Serialize, Deserialize};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+///
+/// Size of an entry of a Polyglot book in bytes.
+///
+
+const ENTRY_SIZE : usize = 16;
+
+///
+/// How the move is picked among the book moves of a position.
+///
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum BookMode
+{
+  /// Move with the greatest weight.
+  Best,
+  /// Random move, the chance of a move is proportional to its weight.
+  #[default]
+  Weighted,
+}
+
+///
+/// Entry of a Polyglot book.
+///
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BookEntry
+{
+  /// Polyglot key of the position, see [Board::polyglot_key].
+  pub key : u64,
+  /// Move in the encoding of Polyglot: target square, source square and promotion, 6, 6 and 3 bits.
+  pub raw_move : u16,
+  /// Weight of the move, moves with zero weight are not played.
+  pub weight : u16,
+  /// Learn data, not used.
+  pub learn : u32,
+}
+
+///
+/// Polyglot opening book. The book is serialized as its path and mode, the entries are read from the path again.
+/// A book which can't be read any more is deserialized without entries, see [Book::is_loaded].
+///
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(from = "BookState", into = "BookState")]
+pub struct Book
+{
+  path : PathBuf,
+  mode : BookMode,
+  entries : Option<Arc<Vec<BookEntry>>>,
+}
+
+impl Book
+{
+  ///
+  /// Reads book from the file.
+  ///
+  pub fn open(path : impl AsRef<Path>) -> Result<Self, Error>
+  {
+    let bytes = std::fs::read(path.as_ref())?;
+    let mut book = Self::from_bytes(&bytes)?;
+    book.path = path.as_ref().to_path_buf();
+    Ok(book)
+  }
+
+  ///
+  /// Reads book from the content of a `.bin` file. The book has no path, so it is deserialized without entries.
+  ///
+  pub fn from_bytes(bytes : &[u8]) -> Result<Self, Error>
+  {
+    if !bytes.len().is_multiple_of(ENTRY_SIZE)
+    {
+      return Err(Error::InvalidBook(format!("size {} is not a multiple of {}", bytes.len(), ENTRY_SIZE)));
+    }
+    let mut entries : Vec<BookEntry> = bytes
+      .chunks_exact(ENTRY_SIZE)
+      .map(|entry|
+      {
+        BookEntry {
+          key : u64::from_be_bytes(entry[0..8].try_into().unwrap()),
+          raw_move : u16::from_be_bytes(entry[8..10].try_into().unwrap()),
+          weight : u16::from_be_bytes(entry[10..12].try_into().unwrap()),
+          learn : u32::from_be_bytes(entry[12..16].try_into().unwrap()),
+        }
+      })
+      .collect();
+    entries.sort_by_key(|entry| entry.key);
+    Ok(Self { path : PathBuf::new(), mode : BookMode::default(), entries : Some(Arc::new(entries)) })
+  }
+
+  ///
+  /// Sets how the move is picked.
+  ///
+  pub fn with_mode(mut self, mode : BookMode) -> Self
+  {
+    self.mode = mode;
+    self
+  }
+
+  ///
+  /// How the move is picked.
+  ///
+  pub fn mode(&self) -> BookMode { self.mode }
+
+  ///
+  /// Path of the file of the book, empty if the book was not read from a file.
+  ///
+  pub fn path(&self) -> &Path { &self.path }
+
+  ///
+  /// False if the file of a deserialized book couldn't be read. Such a book has no entries.
+  ///
+  pub fn is_loaded(&self) -> bool { self.entries.is_some() }
+
+  ///
+  /// Number of entries.
+  ///
+  pub fn len(&self) -> usize { self.entries().len() }
+
+  ///
+  /// True if the book has no entries.
+  ///
+  pub fn is_empty(&self) -> bool { self.entries().is_empty() }
+
+  fn entries(&self) -> &[BookEntry] { self.entries.as_deref().map_or(&[], Vec::as_slice) }
+
+  ///
+  /// Legal book moves of the position with their weights, in the order of the book.
+  ///
+  pub fn moves(&self, board : &Board) -> Vec<(Move, u16)>
+  {
+    let key = board.polyglot_key();
+    let entries = self.entries();
+    let start = entries.partition_point(|entry| entry.key < key);
+    let legal = board.legal_moves();
+    entries[start..]
+      .iter()
+      .take_while(|entry| entry.key == key)
+      .filter_map(|entry|
+      {
+        let uci = uci_move(board, entry.raw_move);
+        legal.iter().find(|m| m.stringify() == uci).map(|m| (*m, entry.weight))
+      })
+      .collect()
+  }
+
+  ///
+  /// Picks a book move of the position by the mode of the book. `None` if the book has no move with weight.
+  ///
+  pub fn choose(&self, board : &Board) -> Option<Move>
+  {
+    let moves : Vec<(Move, u16)> = self.moves(board).into_iter().filter(|(_, weight)| *weight > 0).collect();
+    match self.mode
+    {
+      BookMode::Best => moves.iter().rev().max_by_key(|(_, weight)| *weight).map(|(m, _)| *m),
+      BookMode::Weighted =>
+      {
+        let total : u64 = moves.iter().map(|(_, weight)| *weight as u64).sum();
+        if total == 0
+        {
+          return None;
+        }
+        let mut target = crate::random_below(total);
+        for (m, weight) in &moves
+        {
+          if target < *weight as u64
+          {
+            return Some(*m);
+          }
+          target -= *weight as u64;
+        }
+        None
+      }
+    }
+  }
+}
+
+///
+/// Move of a book entry in UCI notation. Polyglot encodes castling as the king taking its rook,
+/// which is the notation of Chess960 boards, but not of classical ones.
+///
+
+fn uci_move(board : &Board, raw_move : u16) -> String
+{
+  let to = (raw_move & 0x3f) as u8;
+  let from = ((raw_move >> 6) & 0x3f) as u8;
+  let promotion = ((raw_move >> 12) & 0x7) as usize;
+
+  let is_king = board.piece_at(from).type_of() == PieceType::K;
+  let to = match (from, to)
+  {
+    (4, 7) | (60, 63) if is_king && !board.is_chess960() => to - 1,
+    (4, 0) | (60, 56) if is_king && !board.is_chess960() => to + 2,
+    _ => to,
+  };
+
+  let square = |sq : u8| format!("{}{}", (b'a' + sq % 8) as char, (b'1' + sq / 8) as char);
+  let promotion = if (1..=4).contains(&promotion) { &"nbrq"[promotion - 1..promotion] } else { "" };
+  format!("{}{}{}", square(from), square(to), promotion)
+}
+
+///
+/// Serialized form of [Book].
+///
+
+#[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct BookState
+{
+  path : PathBuf,
+  #[serde(default)]
+  mode : BookMode,
+}
+
+impl From<Book> for BookState
+{
+  fn from(src : Book) -> Self { Self { path : src.path, mode : src.mode } }
+}
+
+impl From<BookState> for Book
+{
+  fn from(src : BookState) -> Self
+  {
+    Book::open(&src.path)
+      .unwrap_or_else(|_| Self { path : src.path, mode : BookMode::default(), entries : None })
+      .with_mode(src.mode)
+  }
+}
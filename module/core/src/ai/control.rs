@@ -0,0 +1,511 @@
+//!
+//! Control of the search: limits of depth, nodes and time, cancellation, pondering and reports of progress.
+//!
+
+use super::Score;
+use crate::{Move, Tablebase};
+use crate::clock::{SystemTimeSource, TimeSource};
+
+use std::cell::Cell;
+use std::fmt;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+#[cfg(not(target_arch = "wasm32"))]
+use std::sync::mpsc;
+#[cfg(not(target_arch = "wasm32"))]
+use std::thread;
+use std::time::Duration;
+
+///
+/// Most depth of a search in half-moves.
+///
+
+pub const MAX_DEPTH : u16 = 64;
+
+///
+/// Size of the transposition table in megabytes if it is not set.
+///
+
+pub const DEFAULT_HASH_SIZE : usize = 16;
+
+///
+/// Most size of the transposition table in megabytes the frontends accept.
+///
+
+pub const MAX_HASH_SIZE : usize = 4096;
+
+///
+/// Time kept on the clock for communication and making the move.
+///
+
+const MOVE_OVERHEAD : Duration = Duration::from_millis(50);
+
+///
+/// Moves expected until the next time control when the clock doesn't tell.
+///
+
+const MOVES_TO_GO : u32 = 30;
+
+///
+/// Clock of the side to move.
+///
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TimeLeft
+{
+  /// Time left on the clock.
+  pub remaining : Duration,
+  /// Time added after each move.
+  pub increment : Duration,
+  /// Moves until the next time control, `None` if the remaining time is for the rest of the game.
+  pub moves_to_go : Option<u32>,
+}
+
+///
+/// Limits of a search. The search stops at the first limit reached.
+/// Pleco searchers respect only the depth, searches with other limits are done by the alpha-beta searcher.
+///
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Limits
+{
+  /// Most depth in half-moves.
+  pub depth : Option<u16>,
+  /// Most positions to visit.
+  pub nodes : Option<u64>,
+  /// Time to think on the move.
+  pub movetime : Option<Duration>,
+  /// Clock of the side to move, the search takes a share of the remaining time.
+  pub clock : Option<TimeLeft>,
+  /// Search until cancelled, other limits are ignored.
+  pub infinite : bool,
+}
+
+impl Limits
+{
+  ///
+  /// Limits the depth in half-moves.
+  ///
+  pub fn depth(depth : u16) -> Self { Self { depth : Some(depth), ..Self::default() } }
+
+  ///
+  /// Limits the number of visited positions.
+  ///
+  pub fn nodes(nodes : u64) -> Self { Self { nodes : Some(nodes), ..Self::default() } }
+
+  ///
+  /// Limits the time of the search.
+  ///
+  pub fn movetime(movetime : Duration) -> Self { Self { movetime : Some(movetime), ..Self::default() } }
+
+  ///
+  /// Takes a share of the clock of the side to move.
+  ///
+  pub fn clock(remaining : Duration, increment : Duration) -> Self
+  {
+    Self { clock : Some(TimeLeft { remaining, increment, moves_to_go : None }), ..Self::default() }
+  }
+
+  ///
+  /// Searches until cancelled.
+  ///
+  pub fn infinite() -> Self { Self { infinite : true, ..Self::default() } }
+
+  ///
+  /// True if there is no limit and the search is not infinite.
+  ///
+  pub fn is_empty(&self) -> bool { *self == Self::default() }
+
+  ///
+  /// Time the search may take: the move time or a share of the clock, whichever is less. `None` if the time is not limited.
+  ///
+  pub fn time_budget(&self) -> Option<Duration>
+  {
+    if self.infinite
+    {
+      return None;
+    }
+    let share = self.clock.map(|clock|
+    {
+      let share = clock.remaining / clock.moves_to_go.unwrap_or(MOVES_TO_GO).max(1) + clock.increment * 3 / 4;
+      share.min(clock.remaining.saturating_sub(MOVE_OVERHEAD))
+    });
+    match (self.movetime, share)
+    {
+      (Some(movetime), Some(share)) => Some(movetime.min(share)),
+      (movetime, share) => movetime.or(share),
+    }
+  }
+}
+
+///
+/// Handle which stops a running search. Clones stop the same search.
+///
+
+#[derive(Debug, Clone, Default)]
+pub struct CancelHandle
+{
+  cancelled : Arc<AtomicBool>,
+}
+
+impl CancelHandle
+{
+  ///
+  /// Constructs handle of a search which is not cancelled.
+  ///
+  pub fn new() -> Self { Self::default() }
+
+  ///
+  /// Asks the search to stop. The search returns the best move found so far.
+  ///
+  pub fn cancel(&self) { self.cancelled.store(true, Ordering::Relaxed); }
+
+  ///
+  /// True if the search was asked to stop.
+  ///
+  pub fn is_cancelled(&self) -> bool { self.cancelled.load(Ordering::Relaxed) }
+}
+
+///
+/// Handle of a pondering search, which searches the position after the expected reply while the opponent thinks.
+/// Until the ponder hit the search stops only by its depth, after it the limits of nodes and time apply, the time counted from the hit.
+/// Clones switch the same search.
+///
+
+#[derive(Debug, Clone, Default)]
+pub struct PonderHandle
+{
+  hit : Arc<AtomicBool>,
+}
+
+impl PonderHandle
+{
+  ///
+  /// Constructs handle of a search which is pondering.
+  ///
+  pub fn new() -> Self { Self::default() }
+
+  ///
+  /// Tells the search that the opponent played the expected reply, so it continues as a normal search.
+  ///
+  pub fn hit(&self) { self.hit.store(true, Ordering::Relaxed); }
+
+  ///
+  /// True if the opponent played the expected reply.
+  ///
+  pub fn is_hit(&self) -> bool { self.hit.load(Ordering::Relaxed) }
+}
+
+///
+/// Partial result of a search, reported for each line after each finished depth.
+///
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SearchInfo
+{
+  /// Depth of the finished iteration in half-moves.
+  pub depth : u16,
+  /// Rank of the line, 1 for the best line.
+  pub multipv : usize,
+  /// Score for the side to move.
+  pub score : Score,
+  /// Principal variation of the line.
+  pub pv : Vec<Move>,
+  /// Positions visited since the start of the search.
+  pub nodes : u64,
+  /// Time since the start of the search.
+  pub time : Duration,
+}
+
+impl SearchInfo
+{
+  ///
+  /// First move of the principal variation.
+  ///
+  pub fn best_move(&self) -> Move { self.pv.first().copied().unwrap_or_else(Move::null) }
+
+  ///
+  /// Expected reply of the opponent to the best move, the move to ponder on. `None` if the line is too short.
+  ///
+  pub fn ponder_move(&self) -> Option<Move> { self.pv.get(1).copied() }
+}
+
+///
+/// State shared by the algorithm and the caller of a search: limits, cancellation, counter of nodes and reports.
+///
+
+pub struct SearchControl<'a>
+{
+  limits : Limits,
+  cancel : CancelHandle,
+  time_source : SystemTimeSource,
+  budget : Option<Duration>,
+  lines : usize,
+  hash_size : usize,
+  tablebase : Option<Tablebase>,
+  ponder : Option<PonderHandle>,
+  /// Time of the search when the ponder hit was noticed, the limits of time are counted from it.
+  ponder_hit_at : Cell<Option<Duration>>,
+  nodes : u64,
+  report : Box<dyn FnMut(&SearchInfo) + 'a>,
+}
+
+impl<'a> SearchControl<'a>
+{
+  ///
+  /// Starts the time of the search. Reports are dropped.
+  ///
+  pub fn new(limits : Limits, cancel : CancelHandle) -> Self
+  {
+    Self {
+      limits,
+      cancel,
+      time_source : SystemTimeSource::new(),
+      budget : limits.time_budget(),
+      lines : 1,
+      hash_size : DEFAULT_HASH_SIZE,
+      tablebase : None,
+      ponder : None,
+      ponder_hit_at : Cell::new(None),
+      nodes : 0,
+      report : Box::new(|_| ()),
+    }
+  }
+
+  ///
+  /// Passes partial results of the search to the closure.
+  ///
+  pub fn with_report(mut self, report : impl FnMut(&SearchInfo) + 'a) -> Self
+  {
+    self.report = Box::new(report);
+    self
+  }
+
+  ///
+  /// Number of best lines to search, MultiPV. Algorithms which can't search several lines search only the best one.
+  ///
+  pub fn with_lines(mut self, lines : usize) -> Self
+  {
+    self.lines = lines.max(1);
+    self
+  }
+
+  ///
+  /// Size of the transposition table in megabytes.
+  ///
+  pub fn with_hash_size(mut self, megabytes : usize) -> Self
+  {
+    self.hash_size = megabytes.max(1);
+    self
+  }
+
+  ///
+  /// Tablebase which gives exact results of endgames to the search.
+  ///
+  pub fn with_tablebase(mut self, tablebase : Option<Tablebase>) -> Self
+  {
+    self.tablebase = tablebase;
+    self
+  }
+
+  ///
+  /// Makes the search pondering until the ponder hit, `None` for a normal search.
+  ///
+  pub fn with_ponder(mut self, ponder : Option<PonderHandle>) -> Self
+  {
+    self.ponder = ponder;
+    self
+  }
+
+  ///
+  /// Limits of the search.
+  ///
+  pub fn limits(&self) -> &Limits { &self.limits }
+
+  ///
+  /// True if the search ponders and the opponent has not played the expected reply yet.
+  ///
+  pub fn is_pondering(&self) -> bool { self.ponder.as_ref().is_some_and(|ponder| !ponder.is_hit()) }
+
+  ///
+  /// Size of the transposition table in megabytes.
+  ///
+  pub fn hash_size(&self) -> usize { self.hash_size }
+
+  ///
+  /// Tablebase of the search, `None` if endgames are searched as other positions.
+  ///
+  pub fn tablebase(&self) -> Option<&Tablebase> { self.tablebase.as_ref() }
+
+  ///
+  /// Number of best lines to search.
+  ///
+  pub fn lines(&self) -> usize { self.lines }
+
+  ///
+  /// Most depth the algorithm may reach, [MAX_DEPTH] if the depth is not limited.
+  ///
+  pub fn max_depth(&self) -> u16
+  {
+    match self.limits.depth
+    {
+      Some(depth) if !self.limits.infinite => depth.clamp(1, MAX_DEPTH),
+      _ => MAX_DEPTH,
+    }
+  }
+
+  ///
+  /// Counts a visited position.
+  ///
+  pub fn visit(&mut self) { self.nodes += 1; }
+
+  ///
+  /// Counts positions visited outside of the control, by an external engine.
+  ///
+  pub fn add_nodes(&mut self, nodes : u64) { self.nodes += nodes; }
+
+  ///
+  /// Positions visited since the start of the search.
+  ///
+  pub fn nodes(&self) -> u64 { self.nodes }
+
+  ///
+  /// Time since the start of the search.
+  ///
+  pub fn elapsed(&self) -> Duration { self.time_source.now() }
+
+  ///
+  /// True if the search is cancelled, or the limit of nodes or time is reached. The depth is checked by the algorithm.
+  /// Pondering search stops only by cancellation until the ponder hit, after it the time is counted from the hit.
+  ///
+  pub fn should_stop(&self) -> bool
+  {
+    if self.cancel.is_cancelled()
+    {
+      return true;
+    }
+    if self.limits.infinite || self.is_pondering()
+    {
+      return false;
+    }
+    let elapsed = self.elapsed();
+    let thinking = match self.ponder
+    {
+      Some(_) =>
+      {
+        let hit_at = self.ponder_hit_at.get().unwrap_or(elapsed);
+        self.ponder_hit_at.set(Some(hit_at));
+        elapsed.saturating_sub(hit_at)
+      }
+      None => elapsed,
+    };
+    self.limits.nodes.is_some_and(|nodes| self.nodes >= nodes) || self.budget.is_some_and(|budget| thinking >= budget)
+  }
+
+  ///
+  /// Reports a partial result to the caller.
+  ///
+  pub fn report(&mut self, info : &SearchInfo) { (self.report)(info); }
+}
+
+impl fmt::Debug for SearchControl<'_>
+{
+  fn fmt(&self, f : &mut fmt::Formatter<'_>) -> fmt::Result
+  {
+    f.debug_struct("SearchControl")
+      .field("limits", &self.limits)
+      .field("cancel", &self.cancel)
+      .field("budget", &self.budget)
+      .field("lines", &self.lines)
+      .field("hash_size", &self.hash_size)
+      .field("tablebase", &self.tablebase)
+      .field("ponder", &self.ponder)
+      .field("nodes", &self.nodes)
+      .finish_non_exhaustive()
+  }
+}
+
+///
+/// Search running in its own thread, started by [super::Engine::start].
+/// Partial results are received without blocking, the best move is taken when the search finishes or is stopped.
+///
+
+#[cfg(not(target_arch = "wasm32"))]
+#[derive(Debug)]
+pub struct SearchHandle
+{
+  cancel : CancelHandle,
+  ponder : Option<PonderHandle>,
+  infos : mpsc::Receiver<SearchInfo>,
+  thread : thread::JoinHandle<Move>,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl SearchHandle
+{
+  pub(crate) fn new(cancel : CancelHandle, ponder : Option<PonderHandle>, infos : mpsc::Receiver<SearchInfo>, thread : thread::JoinHandle<Move>) -> Self
+  {
+    Self { cancel, ponder, infos, thread }
+  }
+
+  ///
+  /// Handle which stops the search from elsewhere.
+  ///
+  pub fn cancel_handle(&self) -> CancelHandle { self.cancel.clone() }
+
+  ///
+  /// Asks the search to stop without waiting for it.
+  ///
+  pub fn cancel(&self) { self.cancel.cancel(); }
+
+  ///
+  /// Handle which tells the pondering search about the ponder hit from elsewhere, `None` if the search doesn't ponder.
+  ///
+  pub fn ponder_handle(&self) -> Option<PonderHandle> { self.ponder.clone() }
+
+  ///
+  /// Tells the pondering search that the opponent played the expected reply. The search keeps its work and
+  /// stops by its limits from now on. Does nothing if the search doesn't ponder.
+  ///
+  pub fn ponder_hit(&self)
+  {
+    if let Some(ponder) = &self.ponder
+    {
+      ponder.hit();
+    }
+  }
+
+  ///
+  /// True if the search ponders and the opponent has not played the expected reply yet.
+  ///
+  pub fn is_pondering(&self) -> bool { self.ponder.as_ref().is_some_and(|ponder| !ponder.is_hit()) }
+
+  ///
+  /// Partial results reported since the last call. Doesn't block.
+  ///
+  pub fn poll(&self) -> Vec<SearchInfo> { self.infos.try_iter().collect() }
+
+  ///
+  /// Waits for the next partial result. `None` when the search is finished and all results are received.
+  ///
+  pub fn next_info(&self) -> Option<SearchInfo> { self.infos.recv().ok() }
+
+  ///
+  /// True if the search is finished and the best move can be taken without waiting.
+  ///
+  pub fn is_finished(&self) -> bool { self.thread.is_finished() }
+
+  ///
+  /// Waits for the search to finish and returns the best move.
+  ///
+  pub fn wait(self) -> Move { self.thread.join().unwrap_or_else(|panic| std::panic::resume_unwind(panic)) }
+
+  ///
+  /// Stops the search and returns the best move found so far.
+  ///
+  pub fn stop(self) -> Move
+  {
+    self.cancel();
+    self.wait()
+  }
+}
@@ -0,0 +1,551 @@
+//!
+//! Static evaluation of positions. [Evaluator] scores a position in centipawns,
+//! [DefaultEvaluator] sums material, piece-square tables, mobility, king safety and pawn structure.
+//!
+
+use crate::{Board, PieceType, Player};
+
+///
+/// Scores positions for AI algorithms and for explaining positions to players.
+///
+
+pub trait Evaluator : Send + Sync
+{
+  ///
+  /// Short name for reference, used to serialize [Engine](super::Engine).
+  ///
+  fn name(&self) -> &'static str;
+
+  ///
+  /// Score of the position in centipawns for the side to move, positive if the side to move is better.
+  ///
+  fn evaluate(&self, board : &Board) -> i32;
+
+  ///
+  /// Score of the position split into terms. By default the whole score is a single term named after the evaluator.
+  ///
+  fn breakdown(&self, board : &Board) -> Evaluation
+  {
+    let score = self.evaluate(board);
+    let white = if board.current_turn() == Player::White { score } else { -score };
+    Evaluation {
+      turn : board.current_turn(),
+      terms : vec![Term { name : self.name(), white, black : 0 }],
+    }
+  }
+}
+
+impl core::fmt::Debug for dyn Evaluator
+{
+  fn fmt(&self, f : &mut core::fmt::Formatter<'_>) -> core::fmt::Result { write!(f, "ai::Evaluator{{{}}}", self.name()) }
+}
+
+///
+/// Term of evaluation: points of each side in centipawns.
+///
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Term
+{
+  ///
+  /// Name of the term, for example "material".
+  ///
+  pub name : &'static str,
+  ///
+  /// Points of white.
+  ///
+  pub white : i32,
+  ///
+  /// Points of black.
+  ///
+  pub black : i32,
+}
+
+impl Term
+{
+  ///
+  /// Score of the term for white: points of white minus points of black.
+  ///
+  pub fn score(&self) -> i32 { self.white - self.black }
+}
+
+///
+/// Evaluation of a position split into terms.
+///
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Evaluation
+{
+  ///
+  /// Side to move of the position.
+  ///
+  pub turn : Player,
+  ///
+  /// Terms of evaluation.
+  ///
+  pub terms : Vec<Term>,
+}
+
+impl Evaluation
+{
+  ///
+  /// Score for white: sum of scores of terms.
+  ///
+  pub fn white_score(&self) -> i32 { self.terms.iter().map(Term::score).sum() }
+
+  ///
+  /// Score for the side to move, the same as [Evaluator::evaluate].
+  ///
+  pub fn total(&self) -> i32
+  {
+    match self.turn
+    {
+      Player::White => self.white_score(),
+      Player::Black => -self.white_score(),
+    }
+  }
+}
+
+impl std::fmt::Display for Evaluation
+{
+  fn fmt(&self, f : &mut std::fmt::Formatter<'_>) -> std::fmt::Result
+  {
+    writeln!(f, "{:<16}{:>8}{:>8}{:>8}", "term", "white", "black", "score")?;
+    for term in &self.terms
+    {
+      writeln!(f, "{:<16}{:>8}{:>8}{:>8}", term.name, term.white, term.black, term.score())?;
+    }
+    write!(f, "{:<32}{:>8}\n{:<32}{:>8}", "total for white", self.white_score(), format!("total for {}", self.turn), self.total())
+  }
+}
+
+///
+/// Evaluator of pleco, the evaluation used before [DefaultEvaluator].
+///
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PlecoEvaluator;
+
+impl Evaluator for PlecoEvaluator
+{
+  fn name(&self) -> &'static str { "pleco" }
+
+  fn evaluate(&self, board : &Board) -> i32 { pleco::tools::eval::Eval::eval_low(&board.pleco_board) }
+}
+
+///
+/// Hand-crafted evaluation: material, piece-square tables, mobility, king safety and pawn structure.
+/// Piece-square tables and king safety are tapered between the middlegame and the endgame by the material left.
+///
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DefaultEvaluator;
+
+impl Evaluator for DefaultEvaluator
+{
+  fn name(&self) -> &'static str { "default" }
+
+  fn evaluate(&self, board : &Board) -> i32 { self.breakdown(board).total() }
+
+  fn breakdown(&self, board : &Board) -> Evaluation
+  {
+    let position = Position::new(board);
+    let phase = position.phase();
+    let term = |name : &'static str, f : &dyn Fn(Player) -> i32| Term { name, white : f(Player::White), black : f(Player::Black) };
+
+    Evaluation {
+      turn : board.current_turn(),
+      terms : vec![
+        term("material", &|player| position.material(player)),
+        term("piece-square", &|player| position.piece_square(player, phase)),
+        term("mobility", &|player| position.mobility(player)),
+        term("king safety", &|player| position.king_safety(player) * phase / MAX_PHASE),
+        term("pawn structure", &|player| position.pawn_structure(player)),
+      ],
+    }
+  }
+}
+
+///
+/// Phase of the game with all pieces on the board. Knights and bishops count 1, rooks 2, queens 4.
+///
+
+const MAX_PHASE : i32 = 24;
+
+const PAWN_VALUE : i32 = 100;
+const KNIGHT_VALUE : i32 = 320;
+const BISHOP_VALUE : i32 = 330;
+const ROOK_VALUE : i32 = 500;
+const QUEEN_VALUE : i32 = 900;
+
+const DOUBLED_PAWN : i32 = -12;
+const ISOLATED_PAWN : i32 = -12;
+
+///
+/// Bonus of a passed pawn by its rank, counted from the side of its owner.
+///
+
+const PASSED_PAWN : [i32; 8] = [0, 5, 10, 20, 35, 60, 100, 0];
+
+const SHIELD_PAWN_NEAR : i32 = 10;
+const SHIELD_PAWN_FAR : i32 = 5;
+const SHIELD_MISSING : i32 = -15;
+const KING_OPEN_FILE : i32 = -10;
+const KING_ZONE_ATTACK : i32 = -8;
+
+const KNIGHT_STEPS : [(i32, i32); 8] = [(1, 2), (2, 1), (2, -1), (1, -2), (-1, -2), (-2, -1), (-2, 1), (-1, 2)];
+const KING_STEPS : [(i32, i32); 8] = [(1, 0), (1, 1), (0, 1), (-1, 1), (-1, 0), (-1, -1), (0, -1), (1, -1)];
+const BISHOP_RAYS : [(i32, i32); 4] = [(1, 1), (-1, 1), (-1, -1), (1, -1)];
+const ROOK_RAYS : [(i32, i32); 4] = [(1, 0), (0, 1), (-1, 0), (0, -1)];
+
+// Piece-square tables from white's side, the 8th rank first, as in the Simplified Evaluation Function of Tomasz Michniewski.
+
+#[rustfmt::skip]
+const PAWN_TABLE : [i32; 64] = [
+   0,   0,   0,   0,   0,   0,   0,   0,
+  50,  50,  50,  50,  50,  50,  50,  50,
+  10,  10,  20,  30,  30,  20,  10,  10,
+   5,   5,  10,  25,  25,  10,   5,   5,
+   0,   0,   0,  20,  20,   0,   0,   0,
+   5,  -5, -10,   0,   0, -10,  -5,   5,
+   5,  10,  10, -20, -20,  10,  10,   5,
+   0,   0,   0,   0,   0,   0,   0,   0,
+];
+
+#[rustfmt::skip]
+const KNIGHT_TABLE : [i32; 64] = [
+  -50, -40, -30, -30, -30, -30, -40, -50,
+  -40, -20,   0,   0,   0,   0, -20, -40,
+  -30,   0,  10,  15,  15,  10,   0, -30,
+  -30,   5,  15,  20,  20,  15,   5, -30,
+  -30,   0,  15,  20,  20,  15,   0, -30,
+  -30,   5,  10,  15,  15,  10,   5, -30,
+  -40, -20,   0,   5,   5,   0, -20, -40,
+  -50, -40, -30, -30, -30, -30, -40, -50,
+];
+
+#[rustfmt::skip]
+const BISHOP_TABLE : [i32; 64] = [
+  -20, -10, -10, -10, -10, -10, -10, -20,
+  -10,   0,   0,   0,   0,   0,   0, -10,
+  -10,   0,   5,  10,  10,   5,   0, -10,
+  -10,   5,   5,  10,  10,   5,   5, -10,
+  -10,   0,  10,  10,  10,  10,   0, -10,
+  -10,  10,  10,  10,  10,  10,  10, -10,
+  -10,   5,   0,   0,   0,   0,   5, -10,
+  -20, -10, -10, -10, -10, -10, -10, -20,
+];
+
+#[rustfmt::skip]
+const ROOK_TABLE : [i32; 64] = [
+   0,   0,   0,   0,   0,   0,   0,   0,
+   5,  10,  10,  10,  10,  10,  10,   5,
+  -5,   0,   0,   0,   0,   0,   0,  -5,
+  -5,   0,   0,   0,   0,   0,   0,  -5,
+  -5,   0,   0,   0,   0,   0,   0,  -5,
+  -5,   0,   0,   0,   0,   0,   0,  -5,
+  -5,   0,   0,   0,   0,   0,   0,  -5,
+   0,   0,   0,   5,   5,   0,   0,   0,
+];
+
+#[rustfmt::skip]
+const QUEEN_TABLE : [i32; 64] = [
+  -20, -10, -10,  -5,  -5, -10, -10, -20,
+  -10,   0,   0,   0,   0,   0,   0, -10,
+  -10,   0,   5,   5,   5,   5,   0, -10,
+   -5,   0,   5,   5,   5,   5,   0,  -5,
+    0,   0,   5,   5,   5,   5,   0,  -5,
+  -10,   5,   5,   5,   5,   5,   0, -10,
+  -10,   0,   5,   0,   0,   0,   0, -10,
+  -20, -10, -10,  -5,  -5, -10, -10, -20,
+];
+
+#[rustfmt::skip]
+const KING_MIDDLEGAME_TABLE : [i32; 64] = [
+  -30, -40, -40, -50, -50, -40, -40, -30,
+  -30, -40, -40, -50, -50, -40, -40, -30,
+  -30, -40, -40, -50, -50, -40, -40, -30,
+  -30, -40, -40, -50, -50, -40, -40, -30,
+  -20, -30, -30, -40, -40, -30, -30, -20,
+  -10, -20, -20, -20, -20, -20, -20, -10,
+   20,  20,   0,   0,   0,   0,  20,  20,
+   20,  30,  10,   0,   0,  10,  30,  20,
+];
+
+#[rustfmt::skip]
+const KING_ENDGAME_TABLE : [i32; 64] = [
+  -50, -40, -30, -20, -20, -30, -40, -50,
+  -30, -20, -10,   0,   0, -10, -20, -30,
+  -30, -10,  20,  30,  30,  20, -10, -30,
+  -30, -10,  30,  40,  40,  30, -10, -30,
+  -30, -10,  30,  40,  40,  30, -10, -30,
+  -30, -10,  20,  30,  30,  20, -10, -30,
+  -30, -30,   0,   0,   0,   0, -30, -30,
+  -50, -30, -30, -30, -30, -30, -30, -50,
+];
+
+///
+/// Pieces of the board by squares, a1 is 0 and h8 is 63.
+///
+
+struct Position
+{
+  pieces : [Option<(Player, PieceType)>; 64],
+}
+
+impl Position
+{
+  fn new(board : &Board) -> Self
+  {
+    let mut pieces = [None; 64];
+    for (sq, piece) in pieces.iter_mut().enumerate()
+    {
+      let p = board.piece_at(sq as u8);
+      *piece = p.player().map(|player| (player, p.type_of()));
+    }
+    Self { pieces }
+  }
+
+  fn pieces_of(&self, player : Player) -> impl Iterator<Item = (usize, PieceType)> + '_
+  {
+    self.pieces.iter().enumerate().filter_map(move |(sq, piece)| match piece
+    {
+      Some((owner, kind)) if *owner == player => Some((sq, *kind)),
+      _ => None,
+    })
+  }
+
+  fn has(&self, sq : usize, player : Player, kind : PieceType) -> bool { self.pieces[sq] == Some((player, kind)) }
+
+  fn phase(&self) -> i32
+  {
+    let phase : i32 = [Player::White, Player::Black]
+      .iter()
+      .flat_map(|player| self.pieces_of(*player))
+      .map(|(_, kind)| match kind
+      {
+        PieceType::N | PieceType::B => 1,
+        PieceType::R => 2,
+        PieceType::Q => 4,
+        _ => 0,
+      })
+      .sum();
+    phase.min(MAX_PHASE)
+  }
+
+  fn material(&self, player : Player) -> i32
+  {
+    self
+      .pieces_of(player)
+      .map(|(_, kind)| match kind
+      {
+        PieceType::P => PAWN_VALUE,
+        PieceType::N => KNIGHT_VALUE,
+        PieceType::B => BISHOP_VALUE,
+        PieceType::R => ROOK_VALUE,
+        PieceType::Q => QUEEN_VALUE,
+        _ => 0,
+      })
+      .sum()
+  }
+
+  fn piece_square(&self, player : Player, phase : i32) -> i32
+  {
+    self
+      .pieces_of(player)
+      .map(|(sq, kind)|
+      {
+        let index = table_index(sq, player);
+        match kind
+        {
+          PieceType::P => PAWN_TABLE[index],
+          PieceType::N => KNIGHT_TABLE[index],
+          PieceType::B => BISHOP_TABLE[index],
+          PieceType::R => ROOK_TABLE[index],
+          PieceType::Q => QUEEN_TABLE[index],
+          PieceType::K => (KING_MIDDLEGAME_TABLE[index] * phase + KING_ENDGAME_TABLE[index] * (MAX_PHASE - phase)) / MAX_PHASE,
+          _ => 0,
+        }
+      })
+      .sum()
+  }
+
+  ///
+  /// Squares attacked by pieces other than pawns and kings which are not occupied by own pieces.
+  ///
+  fn mobility(&self, player : Player) -> i32
+  {
+    self
+      .pieces_of(player)
+      .map(|(sq, kind)|
+      {
+        let weight = match kind
+        {
+          PieceType::N => 4,
+          PieceType::B => 5,
+          PieceType::R => 3,
+          PieceType::Q => 1,
+          _ => return 0,
+        };
+        let free = self.attacks(sq, kind).into_iter().filter(|target| self.pieces[*target].is_none_or(|(owner, _)| owner != player));
+        weight * free.count() as i32
+      })
+      .sum()
+  }
+
+  ///
+  /// Pawn shield in front of the king, open files near the king and attacks of enemy pieces on squares around the king.
+  ///
+  fn king_safety(&self, player : Player) -> i32
+  {
+    let king = match self.pieces_of(player).find(|(_, kind)| *kind == PieceType::K)
+    {
+      Some((sq, _)) => sq,
+      None => return 0,
+    };
+    let (file, rank) = ((king % 8) as i32, (king / 8) as i32);
+    let forward = if player == Player::White { 1 } else { -1 };
+
+    let mut safety = 0;
+    for f in (file - 1..=file + 1).filter(|f| (0..8).contains(f))
+    {
+      let near = on_board(f, rank + forward).is_some_and(|sq| self.has(sq, player, PieceType::P));
+      let far = on_board(f, rank + 2 * forward).is_some_and(|sq| self.has(sq, player, PieceType::P));
+      safety += if near
+      {
+        SHIELD_PAWN_NEAR
+      }
+      else if far
+      {
+        SHIELD_PAWN_FAR
+      }
+      else
+      {
+        SHIELD_MISSING
+      };
+
+      let open = (0..8).all(|r| self.pieces[(r * 8 + f) as usize].is_none_or(|(_, kind)| kind != PieceType::P));
+      if open
+      {
+        safety += KING_OPEN_FILE;
+      }
+    }
+
+    let zone : Vec<usize> = std::iter::once(king)
+      .chain(KING_STEPS.iter().filter_map(|(df, dr)| on_board(file + df, rank + dr)))
+      .collect();
+    let attacks : usize = self
+      .pieces_of(player.other_player())
+      .filter(|(_, kind)| matches!(kind, PieceType::N | PieceType::B | PieceType::R | PieceType::Q))
+      .map(|(sq, kind)| self.attacks(sq, kind).iter().filter(|target| zone.contains(*target)).count())
+      .sum();
+    safety + KING_ZONE_ATTACK * attacks as i32
+  }
+
+  ///
+  /// Penalties for doubled and isolated pawns, bonuses for passed pawns.
+  ///
+  fn pawn_structure(&self, player : Player) -> i32
+  {
+    let mut files = [0; 8];
+    let pawns : Vec<usize> = self.pieces_of(player).filter(|(_, kind)| *kind == PieceType::P).map(|(sq, _)| sq).collect();
+    for sq in &pawns
+    {
+      files[sq % 8] += 1;
+    }
+
+    let mut score = 0;
+    for (file, count) in files.iter().enumerate()
+    {
+      if *count > 1
+      {
+        score += DOUBLED_PAWN * (count - 1);
+      }
+      let neighbours = (file > 0 && files[file - 1] > 0) || (file < 7 && files[file + 1] > 0);
+      if *count > 0 && !neighbours
+      {
+        score += ISOLATED_PAWN * count;
+      }
+    }
+
+    let enemy = player.other_player();
+    for sq in pawns
+    {
+      let (file, rank) = ((sq % 8) as i32, (sq / 8) as i32);
+      let ahead : Vec<i32> = if player == Player::White { (rank + 1..8).collect() } else { (0..rank).collect() };
+      let blocked = ahead.iter().any(|r|
+      {
+        (file - 1..=file + 1).filter_map(|f| on_board(f, *r)).any(|target| self.has(target, enemy, PieceType::P))
+      });
+      if !blocked
+      {
+        let relative_rank = if player == Player::White { rank } else { 7 - rank };
+        score += PASSED_PAWN[relative_rank as usize];
+      }
+    }
+    score
+  }
+
+  ///
+  /// Squares attacked by the piece.
+  ///
+  fn attacks(&self, sq : usize, kind : PieceType) -> Vec<usize>
+  {
+    let (file, rank) = ((sq % 8) as i32, (sq / 8) as i32);
+    let steps = |steps : &[(i32, i32)]| -> Vec<usize> { steps.iter().filter_map(|(df, dr)| on_board(file + df, rank + dr)).collect() };
+    let rays = |rays : &[(i32, i32)]| -> Vec<usize>
+    {
+      let mut targets = Vec::new();
+      for (df, dr) in rays
+      {
+        let (mut f, mut r) = (file + df, rank + dr);
+        while let Some(target) = on_board(f, r)
+        {
+          targets.push(target);
+          if self.pieces[target].is_some()
+          {
+            break;
+          }
+          f += df;
+          r += dr;
+        }
+      }
+      targets
+    };
+
+    match kind
+    {
+      PieceType::N => steps(&KNIGHT_STEPS),
+      PieceType::K => steps(&KING_STEPS),
+      PieceType::B => rays(&BISHOP_RAYS),
+      PieceType::R => rays(&ROOK_RAYS),
+      PieceType::Q => [rays(&BISHOP_RAYS), rays(&ROOK_RAYS)].concat(),
+      _ => Vec::new(),
+    }
+  }
+}
+
+fn on_board(file : i32, rank : i32) -> Option<usize>
+{
+  if (0..8).contains(&file) && (0..8).contains(&rank)
+  {
+    Some((rank * 8 + file) as usize)
+  }
+  else
+  {
+    None
+  }
+}
+
+///
+/// Index in piece-square tables, which are written from white's side with the 8th rank first.
+///
+
+fn table_index(sq : usize, player : Player) -> usize
+{
+  match player
+  {
+    Player::White => (7 - sq / 8) * 8 + sq % 8,
+    Player::Black => sq,
+  }
+}
@@ -0,0 +1,464 @@
+//!
+//! External engines: a local executable speaking Universal Chess Interface ( UCI ) protocol runs as a child process
+//! and searches for the [Engine](super::Engine). The engine is started once and reused by all searches.
+//!
+
+use super::{greedy_move, Algorithm, Evaluator, Limits, Score, SearchControl, SearchInfo};
+use crate::{Board, Error, Move, Player, UCI};
+
+use std::io::{BufRead, BufReader, Write};
+use std::process::{Child, ChildStdin, Command, Stdio};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::sync::Mutex;
+use std::time::Duration;
+
+///
+/// Prefix of algorithm names which start an external engine, "uci:<program> [arguments]".
+///
+
+pub const UCI_PREFIX : &str = "uci:";
+
+///
+/// Time the engine has to answer the handshake and `isready`.
+///
+
+const ANSWER_TIMEOUT : Duration = Duration::from_secs(10);
+
+///
+/// How often limits and cancellation are checked while the engine thinks.
+///
+
+const POLL_INTERVAL : Duration = Duration::from_millis(5);
+
+///
+/// Option declared by the engine in the handshake.
+///
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UciOption
+{
+  /// Name of the option.
+  pub name : String,
+  /// Type of the option: check, spin, combo, button or string.
+  pub kind : String,
+  /// Default value, `None` if the engine doesn't declare it.
+  pub default : Option<String>,
+}
+
+///
+/// External UCI engine. Limits of the search are passed to the engine with `go`, cancellation sends `stop`.
+/// Hash, MultiPV and UCI_Chess960 follow the search if the engine has these options, other options are set by [UciEngine::set_option].
+/// The engine is serialized by its command, options are not serialized. The deserialized engine is not started.
+/// If the engine fails during a search, the move is picked by the evaluator as the greedy algorithm does.
+///
+
+#[derive(Debug)]
+pub struct UciEngine
+{
+  command : Vec<String>,
+  name : String,
+  author : String,
+  options : Vec<UciOption>,
+  process : Mutex<Process>,
+}
+
+///
+/// Running engine and the options of the last search.
+///
+
+#[derive(Debug)]
+struct Process
+{
+  child : Child,
+  stdin : ChildStdin,
+  lines : Receiver<String>,
+  hash_size : Option<usize>,
+  multipv : usize,
+  chess960 : bool,
+}
+
+impl UciEngine
+{
+  ///
+  /// Starts the program with the arguments and waits for the handshake.
+  ///
+  pub fn start(program : &str, args : &[&str]) -> Result<Self, Error>
+  {
+    let mut child = Command::new(program).args(args).stdin(Stdio::piped()).stdout(Stdio::piped()).stderr(Stdio::null()).spawn()?;
+    let stdin = child.stdin.take().expect("stdin of the engine is piped");
+    let stdout = child.stdout.take().expect("stdout of the engine is piped");
+    let (sender, lines) = mpsc::channel();
+    std::thread::spawn(move ||
+    {
+      for line in BufReader::new(stdout).lines()
+      {
+        match line
+        {
+          Ok(line) => if sender.send(line).is_err() { break },
+          Err(_) => break,
+        }
+      }
+    });
+    let mut process = Process { child, stdin, lines, hash_size : None, multipv : 1, chess960 : false };
+
+    let mut name = program.to_string();
+    let mut author = String::new();
+    let mut options = Vec::new();
+    process.send("uci")?;
+    loop
+    {
+      let line = process.receive(ANSWER_TIMEOUT)?;
+      let tokens : Vec<&str> = line.split_whitespace().collect();
+      match tokens.as_slice()
+      {
+        ["id", "name", rest @ ..] => name = rest.join(" "),
+        ["id", "author", rest @ ..] => author = rest.join(" "),
+        ["option", rest @ ..] => options.extend(parse_option(rest)),
+        ["uciok"] => break,
+        _ => (),
+      }
+    }
+    process.ready()?;
+
+    let mut command = vec![program.to_string()];
+    command.extend(args.iter().map(|arg| arg.to_string()));
+    Ok(Self { command, name, author, options, process : Mutex::new(process) })
+  }
+
+  ///
+  /// Starts the engine by command line: the program and its arguments separated by whitespace.
+  ///
+  pub fn from_command(command : &str) -> Result<Self, Error>
+  {
+    let words : Vec<&str> = command.split_whitespace().collect();
+    match words.split_first()
+    {
+      Some((program, args)) => Self::start(program, args),
+      None => Err(Error::Engine(String::from("the command of the engine is empty"))),
+    }
+  }
+
+  ///
+  /// Program and arguments the engine was started with.
+  ///
+  pub fn command(&self) -> &[String] { &self.command }
+
+  ///
+  /// Name of the engine from the handshake, the program if the engine has no name.
+  ///
+  pub fn engine_name(&self) -> &str { &self.name }
+
+  ///
+  /// Author of the engine from the handshake, empty if the engine has no author.
+  ///
+  pub fn author(&self) -> &str { &self.author }
+
+  ///
+  /// Options declared by the engine.
+  ///
+  pub fn options(&self) -> &[UciOption] { &self.options }
+
+  ///
+  /// Sets option of the engine and waits until the engine is ready. Names are not case sensitive.
+  /// Option which the engine didn't declare is an error.
+  ///
+  pub fn set_option(&self, name : &str, value : &str) -> Result<(), Error>
+  {
+    let option = self.option(name).ok_or_else(|| Error::Engine(format!("the engine has no option '{}'", name)))?;
+    let mut process = self.process.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    process.set_option(&option.name, value)?;
+    process.ready()
+  }
+
+  fn option(&self, name : &str) -> Option<&UciOption> { self.options.iter().find(|option| option.name.eq_ignore_ascii_case(name)) }
+
+  fn search(&self, process : &mut Process, board : &Board, control : &mut SearchControl<'_>) -> Result<Move, Error>
+  {
+    if let Some(option) = self.option("Hash").filter(|_| process.hash_size != Some(control.hash_size()))
+    {
+      process.set_option(&option.name, &control.hash_size().to_string())?;
+      process.hash_size = Some(control.hash_size());
+    }
+    if let Some(option) = self.option("MultiPV").filter(|_| process.multipv != control.lines())
+    {
+      process.set_option(&option.name, &control.lines().to_string())?;
+      process.multipv = control.lines();
+    }
+    if let Some(option) = self.option("UCI_Chess960").filter(|_| process.chess960 != board.is_chess960())
+    {
+      process.set_option(&option.name, &board.is_chess960().to_string())?;
+      process.chess960 = board.is_chess960();
+    }
+    process.ready()?;
+
+    process.send(&format!("position fen {}", board.to_fen().as_str()))?;
+    process.send(&go_command(control.limits(), board.current_turn()))?;
+    let mut stopping = false;
+    loop
+    {
+      if !stopping && control.should_stop()
+      {
+        process.send("stop")?;
+        stopping = true;
+      }
+      let line = match process.lines.recv_timeout(POLL_INTERVAL)
+      {
+        Ok(line) => line,
+        Err(RecvTimeoutError::Timeout) => continue,
+        Err(RecvTimeoutError::Disconnected) => return Err(Error::Engine(String::from("the engine exited"))),
+      };
+      let tokens : Vec<&str> = line.split_whitespace().collect();
+      match tokens.as_slice()
+      {
+        ["info", ..] =>
+        {
+          if let Some(info) = parse_info(board, &tokens)
+          {
+            control.add_nodes(info.nodes.saturating_sub(control.nodes()));
+            control.report(&info);
+          }
+        }
+        ["bestmove", "(none)" | "0000", ..] => return Ok(Move::null()),
+        ["bestmove", m, ..] =>
+        {
+          return board.move_from_uci(UCI::from(*m)).map_err(|_| Error::Engine(format!("the engine played illegal move '{}'", m)));
+        }
+        _ => (),
+      }
+    }
+  }
+}
+
+impl Algorithm for UciEngine
+{
+  fn name(&self) -> &'static str { "UCI Engine" }
+
+  fn short_name(&self) -> &'static str { "uci" }
+
+  fn id(&self) -> String { format!("{}{}", UCI_PREFIX, self.command.join(" ")) }
+
+  fn best_move(&self, board : Board, evaluator : &dyn Evaluator, control : &mut SearchControl<'_>) -> Move
+  {
+    let mut process = self.process.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    self.search(&mut process, &board, control).unwrap_or_else(|_| greedy_move(&board, evaluator))
+  }
+}
+
+///
+/// External engine which was deserialized and is not started yet. Deserialization never starts processes,
+/// the engine is started by [Engine::start_external](super::Engine::start_external).
+/// Until then moves are picked by the evaluator as the greedy algorithm does.
+///
+
+#[derive(Debug)]
+pub(crate) struct UnstartedEngine
+{
+  command : String,
+}
+
+impl UnstartedEngine
+{
+  pub(crate) fn new(command : &str) -> Self { Self { command : command.to_string() } }
+}
+
+impl Algorithm for UnstartedEngine
+{
+  fn name(&self) -> &'static str { "UCI Engine" }
+
+  fn short_name(&self) -> &'static str { "uci" }
+
+  fn id(&self) -> String { format!("{}{}", UCI_PREFIX, self.command) }
+
+  fn unstarted_command(&self) -> Option<&str> { Some(&self.command) }
+
+  fn best_move(&self, board : Board, evaluator : &dyn Evaluator, _control : &mut SearchControl<'_>) -> Move { greedy_move(&board, evaluator) }
+}
+
+impl Process
+{
+  fn send(&mut self, line : &str) -> Result<(), Error>
+  {
+    writeln!(self.stdin, "{}", line)?;
+    self.stdin.flush()?;
+    Ok(())
+  }
+
+  fn receive(&mut self, timeout : Duration) -> Result<String, Error>
+  {
+    self.lines.recv_timeout(timeout).map_err(|error| match error
+    {
+      RecvTimeoutError::Timeout => Error::Engine(String::from("the engine doesn't answer")),
+      RecvTimeoutError::Disconnected => Error::Engine(String::from("the engine exited")),
+    })
+  }
+
+  fn set_option(&mut self, name : &str, value : &str) -> Result<(), Error>
+  {
+    if value.is_empty()
+    {
+      self.send(&format!("setoption name {}", name))
+    }
+    else
+    {
+      self.send(&format!("setoption name {} value {}", name, value))
+    }
+  }
+
+  ///
+  /// Sends `isready` and skips output until `readyok`.
+  ///
+  fn ready(&mut self) -> Result<(), Error>
+  {
+    self.send("isready")?;
+    while self.receive(ANSWER_TIMEOUT)?.trim() != "readyok" {}
+    Ok(())
+  }
+}
+
+///
+/// Asks the engine to quit and kills it if it doesn't.
+///
+
+impl Drop for Process
+{
+  fn drop(&mut self)
+  {
+    let _ = self.send("quit");
+    for _ in 0..20
+    {
+      if let Ok(Some(_)) = self.child.try_wait()
+      {
+        return;
+      }
+      std::thread::sleep(POLL_INTERVAL);
+    }
+    let _ = self.child.kill();
+    let _ = self.child.wait();
+  }
+}
+
+///
+/// `go` with the limits. The clock is given to the side to move only.
+///
+
+fn go_command(limits : &Limits, turn : Player) -> String
+{
+  if limits.infinite
+  {
+    return String::from("go infinite");
+  }
+  let mut command = String::from("go");
+  if let Some(depth) = limits.depth
+  {
+    command.push_str(&format!(" depth {}", depth));
+  }
+  if let Some(nodes) = limits.nodes
+  {
+    command.push_str(&format!(" nodes {}", nodes));
+  }
+  if let Some(movetime) = limits.movetime
+  {
+    command.push_str(&format!(" movetime {}", movetime.as_millis()));
+  }
+  if let Some(clock) = limits.clock
+  {
+    let (time, increment) = if turn == Player::White { ("wtime", "winc") } else { ("btime", "binc") };
+    command.push_str(&format!(" {} {} {} {}", time, clock.remaining.as_millis(), increment, clock.increment.as_millis()));
+    if let Some(moves_to_go) = clock.moves_to_go
+    {
+      command.push_str(&format!(" movestogo {}", moves_to_go));
+    }
+  }
+  command
+}
+
+///
+/// `option name <name> type <kind> [default <value>] [min <min>] [max <max>] [var <value>]...`
+///
+
+fn parse_option(tokens : &[&str]) -> Option<UciOption>
+{
+  let keyword = |token : &&str| matches!(*token, "type" | "default" | "min" | "max" | "var");
+  let type_at = tokens.iter().position(|token| *token == "type")?;
+  let name = tokens.get(1..type_at)?.join(" ");
+  let kind = tokens.get(type_at + 1)?.to_string();
+  let default = tokens.iter().position(|token| *token == "default").map(|default_at|
+  {
+    let rest = &tokens[default_at + 1..];
+    let end = rest.iter().position(keyword).unwrap_or(rest.len());
+    rest[..end].join(" ")
+  });
+  if tokens.first() != Some(&"name") || name.is_empty()
+  {
+    return None;
+  }
+  Some(UciOption { name, kind, default })
+}
+
+///
+/// Information of a finished line: `info depth <plies> [multipv <rank>] score cp <centipawns>|mate <moves> [nodes <count>] [time <ms>] pv <moves>`.
+/// Bounds, strings and lines without score or principal variation are skipped.
+///
+
+fn parse_info(board : &Board, tokens : &[&str]) -> Option<SearchInfo>
+{
+  let (mut depth, mut multipv, mut nodes, mut time) = (0, 1, 0, 0);
+  let mut score = None;
+  let mut pv = Vec::new();
+  for (i, token) in tokens.iter().enumerate()
+  {
+    let number = || tokens.get(i + 1).and_then(|value| value.parse::<u64>().ok()).unwrap_or(0);
+    match *token
+    {
+      "depth" => depth = number() as u16,
+      "multipv" => multipv = (number() as usize).max(1),
+      "nodes" => nodes = number(),
+      "time" => time = number(),
+      "score" =>
+      {
+        let value = tokens.get(i + 2).and_then(|value| value.parse::<i32>().ok());
+        score = match tokens.get(i + 1)
+        {
+          Some(&"cp") => value.map(Score::Centipawns),
+          Some(&"mate") => value.map(Score::Mate),
+          _ => None,
+        };
+      }
+      "lowerbound" | "upperbound" | "string" => return None,
+      "pv" =>
+      {
+        pv = pv_moves(board, &tokens[i + 1..]);
+        break;
+      }
+      _ => (),
+    }
+  }
+  if pv.is_empty()
+  {
+    return None;
+  }
+  Some(SearchInfo { depth, multipv, score : score?, pv, nodes, time : Duration::from_millis(time) })
+}
+
+///
+/// Moves of the principal variation played from the board. Stops at the first move which is not legal.
+///
+
+fn pv_moves(board : &Board, moves : &[&str]) -> Vec<Move>
+{
+  let mut board = board.clone();
+  let mut pv = Vec::new();
+  for m in moves
+  {
+    match board.move_from_uci(UCI::from(*m))
+    {
+      Ok(m) =>
+      {
+        board.apply(m);
+        pv.push(m);
+      }
+      Err(_) => break,
+    }
+  }
+  pv
+}
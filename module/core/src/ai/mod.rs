@@ -0,0 +1,656 @@
+//!
+//! Implement ai for the chess game.
+//! Wraps pleco bots in dyn traits. Serializes algorithms and evaluators.
+//! Searches are limited by depth, nodes or time, can be cancelled and run in their own thread.
+//! Analysis reports scores, principal variations and several best lines.
+//! Engines may play the first moves from a Polyglot opening book and endgames from Syzygy tablebases.
+//! The algorithm may be an external UCI engine running as a child process.
+//! Skill levels weaken engines with limited search and deliberate mistakes.
+//! Engines may ponder: search the expected reply while the opponent thinks.
+//!
+
+mod analysis;
+mod book;
+mod control;
+mod evaluation;
+#[cfg(not(target_arch = "wasm32"))]
+mod external;
+mod search;
+mod skill;
+
+#[cfg(not(target_arch = "wasm32"))]
+pub use control::SearchHandle;
+#[cfg(not(target_arch = "wasm32"))]
+pub use external::{UciEngine, UciOption, UCI_PREFIX};
+pub use analysis::{Analysis, Line, Score};
+pub use book::{Book, BookEntry, BookMode};
+pub use control::{CancelHandle, Limits, PonderHandle, SearchControl, SearchInfo, TimeLeft, DEFAULT_HASH_SIZE, MAX_DEPTH, MAX_HASH_SIZE};
+pub use evaluation::{DefaultEvaluator, Evaluation, Evaluator, PlecoEvaluator, Term};
+pub use skill::{Skill, MAX_SKILL_LEVEL, MIN_SKILL_LEVEL};
+
+use super::{Board, Move, Tablebase};
+
+use pleco::tools::Searcher;
+use serde::{Serialize, Deserialize, Serializer, Deserializer};
+use std::sync::Arc;
+
+///
+/// Depth of engines created without depth, and of pleco searchers when the limits don't have depth.
+///
+
+pub const DEFAULT_DEPTH : u16 = 5;
+
+///
+/// AI algorithm. Implements gameplay strategy.
+///
+
+pub trait Algorithm: Send + Sync
+{
+  ///
+  /// Pleco bot name
+  ///
+  fn name(&self) -> &'static str;
+
+  ///
+  /// Short name for reference
+  ///
+  fn short_name(&self) -> &'static str;
+
+  ///
+  /// Name which creates the algorithm again with [Engine::new], the short name for built-in algorithms.
+  ///
+  fn id(&self) -> String { self.short_name().to_string() }
+
+  ///
+  /// Command of an external engine which is not started yet, see [Engine::start_external].
+  ///
+  fn unstarted_command(&self) -> Option<&str> { None }
+
+  ///
+  /// Searches the best move within the limits of the control. Algorithms with own evaluation ignore the evaluator.
+  /// Returns null move if there are no legal moves.
+  ///
+  fn best_move(&self, board : Board, evaluator : &dyn Evaluator, control : &mut SearchControl<'_>) -> Move;
+}
+
+macro_rules! implement_algorithm_trait {
+  ($name:ident, $searcher:ty, $short_name:expr) => {
+    struct $name;
+    impl Algorithm for $name
+    {
+      fn name(&self) -> &'static str { <$searcher>::name() }
+
+      fn short_name(&self) -> &'static str { $short_name }
+
+      fn best_move(&self, board : Board, evaluator : &dyn Evaluator, control : &mut SearchControl<'_>) -> Move
+      {
+        pleco_best_move(board, evaluator, control, <$searcher>::best_move)
+      }
+    }
+  };
+}
+
+///
+/// Legal moves as lines of one move scored by the evaluator, best first, checkmate is the best.
+/// Skill levels pick among them if the algorithm doesn't report lines.
+///
+
+fn evaluated_lines(board : &Board, evaluator : &dyn Evaluator) -> Vec<Line>
+{
+  let mut lines : Vec<Line> = board
+    .legal_moves()
+    .into_iter()
+    .map(|m|
+    {
+      let mut after = board.clone();
+      after.apply(m);
+      let score = if after.is_checkmate()
+      {
+        Score::Mate(1)
+      }
+      else if after.is_stalemate()
+      {
+        Score::Centipawns(0)
+      }
+      else
+      {
+        Score::Centipawns(-evaluator.evaluate(&after))
+      };
+      Line { score, pv : vec![m] }
+    })
+    .collect();
+  lines.sort_by_key(|line| match line.score
+  {
+    Score::Mate(_) => i32::MIN,
+    Score::Centipawns(centipawns) => -centipawns,
+  });
+  lines
+}
+
+///
+/// How often cancellation is checked while a pleco searcher thinks.
+///
+
+#[cfg(not(target_arch = "wasm32"))]
+const POLL_INTERVAL : std::time::Duration = std::time::Duration::from_millis(5);
+
+///
+/// Move of a pleco searcher, which respects only the depth. Searches limited by nodes or time and infinite searches
+/// are done by the alpha-beta searcher instead. The pleco searcher thinks in its own thread, so the search can be cancelled:
+/// the searcher is abandoned and the move is picked by the evaluator as the greedy algorithm does.
+///
+
+fn pleco_best_move(board : Board, evaluator : &dyn Evaluator, control : &mut SearchControl<'_>, search : fn(pleco::Board, u16) -> Move) -> Move
+{
+  let limits = *control.limits();
+  if limits.infinite || limits.nodes.is_some() || limits.time_budget().is_some()
+  {
+    return search::AlphaBetaAlgorithm {}.best_move(board, evaluator, control);
+  }
+  let depth = limits.depth.unwrap_or(DEFAULT_DEPTH);
+
+  #[cfg(target_arch = "wasm32")]
+  return search(board.pleco_board, depth);
+
+  #[cfg(not(target_arch = "wasm32"))]
+  {
+    let (sender, receiver) = std::sync::mpsc::channel();
+    let pleco_board = board.pleco_board.clone();
+    std::thread::spawn(move || { let _ = sender.send(search(pleco_board, depth)); });
+    loop
+    {
+      if control.should_stop()
+      {
+        return greedy_move(&board, evaluator);
+      }
+      match receiver.recv_timeout(POLL_INTERVAL)
+      {
+        Ok(m) => return m,
+        Err(std::sync::mpsc::RecvTimeoutError::Timeout) => continue,
+        Err(std::sync::mpsc::RecvTimeoutError::Disconnected) => return greedy_move(&board, evaluator),
+      }
+    }
+  }
+}
+
+implement_algorithm_trait!(MinMaxAlgorithm, pleco::bots::ParallelMiniMaxSearcher, "min_max");
+implement_algorithm_trait!(IterativeAlgorithm, pleco::bots::IterativeSearcher, "iterative");
+implement_algorithm_trait!(RandomAlgorithm, pleco::bots::RandomBot, "random");
+
+///
+/// Makes the move with the best evaluation of the position after it. Doesn't look deeper, depth is ignored.
+///
+
+struct GreedyAlgorithm;
+
+impl Algorithm for GreedyAlgorithm
+{
+  fn name(&self) -> &'static str { "Greedy Searcher" }
+
+  fn short_name(&self) -> &'static str { "greedy" }
+
+  fn best_move(&self, board : Board, evaluator : &dyn Evaluator, _control : &mut SearchControl<'_>) -> Move { greedy_move(&board, evaluator) }
+}
+
+///
+/// Legal move with the best evaluation of the position after it, checkmate is the best. Null move if there are no legal moves.
+///
+
+pub(crate) fn greedy_move(board : &Board, evaluator : &dyn Evaluator) -> Move
+{
+  board
+    .legal_moves()
+    .into_iter()
+    .map(|m|
+    {
+      let mut after = board.clone();
+      after.apply(m);
+      let score = if after.is_checkmate()
+      {
+        i32::MAX
+      }
+      else if after.is_stalemate()
+      {
+        0
+      }
+      else
+      {
+        -evaluator.evaluate(&after)
+      };
+      (m, score)
+    })
+    .max_by_key(|(_, score)| *score)
+    .map_or(Move::null(), |(m, _)| m)
+}
+
+///
+/// Encapsulates all data required for AI algorithms to work.
+/// Clones share the algorithm and the evaluator.
+///
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct Engine
+{
+  #[serde(serialize_with = "ai_ser", deserialize_with = "ai_der")]
+  algorithm : Arc<dyn Algorithm>,
+  ///
+  /// Depth of calculation of moves by the engine
+  ///
+  pub depth : u16,
+  #[serde(default = "default_evaluator", serialize_with = "evaluator_ser", deserialize_with = "evaluator_der")]
+  evaluator : Arc<dyn Evaluator>,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  book : Option<Book>,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  tablebase : Option<Tablebase>,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  skill : Option<Skill>,
+  /// Size of the transposition table in megabytes. It depends on the machine, so it is not serialized.
+  #[serde(skip, default = "default_hash_size")]
+  hash_size : usize,
+}
+
+///
+/// Error during creating of AI Algorithm
+///
+#[derive(Debug)]
+pub enum CreationError
+{
+  ///
+  /// Unrecognised algorithm name
+  ///
+  UnknownAlgorithm,
+  ///
+  /// Unrecognised evaluator name
+  ///
+  UnknownEvaluator,
+  ///
+  /// External engine can't be started
+  ///
+  EngineFailed(crate::Error),
+}
+
+impl std::fmt::Display for CreationError
+{
+  fn fmt(&self, f : &mut std::fmt::Formatter<'_>) -> std::fmt::Result
+  {
+    match self
+    {
+      CreationError::UnknownAlgorithm => write!(f, "unknown AI algorithm"),
+      CreationError::UnknownEvaluator => write!(f, "unknown evaluator"),
+      CreationError::EngineFailed(error) => write!(f, "external engine failed : {}", error),
+    }
+  }
+}
+
+impl std::error::Error for CreationError {}
+
+impl Engine
+{
+  ///
+  /// Create new engine by name. "uci:<program> [arguments]" starts an external UCI engine.
+  ///
+  pub fn new(name : String) -> Result<Self, CreationError> { Self::new_with_depth(name, DEFAULT_DEPTH) }
+
+  ///
+  /// Create new engine by name and depth
+  ///
+  pub fn new_with_depth(name : String, depth : u16) -> Result<Self, CreationError>
+  {
+    Ok(Self::with_algorithm(Self::new_algorithm(name)?, depth))
+  }
+
+  ///
+  /// Create new engine with own algorithm, for example [UciEngine] with options set.
+  ///
+  pub fn with_algorithm(algorithm : Box<dyn Algorithm>, depth : u16) -> Self
+  {
+    Engine {
+      algorithm : algorithm.into(),
+      depth,
+      evaluator : default_evaluator(),
+      book : None,
+      tablebase : None,
+      skill : None,
+      hash_size : DEFAULT_HASH_SIZE,
+    }
+  }
+
+  fn new_algorithm(name : String) -> Result<Box<dyn Algorithm>, CreationError>
+  {
+    match name.as_str()
+    {
+      "min_max" => Ok(Box::new(MinMaxAlgorithm {})),
+      "iterative" => Ok(Box::new(IterativeAlgorithm {})),
+      "random" => Ok(Box::new(RandomAlgorithm {})),
+      "greedy" => Ok(Box::new(GreedyAlgorithm {})),
+      "alpha_beta" => Ok(Box::new(search::AlphaBetaAlgorithm {})),
+      #[cfg(not(target_arch = "wasm32"))]
+      command if command.starts_with(UCI_PREFIX) =>
+      {
+        let engine = UciEngine::from_command(&command[UCI_PREFIX.len()..]).map_err(CreationError::EngineFailed)?;
+        Ok(Box::new(engine))
+      }
+      _ => Err(CreationError::UnknownAlgorithm),
+    }
+  }
+
+  ///
+  /// Replaces algorithm of the engine by name. Other settings of the engine are kept.
+  ///
+  pub fn set_algorithm(&mut self, name : String) -> Result<(), CreationError>
+  {
+    self.algorithm = Self::new_algorithm(name)?.into();
+    Ok(())
+  }
+
+  ///
+  /// Command of the external engine if the engine was deserialized and the engine is not started yet.
+  /// Saves come from anywhere, so the frontend asks the user or checks the command before [Engine::start_external].
+  ///
+  pub fn unstarted_command(&self) -> Option<&str> { self.algorithm.unstarted_command() }
+
+  ///
+  /// Starts the external engine which was deserialized. Does nothing if the algorithm is not such an engine.
+  ///
+  pub fn start_external(&mut self) -> Result<(), CreationError>
+  {
+    if self.unstarted_command().is_some()
+    {
+      self.set_algorithm(self.algorithm.id())?;
+    }
+    Ok(())
+  }
+
+  ///
+  /// Short name of the algorithm of the engine.
+  ///
+  pub fn algorithm_name(&self) -> &'static str { self.algorithm.short_name() }
+
+  ///
+  /// Name which creates the algorithm of the engine again, see [Algorithm::id].
+  ///
+  pub fn algorithm_id(&self) -> String { self.algorithm.id() }
+
+  ///
+  /// Creates evaluator by name: "default" or "pleco".
+  ///
+  pub fn new_evaluator(name : &str) -> Result<Box<dyn Evaluator>, CreationError>
+  {
+    match name
+    {
+      "default" => Ok(Box::new(DefaultEvaluator {})),
+      "pleco" => Ok(Box::new(PlecoEvaluator {})),
+      _ => Err(CreationError::UnknownEvaluator),
+    }
+  }
+
+  ///
+  /// Replaces evaluator of the engine. Evaluators are serialized by name,
+  /// so an engine with own evaluator can be serialized, but not deserialized.
+  ///
+  pub fn set_evaluator(&mut self, evaluator : Box<dyn Evaluator>) { self.evaluator = evaluator.into(); }
+
+  ///
+  /// Evaluator used by the algorithm of the engine.
+  ///
+  pub fn evaluator(&self) -> &dyn Evaluator { &*self.evaluator }
+
+  ///
+  /// Sets size of the transposition table in megabytes, at least one.
+  ///
+  pub fn set_hash_size(&mut self, megabytes : usize) { self.hash_size = megabytes.max(1); }
+
+  ///
+  /// Size of the transposition table in megabytes.
+  ///
+  pub fn hash_size(&self) -> usize { self.hash_size }
+
+  ///
+  /// Sets opening book of the engine, `None` to search from the first move.
+  ///
+  pub fn set_book(&mut self, book : Option<Book>) { self.book = book; }
+
+  ///
+  /// Opening book of the engine.
+  ///
+  pub fn book(&self) -> Option<&Book> { self.book.as_ref() }
+
+  ///
+  /// Sets endgame tablebase of the engine, `None` to search endgames as other positions.
+  ///
+  pub fn set_tablebase(&mut self, tablebase : Option<Tablebase>) { self.tablebase = tablebase; }
+
+  ///
+  /// Endgame tablebase of the engine.
+  ///
+  pub fn tablebase(&self) -> Option<&Tablebase> { self.tablebase.as_ref() }
+
+  ///
+  /// Sets skill level of the engine, `None` for full strength.
+  ///
+  pub fn set_skill(&mut self, skill : Option<Skill>) { self.skill = skill; }
+
+  ///
+  /// Skill level of the engine, `None` for full strength.
+  ///
+  pub fn skill(&self) -> Option<Skill> { self.skill }
+
+  ///
+  /// Find best move on board
+  ///
+  pub fn best_move(&self, board : Board) -> Move { self.best_move_with_limits(board, Limits::depth(self.depth)) }
+
+  ///
+  /// Finds best move on board within the limits. Blocks until the search is finished.
+  ///
+  pub fn best_move_with_limits(&self, board : Board, limits : Limits) -> Move
+  {
+    self.search(board, limits, CancelHandle::new(), |_| ())
+  }
+
+  ///
+  /// Finds best move on board within the limits. Blocks until the search is finished or cancelled
+  /// and passes partial results to `report`. Limits without any limit use the depth of the engine.
+  /// A move of the opening book or the tablebase is played without search. Skill level below full strength
+  /// narrows the limits and picks the move among the near-best lines, the lines of the evaluator if the algorithm reports none.
+  ///
+  pub fn search(&self, board : Board, limits : Limits, cancel : CancelHandle, report : impl FnMut(&SearchInfo)) -> Move
+  {
+    self.play(board, limits, cancel, None, report)
+  }
+
+  ///
+  /// Search of [Engine::search], pondering until the ponder hit if the search has [PonderHandle].
+  ///
+  fn play(&self, board : Board, limits : Limits, cancel : CancelHandle, ponder : Option<PonderHandle>, mut report : impl FnMut(&SearchInfo)) -> Move
+  {
+    if let Some(m) = self.known_move(&board)
+    {
+      return m;
+    }
+    let skill = match self.skill
+    {
+      Some(skill) if !skill.is_full_strength() => skill,
+      _ =>
+      {
+        let mut control = self.control(limits, cancel).with_ponder(ponder).with_report(report);
+        return self.algorithm.best_move(board, &*self.evaluator, &mut control);
+      }
+    };
+
+    let limits = skill.limit(if limits.is_empty() { Limits::depth(self.depth) } else { limits });
+    let mut infos = Vec::new();
+    let mut control = self.control(limits, cancel).with_lines(skill.lines()).with_ponder(ponder).with_report(|info|
+    {
+      report(info);
+      infos.push(info.clone());
+    });
+    let best_move = self.algorithm.best_move(board.clone(), &*self.evaluator, &mut control);
+    drop(control);
+    let mut lines = Analysis::from_infos(&infos, 0, std::time::Duration::ZERO).lines;
+    if lines.is_empty()
+    {
+      lines = evaluated_lines(&board, &*self.evaluator);
+      lines.truncate(skill.lines());
+    }
+    skill.choose(&lines).unwrap_or(best_move)
+  }
+
+  ///
+  /// Analyzes the position within the limits: finds up to `lines` best lines with scores and principal variations.
+  /// Algorithms which don't report lines give their best move scored by the evaluator. Blocks until the search is finished.
+  ///
+  pub fn analyze(&self, board : Board, limits : Limits, lines : usize) -> Analysis
+  {
+    let mut infos = Vec::new();
+    let mut control = self.control(limits, CancelHandle::new()).with_lines(lines).with_report(|info| infos.push(info.clone()));
+    let best_move = self.algorithm.best_move(board.clone(), &*self.evaluator, &mut control);
+    let (nodes, time) = (control.nodes(), control.elapsed());
+    drop(control);
+
+    let mut analysis = Analysis::from_infos(&infos, nodes, time);
+    if analysis.lines.is_empty() && !best_move.is_null()
+    {
+      let mut after = board;
+      after.apply(best_move);
+      analysis.lines.push(Line { score : Score::Centipawns(-self.evaluator.evaluate(&after)), pv : vec![best_move] });
+    }
+    analysis
+  }
+
+  ///
+  /// Starts search of the best move in its own thread and returns immediately. The move is searched as by [Engine::search].
+  ///
+  #[cfg(not(target_arch = "wasm32"))]
+  pub fn start(&self, board : Board, limits : Limits) -> SearchHandle { self.spawn(board, limits, 1, true, None) }
+
+  ///
+  /// Starts pondering in its own thread and returns immediately: searches the position after the expected reply of the opponent,
+  /// usually [SearchInfo::ponder_move], while the opponent thinks. Until [SearchHandle::ponder_hit] the search stops only by
+  /// the depth, after it the search continues with the work done so far and the limits of nodes and time apply from the hit.
+  /// If the opponent plays another move, stop the handle and start a new search.
+  ///
+  #[cfg(not(target_arch = "wasm32"))]
+  pub fn start_ponder(&self, board : Board, limits : Limits) -> SearchHandle { self.spawn(board, limits, 1, true, Some(PonderHandle::new())) }
+
+  ///
+  /// Starts search of up to `lines` best lines in its own thread and returns immediately.
+  /// Each finished line is reported as [SearchInfo]. The opening book and the tablebase are used only inside the search.
+  ///
+  #[cfg(not(target_arch = "wasm32"))]
+  pub fn start_analysis(&self, board : Board, limits : Limits, lines : usize) -> SearchHandle { self.spawn(board, limits, lines, false, None) }
+
+  #[cfg(not(target_arch = "wasm32"))]
+  fn spawn(&self, board : Board, limits : Limits, lines : usize, play : bool, ponder : Option<PonderHandle>) -> SearchHandle
+  {
+    let engine = self.clone();
+    let cancel = CancelHandle::new();
+    let (sender, infos) = std::sync::mpsc::channel();
+    let thread =
+    {
+      let cancel = cancel.clone();
+      let ponder = ponder.clone();
+      std::thread::spawn(move ||
+      {
+        let report = |info : &SearchInfo| { let _ = sender.send(info.clone()); };
+        if play
+        {
+          return engine.play(board, limits, cancel, ponder, report);
+        }
+        let mut control = engine.control(limits, cancel).with_lines(lines).with_report(report);
+        engine.algorithm.best_move(board, &*engine.evaluator, &mut control)
+      })
+    };
+    SearchHandle::new(cancel, ponder, infos, thread)
+  }
+
+  ///
+  /// Move of the opening book for the position, `None` if the engine has no book or the book has no move.
+  ///
+  pub fn book_move(&self, board : &Board) -> Option<Move> { self.book.as_ref().and_then(|book| book.choose(board)) }
+
+  ///
+  /// Move of the tablebase for the position, `None` if the engine has no tablebase or the position is not in it.
+  ///
+  pub fn tablebase_move(&self, board : &Board) -> Option<Move> { self.tablebase.as_ref().and_then(|tablebase| tablebase.best_move(board)) }
+
+  ///
+  /// Move played without search: the move of the opening book, or else the move of the tablebase.
+  ///
+  fn known_move(&self, board : &Board) -> Option<Move> { self.book_move(board).or_else(|| self.tablebase_move(board)) }
+
+  ///
+  /// Control of a search within the limits. Limits without any limit use the depth of the engine.
+  ///
+  fn control<'a>(&self, limits : Limits, cancel : CancelHandle) -> SearchControl<'a>
+  {
+    let limits = if limits.is_empty() { Limits::depth(self.depth) } else { limits };
+    SearchControl::new(limits, cancel).with_hash_size(self.hash_size).with_tablebase(self.tablebase.clone())
+  }
+}
+
+impl std::default::Default for Box<dyn Algorithm>
+{
+  fn default() -> Box<dyn Algorithm> { Box::new(IterativeAlgorithm {}) }
+}
+
+impl std::default::Default for Box<dyn Evaluator>
+{
+  fn default() -> Box<dyn Evaluator> { Box::new(DefaultEvaluator {}) }
+}
+
+fn default_evaluator() -> Arc<dyn Evaluator> { Arc::new(DefaultEvaluator {}) }
+
+fn default_hash_size() -> usize { DEFAULT_HASH_SIZE }
+
+impl core::fmt::Debug for dyn Algorithm
+{
+  fn fmt(&self, f : &mut core::fmt::Formatter<'_>) -> core::fmt::Result { write!(f, "ai::Algorithm{{{}}}", self.id()) }
+}
+
+///
+/// Serialize ai::Algorithm to string.
+///
+
+pub fn ai_ser<S : Serializer>(algorithm : &Arc<dyn Algorithm>, s : S) -> Result<S::Ok, S::Error>
+{
+  s.serialize_str(&algorithm.id())
+}
+
+///
+/// Deserialize ai::Algorithm from string. Unknown algorithm is an error.
+/// External engine is not started, see [Engine::start_external].
+///
+
+pub fn ai_der<'de, D : Deserializer<'de>>(d : D) -> Result<Arc<dyn Algorithm>, D::Error>
+{
+  let short_name : String = Deserialize::deserialize(d)?;
+  #[cfg(not(target_arch = "wasm32"))]
+  if let Some(command) = short_name.strip_prefix(UCI_PREFIX)
+  {
+    return Ok(Arc::new(external::UnstartedEngine::new(command)));
+  }
+  Engine::new_algorithm(short_name.clone())
+    .map(Arc::from)
+    .map_err(|error| serde::de::Error::custom(format!("{} '{}'", error, short_name)))
+}
+
+///
+/// Serialize ai::Evaluator to string.
+///
+
+pub fn evaluator_ser<S : Serializer>(evaluator : &Arc<dyn Evaluator>, s : S) -> Result<S::Ok, S::Error>
+{
+  s.serialize_str(evaluator.name())
+}
+
+///
+/// Deserialize ai::Evaluator from string. Unknown evaluator is an error.
+///
+
+pub fn evaluator_der<'de, D : Deserializer<'de>>(d : D) -> Result<Arc<dyn Evaluator>, D::Error>
+{
+  let name : String = Deserialize::deserialize(d)?;
+  Engine::new_evaluator(&name).map(Arc::from).map_err(|error| serde::de::Error::custom(format!("{} '{}'", error, name)))
+}
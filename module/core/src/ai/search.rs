@@ -0,0 +1,568 @@
+//!
+//! Native search: iterative deepening alpha-beta with transposition table, quiescence search,
+//! move ordering by MVV-LVA, killer moves and history heuristic, and null-move pruning.
+//! The search stops at the limits of its control and keeps the best move of the last finished depth.
+//! Endgames reached by a capture or a pawn move take their result from the tablebase of the control.
+//!
+
+use super::{Algorithm, Evaluator, Score, SearchControl, SearchInfo};
+use crate::{Board, Move, PieceType, Player, Wdl};
+
+///
+/// Score of checkmate at the root. Mate in n half-moves scores `MATE - n`.
+///
+
+pub(crate) const MATE : i32 = 30_000;
+
+///
+/// Scores above this are mates.
+///
+
+const MATE_BOUND : i32 = MATE - 1_000;
+
+const INFINITY : i32 = MATE + 1;
+
+///
+/// Score of a win from the tablebase at the root, less than any mate.
+///
+
+const TABLEBASE_WIN : i32 = MATE_BOUND - MAX_PLY as i32 - 1;
+
+///
+/// Depth is reduced by `NULL_MOVE_REDUCTION + 1` for the search after a null move.
+///
+
+const NULL_MOVE_REDUCTION : i32 = 2;
+
+const MAX_PLY : usize = 128;
+
+// Move ordering: move of the transposition table, captures by MVV-LVA, killers, then quiet moves by history.
+const ORDER_TABLE_MOVE : i32 = 1 << 30;
+const ORDER_CAPTURE : i32 = 1 << 29;
+const ORDER_KILLER : i32 = 1 << 28;
+
+///
+/// Iterative deepening alpha-beta searcher which uses the evaluator of the engine.
+///
+
+pub(crate) struct AlphaBetaAlgorithm;
+
+impl Algorithm for AlphaBetaAlgorithm
+{
+  fn name(&self) -> &'static str { "Alpha-Beta Searcher" }
+
+  fn short_name(&self) -> &'static str { "alpha_beta" }
+
+  fn best_move(&self, board : Board, evaluator : &dyn Evaluator, control : &mut SearchControl<'_>) -> Move
+  {
+    Search::new(evaluator, control).iterate(&board).0
+  }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Bound
+{
+  Exact,
+  Lower,
+  Upper,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Entry
+{
+  key : u64,
+  depth : i32,
+  score : i32,
+  bound : Bound,
+  best_move : Option<Move>,
+}
+
+///
+/// State of one search. The transposition table, killers and history live as long as the search.
+///
+
+pub(crate) struct Search<'a, 'b>
+{
+  evaluator : &'a dyn Evaluator,
+  control : &'a mut SearchControl<'b>,
+  /// Set when a limit is reached, scores of the unfinished depth are not used.
+  stopped : bool,
+  table : Vec<Option<Entry>>,
+  killers : Vec<[Option<Move>; 2]>,
+  history : Vec<[i32; 64]>,
+}
+
+impl<'a, 'b> Search<'a, 'b>
+{
+  pub(crate) fn new(evaluator : &'a dyn Evaluator, control : &'a mut SearchControl<'b>) -> Self
+  {
+    let entries = table_size(control.hash_size());
+    Self {
+      evaluator,
+      control,
+      stopped : false,
+      table : vec![None; entries],
+      killers : vec![[None; 2]; MAX_PLY],
+      history : vec![[0; 64]; 64],
+    }
+  }
+
+  ///
+  /// Searches with depth 1, 2 and so on up to the most depth of the control, until a limit is reached.
+  /// At each depth searches as many best lines as the control asks, each line excludes the first moves of the better ones.
+  /// Reports each finished line. Returns the best move and its score for the side to move.
+  /// Null move if there are no legal moves.
+  ///
+  pub(crate) fn iterate(&mut self, board : &Board) -> (Move, i32)
+  {
+    let legal = board.legal_moves();
+    let mut best = (legal.first().copied().unwrap_or_else(Move::null), 0);
+    if best.0.is_null()
+    {
+      return (best.0, if board.is_check() { -MATE } else { 0 });
+    }
+    let lines = self.control.lines().min(legal.len());
+    for depth in 1..=self.control.max_depth()
+    {
+      let mut excluded = Vec::new();
+      for multipv in 1..=lines
+      {
+        let line = match self.root(board, depth as i32, &excluded)
+        {
+          Some(line) => line,
+          None => break,
+        };
+        if multipv == 1
+        {
+          best = line;
+        }
+        if self.stopped
+        {
+          break;
+        }
+        excluded.push(line.0);
+        let info = SearchInfo {
+          depth,
+          multipv,
+          score : score(line.1),
+          pv : self.pv(board, line.0, depth as usize),
+          nodes : self.control.nodes(),
+          time : self.control.elapsed(),
+        };
+        self.control.report(&info);
+      }
+      if self.stopped || best.1.abs() >= MATE_BOUND || self.control.should_stop()
+      {
+        break;
+      }
+    }
+    best
+  }
+
+  ///
+  /// Searches moves of the root except the excluded ones with full window to find the best one.
+  /// If a limit is reached, returns the best of the moves searched to the end, `None` if there are none.
+  ///
+  pub(crate) fn root(&mut self, board : &Board, depth : i32, excluded : &[Move]) -> Option<(Move, i32)>
+  {
+    let mut moves : Vec<Move> = board.legal_moves().into_iter().filter(|m| !excluded.contains(m)).collect();
+    let table_move = self.probe(board.hash()).and_then(|entry| entry.best_move);
+    self.order(board, &mut moves, table_move, 0);
+
+    let mut alpha = -INFINITY;
+    let mut best = None;
+    for m in moves
+    {
+      let after = make(board, m);
+      let score = -self.negamax(&after, depth - 1, -INFINITY, -alpha, 1, true);
+      if self.stopped
+      {
+        break;
+      }
+      if score > alpha
+      {
+        alpha = score;
+        best = Some(m);
+      }
+    }
+
+    if !self.stopped && excluded.is_empty()
+    {
+      self.store(board.hash(), depth, alpha, Bound::Exact, best, 0);
+    }
+    best.map(|best| (best, alpha))
+  }
+
+  ///
+  /// Principal variation which starts with the move: best moves of the transposition table, up to the depth.
+  /// Stops at a repeated position.
+  ///
+  fn pv(&self, board : &Board, first : Move, depth : usize) -> Vec<Move>
+  {
+    let mut pv = vec![first];
+    let mut position = make(board, first);
+    let mut seen = vec![board.hash(), position.hash()];
+    while pv.len() < depth
+    {
+      let m = match self.probe(position.hash()).and_then(|entry| entry.best_move)
+      {
+        Some(m) if position.legal_moves().contains(&m) => m,
+        _ => break,
+      };
+      position = make(&position, m);
+      if seen.contains(&position.hash())
+      {
+        break;
+      }
+      seen.push(position.hash());
+      pv.push(m);
+    }
+    pv
+  }
+
+  ///
+  /// Counts the node and checks the limits. Once a limit is reached the search unwinds.
+  ///
+  fn visit(&mut self) -> bool
+  {
+    self.control.visit();
+    if !self.stopped && self.control.should_stop()
+    {
+      self.stopped = true;
+    }
+    self.stopped
+  }
+
+  fn negamax(&mut self, board : &Board, mut depth : i32, mut alpha : i32, beta : i32, ply : usize, allow_null : bool) -> i32
+  {
+    if self.visit()
+    {
+      return 0;
+    }
+    if board.is_insufficient_material()
+    {
+      return 0;
+    }
+    if let Some(score) = self.probe_tablebase(board, ply)
+    {
+      return score;
+    }
+
+    let in_check = board.is_check();
+    if in_check
+    {
+      depth += 1;
+    }
+    if depth <= 0 || ply >= MAX_PLY - 1
+    {
+      return self.quiescence(board, alpha, beta, ply);
+    }
+
+    let key = board.hash();
+    let entry = self.probe(key);
+    if let Some(entry) = entry
+    {
+      if entry.depth >= depth
+      {
+        let score = score_from_table(entry.score, ply);
+        match entry.bound
+        {
+          Bound::Exact => return score,
+          Bound::Lower if score >= beta => return score,
+          Bound::Upper if score <= alpha => return score,
+          _ => (),
+        }
+      }
+    }
+
+    if allow_null && !in_check && depth > NULL_MOVE_REDUCTION && has_pieces(board, board.current_turn())
+    {
+      let after = make_null(board);
+      let score = -self.negamax(&after, depth - 1 - NULL_MOVE_REDUCTION, -beta, -beta + 1, ply + 1, false);
+      if score >= beta
+      {
+        return beta;
+      }
+    }
+
+    let mut moves = board.legal_moves();
+    if moves.is_empty()
+    {
+      return if in_check { -MATE + ply as i32 } else { 0 };
+    }
+    self.order(board, &mut moves, entry.and_then(|entry| entry.best_move), ply);
+
+    let original_alpha = alpha;
+    let mut best_score = -INFINITY;
+    let mut best_move = None;
+    for m in moves
+    {
+      let after = make(board, m);
+      let score = -self.negamax(&after, depth - 1, -beta, -alpha, ply + 1, true);
+      if self.stopped
+      {
+        break;
+      }
+      if score > best_score
+      {
+        best_score = score;
+        best_move = Some(m);
+      }
+      if score > alpha
+      {
+        alpha = score;
+      }
+      if alpha >= beta
+      {
+        if !is_tactical(m)
+        {
+          self.remember_quiet(m, depth, ply);
+        }
+        break;
+      }
+    }
+
+    if self.stopped
+    {
+      return 0;
+    }
+    let bound = if best_score >= beta
+    {
+      Bound::Lower
+    }
+    else if best_score > original_alpha
+    {
+      Bound::Exact
+    }
+    else
+    {
+      Bound::Upper
+    };
+    self.store(key, depth, best_score, bound, best_move, ply);
+    best_score
+  }
+
+  ///
+  /// Searches captures and promotions only, until the position is quiet.
+  /// The side to move may stand pat: take the static evaluation instead of capturing.
+  ///
+  fn quiescence(&mut self, board : &Board, mut alpha : i32, beta : i32, ply : usize) -> i32
+  {
+    if self.visit()
+    {
+      return 0;
+    }
+    let stand_pat = self.evaluator.evaluate(board);
+    if stand_pat >= beta || ply >= MAX_PLY - 1
+    {
+      return stand_pat;
+    }
+    if stand_pat > alpha
+    {
+      alpha = stand_pat;
+    }
+
+    let mut moves : Vec<Move> = board.legal_moves().into_iter().filter(|m| is_tactical(*m)).collect();
+    self.order(board, &mut moves, None, ply);
+    for m in moves
+    {
+      let after = make(board, m);
+      let score = -self.quiescence(&after, -beta, -alpha, ply + 1);
+      if self.stopped
+      {
+        return 0;
+      }
+      if score >= beta
+      {
+        return score;
+      }
+      if score > alpha
+      {
+        alpha = score;
+      }
+    }
+    alpha
+  }
+
+  ///
+  /// Result of the tablebase for a position reached by a capture or a pawn move, so the fifty-move counter is zero.
+  /// Wins are scored as less than mates, wins and losses which are drawn by the fifty-move rule are draws.
+  ///
+  fn probe_tablebase(&self, board : &Board, ply : usize) -> Option<i32>
+  {
+    let tablebase = self.control.tablebase()?;
+    let m = board.last_move()?;
+    let zeroing = m.is_capture() || m.is_promo() || board.piece_at(m.get_dest().0).type_of() == PieceType::P;
+    if !zeroing
+    {
+      return None;
+    }
+    Some(match tablebase.probe_wdl(board)?
+    {
+      Wdl::Win => TABLEBASE_WIN - ply as i32,
+      Wdl::Loss => -TABLEBASE_WIN + ply as i32,
+      Wdl::CursedWin | Wdl::Draw | Wdl::BlessedLoss => 0,
+    })
+  }
+
+  fn order(&self, board : &Board, moves : &mut [Move], table_move : Option<Move>, ply : usize)
+  {
+    moves.sort_by_cached_key(|m|
+    {
+      let priority = if Some(*m) == table_move
+      {
+        ORDER_TABLE_MOVE
+      }
+      else if is_tactical(*m)
+      {
+        ORDER_CAPTURE + 10 * piece_value(victim(board, *m)) - piece_value(board.piece_at(m.get_src().0).type_of())
+      }
+      else if self.killers[ply].contains(&Some(*m))
+      {
+        ORDER_KILLER
+      }
+      else
+      {
+        self.history[m.get_src().0 as usize][m.get_dest().0 as usize]
+      };
+      -priority
+    });
+  }
+
+  ///
+  /// Quiet move caused a cutoff: it becomes a killer of the ply and gains history.
+  ///
+  fn remember_quiet(&mut self, m : Move, depth : i32, ply : usize)
+  {
+    let killers = &mut self.killers[ply];
+    if killers[0] != Some(m)
+    {
+      killers[1] = killers[0];
+      killers[0] = Some(m);
+    }
+    let history = &mut self.history[m.get_src().0 as usize][m.get_dest().0 as usize];
+    *history = (*history + depth * depth).min(ORDER_KILLER - 1);
+  }
+
+  fn probe(&self, key : u64) -> Option<Entry> { self.table[key as usize & (self.table.len() - 1)].filter(|entry| entry.key == key) }
+
+  fn store(&mut self, key : u64, depth : i32, score : i32, bound : Bound, best_move : Option<Move>, ply : usize)
+  {
+    let index = key as usize & (self.table.len() - 1);
+    let slot = &mut self.table[index];
+    if slot.is_none_or(|entry| entry.key != key || entry.depth <= depth)
+    {
+      *slot = Some(Entry { key, depth, score : score_to_table(score, ply), bound, best_move });
+    }
+  }
+}
+
+///
+/// Score for reports: mate scores become mate in moves.
+///
+
+fn score(score : i32) -> Score
+{
+  if score.abs() >= MATE_BOUND
+  {
+    let moves = (MATE - score.abs() + 1) / 2;
+    Score::Mate(if score > 0 { moves } else { -moves })
+  }
+  else
+  {
+    Score::Centipawns(score)
+  }
+}
+
+///
+/// Number of entries of the transposition table which fit in the megabytes, a power of two.
+///
+
+fn table_size(megabytes : usize) -> usize
+{
+  let entries = (megabytes.saturating_mul(1024 * 1024) / std::mem::size_of::<Option<Entry>>()).max(1);
+  if entries.is_power_of_two() { entries } else { entries.next_power_of_two() / 2 }
+}
+
+fn make(board : &Board, m : Move) -> Board
+{
+  let mut after = board.clone();
+  after.apply(m);
+  after
+}
+
+///
+/// Passes the move to the other side. Not allowed in check.
+///
+
+fn make_null(board : &Board) -> Board
+{
+  let mut after = board.clone();
+  // SAFETY: the side to move is not in check, which is the only requirement of pleco for null moves.
+  unsafe { after.pleco_board.apply_null_move() };
+  after
+}
+
+fn is_tactical(m : Move) -> bool { m.is_capture() || m.is_promo() }
+
+fn victim(board : &Board, m : Move) -> PieceType
+{
+  if m.is_en_passant()
+  {
+    PieceType::P
+  }
+  else
+  {
+    board.piece_at(m.get_dest().0).type_of()
+  }
+}
+
+fn piece_value(piece : PieceType) -> i32 { piece as i32 }
+
+///
+/// True if the player has pieces other than pawns and the king. Null move is unsafe in pawn endings because of zugzwang.
+///
+
+fn has_pieces(board : &Board, player : Player) -> bool
+{
+  (0..64).map(|sq| board.piece_at(sq)).any(|piece|
+  {
+    piece.player() == Some(player) && !matches!(piece.type_of(), PieceType::P | PieceType::K | PieceType::None)
+  })
+}
+
+///
+/// Mate scores are stored relative to the position, not to the root.
+///
+
+fn score_to_table(score : i32, ply : usize) -> i32
+{
+  if score >= MATE_BOUND
+  {
+    score + ply as i32
+  }
+  else if score <= -MATE_BOUND
+  {
+    score - ply as i32
+  }
+  else
+  {
+    score
+  }
+}
+
+fn score_from_table(score : i32, ply : usize) -> i32
+{
+  if score >= MATE_BOUND
+  {
+    score - ply as i32
+  }
+  else if score <= -MATE_BOUND
+  {
+    score + ply as i32
+  }
+  else
+  {
+    score
+  }
+}
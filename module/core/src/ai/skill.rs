@@ -0,0 +1,213 @@
+//!
+//! Skill levels: weaker engines search less and play one of the near-best lines instead of the best one.
+//! The weaker the level, the greater the loss in centipawns the engine accepts.
+//!
+
+use super::{Limits, Line, Score};
+use crate::Move;
+
+use serde::{Serialize, Deserialize};
+
+///
+/// Weakest skill level.
+///
+
+pub const MIN_SKILL_LEVEL : u8 = 1;
+
+///
+/// Strongest skill level, the engine plays at full strength.
+///
+
+pub const MAX_SKILL_LEVEL : u8 = 20;
+
+///
+/// Rough Elo of the weakest and the strongest limited level.
+///
+
+const MIN_ELO : u32 = 800;
+const MAX_ELO : u32 = 2400;
+
+///
+/// Lines searched to pick a move from.
+///
+
+const CANDIDATE_LINES : usize = 4;
+
+///
+/// Loss in centipawns accepted per level below the strongest one.
+///
+
+const ERROR_PER_LEVEL : i64 = 25;
+
+///
+/// Scores of mates, beyond any score in centipawns.
+///
+
+const MATE_CENTIPAWNS : i64 = 1_000_000;
+
+///
+/// Skill level of an engine from [MIN_SKILL_LEVEL] to [MAX_SKILL_LEVEL]. Levels below the strongest limit depth and nodes
+/// of the search and make mistakes: the move is picked randomly among the lines which lose not more than the error budget,
+/// better lines are picked more often. The level is serialized as a number.
+///
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(try_from = "u8", into = "u8")]
+pub struct Skill
+{
+  level : u8,
+}
+
+impl Skill
+{
+  ///
+  /// Skill of the level, clamped to the range of levels.
+  ///
+  pub fn new(level : u8) -> Self { Self { level : level.clamp(MIN_SKILL_LEVEL, MAX_SKILL_LEVEL) } }
+
+  ///
+  /// Skill which plays at about the Elo. Elo above the strongest limited level gives full strength.
+  ///
+  pub fn from_elo(elo : u32) -> Self
+  {
+    if elo > MAX_ELO
+    {
+      return Self::new(MAX_SKILL_LEVEL);
+    }
+    let steps = (MAX_SKILL_LEVEL - MIN_SKILL_LEVEL - 1) as u32;
+    let level = (elo.saturating_sub(MIN_ELO) * steps + (MAX_ELO - MIN_ELO) / 2) / (MAX_ELO - MIN_ELO);
+    Self::new(MIN_SKILL_LEVEL + level as u8)
+  }
+
+  ///
+  /// Level of the skill.
+  ///
+  pub fn level(&self) -> u8 { self.level }
+
+  ///
+  /// Rough Elo of the level, `None` for full strength.
+  ///
+  pub fn elo(&self) -> Option<u32>
+  {
+    if self.is_full_strength()
+    {
+      return None;
+    }
+    let steps = (MAX_SKILL_LEVEL - MIN_SKILL_LEVEL - 1) as u32;
+    Some(MIN_ELO + (self.level - MIN_SKILL_LEVEL) as u32 * (MAX_ELO - MIN_ELO) / steps)
+  }
+
+  ///
+  /// True for the strongest level, which doesn't limit the engine.
+  ///
+  pub fn is_full_strength(&self) -> bool { self.level == MAX_SKILL_LEVEL }
+
+  ///
+  /// Most depth of the search in half-moves.
+  ///
+  pub fn depth(&self) -> u16 { self.level as u16 / 2 + 1 }
+
+  ///
+  /// Most positions visited by the search.
+  ///
+  pub fn nodes(&self) -> u64 { 1 << (self.level + 6) }
+
+  ///
+  /// Number of best lines searched to pick the move from.
+  ///
+  pub fn lines(&self) -> usize { if self.is_full_strength() { 1 } else { CANDIDATE_LINES } }
+
+  ///
+  /// Most loss in centipawns of a picked line against the best line.
+  ///
+  pub fn error_budget(&self) -> i64 { (MAX_SKILL_LEVEL - self.level) as i64 * ERROR_PER_LEVEL }
+
+  ///
+  /// Limits narrowed by the depth and nodes of the level. Infinite search becomes finite.
+  ///
+  pub fn limit(&self, limits : Limits) -> Limits
+  {
+    if self.is_full_strength()
+    {
+      return limits;
+    }
+    Limits {
+      depth : Some(limits.depth.map_or(self.depth(), |depth| depth.min(self.depth()))),
+      nodes : Some(limits.nodes.map_or(self.nodes(), |nodes| nodes.min(self.nodes()))),
+      infinite : false,
+      ..limits
+    }
+  }
+
+  ///
+  /// Picks the move among lines sorted best first: randomly among the lines within the error budget,
+  /// the chance of a line grows with the budget left after its loss. `None` if there are no lines.
+  ///
+  pub fn choose(&self, lines : &[Line]) -> Option<Move>
+  {
+    let lines : Vec<&Line> = lines.iter().filter(|line| !line.pv.is_empty()).collect();
+    let best = centipawns(lines.first()?.score);
+    let budget = self.error_budget();
+    let candidates : Vec<(Move, u64)> = lines
+      .iter()
+      .filter_map(|line|
+      {
+        let loss = best - centipawns(line.score);
+        if loss <= budget { Some((line.pv[0], (budget - loss.max(0) + 1) as u64)) } else { None }
+      })
+      .collect();
+
+    let total : u64 = candidates.iter().map(|(_, weight)| weight).sum();
+    let mut target = crate::random_below(total);
+    for (m, weight) in &candidates
+    {
+      if target < *weight
+      {
+        return Some(*m);
+      }
+      target -= weight;
+    }
+    Some(lines[0].pv[0])
+  }
+}
+
+impl std::default::Default for Skill
+{
+  fn default() -> Self { Self::new(MAX_SKILL_LEVEL) }
+}
+
+impl From<Skill> for u8
+{
+  fn from(src : Skill) -> Self { src.level }
+}
+
+impl TryFrom<u8> for Skill
+{
+  type Error = String;
+
+  fn try_from(level : u8) -> Result<Self, Self::Error>
+  {
+    if (MIN_SKILL_LEVEL..=MAX_SKILL_LEVEL).contains(&level)
+    {
+      Ok(Self { level })
+    }
+    else
+    {
+      Err(format!("skill level {} is not in {}..={}", level, MIN_SKILL_LEVEL, MAX_SKILL_LEVEL))
+    }
+  }
+}
+
+///
+/// Score as centipawns, mates beyond any centipawns, sooner mates better.
+///
+
+fn centipawns(score : Score) -> i64
+{
+  match score
+  {
+    Score::Centipawns(centipawns) => centipawns as i64,
+    Score::Mate(moves) if moves > 0 => MATE_CENTIPAWNS - moves as i64,
+    Score::Mate(moves) => -MATE_CENTIPAWNS - moves as i64,
+  }
+}
@@ -0,0 +1,596 @@
+//!
+//! Chess960 ( Fischer Random Chess ): start positions by index, castling with rooks on any file, X-FEN and Shredder-FEN.
+//!
+//! Pleco knows only classical castling, so a Chess960 board keeps castling rights itself and gives pleco
+//! the position without castling rights. Castling is the king taking its own rook, for example "b1a1" in UCI,
+//! like with option UCI_Chess960. When the rights left are classical ( the king on the e-file and rooks in the corners ),
+//! the board turns back to classical castling.
+//!
+
+use super::zobrist::{POLYGLOT_CASTLING, POLYGLOT_RANDOM};
+use super::{Board, Cell, Error, Fen, Move, PieceType, Player};
+
+///
+/// Files of castling rooks indexed by [Player] and by side: 0 is the king side ( h-file ), 1 is the queen side ( a-file ).
+///
+
+pub(crate) type CastlingRooks = [[Option<u8>; 2]; 2];
+
+///
+/// Pieces on squares as FEN characters, '.' for an empty square. Indexed like [Cell].
+///
+
+type Squares = [char; 64];
+
+const KING_SIDE : usize = 0;
+const QUEEN_SIDE : usize = 1;
+
+///
+/// Number of Chess960 start positions.
+///
+
+pub const CHESS960_POSITIONS : u16 = 960;
+
+///
+/// Index of the classical start position among Chess960 start positions.
+///
+
+pub const CHESS960_CLASSICAL : u16 = 518;
+
+///
+/// Placements of two knights on five free squares.
+///
+
+const KNIGHTS : [(usize, usize); 10] = [(0, 1), (0, 2), (0, 3), (0, 4), (1, 2), (1, 3), (1, 4), (2, 3), (2, 4), (3, 4)];
+
+impl Board
+{
+  ///
+  /// Constructs a board with Chess960 start position. Positions are numbered from 0 to 959 by the scheme
+  /// of Reinhard Scharnagl, 518 is the classical start position.
+  ///
+  pub fn chess960(index : u16) -> Result<Self, Error>
+  {
+    if index >= CHESS960_POSITIONS
+    {
+      return Err(Error::InvalidChess960Index(index));
+    }
+
+    let mut rank = ['.'; 8];
+    let free = |rank : &[char; 8]| -> Vec<usize> { (0..8).filter(|f| rank[*f] == '.').collect() };
+    let mut n = index as usize;
+    rank[(n % 4) * 2 + 1] = 'B';
+    n /= 4;
+    rank[(n % 4) * 2] = 'B';
+    n /= 4;
+    rank[free(&rank)[n % 6]] = 'Q';
+    n /= 6;
+    let (first, second) = KNIGHTS[n];
+    let squares = free(&rank);
+    rank[squares[first]] = 'N';
+    rank[squares[second]] = 'N';
+    let squares = free(&rank);
+    rank[squares[0]] = 'R';
+    rank[squares[1]] = 'K';
+    rank[squares[2]] = 'R';
+
+    let white : String = rank.iter().collect();
+    let rooks = [Some(squares[2] as u8), Some(squares[0] as u8)];
+    let fen = format!(
+      "{}/pppppppp/8/8/8/8/PPPPPPPP/{} w {} - 0 1",
+      white.to_lowercase(),
+      white,
+      shredder_castling(&[rooks, rooks])
+    );
+    Board::from_fen(&Fen::from(fen))
+  }
+
+  ///
+  /// Constructs a board with random Chess960 start position.
+  ///
+  pub fn chess960_random() -> Self { Self::chess960(random_chess960_index()).expect("index is less than 960") }
+
+  ///
+  /// True if castling follows Chess960 rules: some castling rook is not in its corner or the king is not on the e-file.
+  ///
+  pub fn is_chess960(&self) -> bool { self.castling.is_some() }
+
+  ///
+  /// Creates FEN of the board with castling rights in Shredder-FEN, as files of castling rooks. For example: "HAha".
+  ///
+  pub fn to_shredder_fen(&self) -> Fen
+  {
+    let fen = self.pleco_board.fen();
+    let rights = match &self.castling
+    {
+      Some(rooks) => shredder_castling(rooks),
+      None => fen
+        .split_whitespace()
+        .nth(2)
+        .unwrap_or("-")
+        .chars()
+        .map(|c| match c
+        {
+          'K' => 'H',
+          'Q' => 'A',
+          'k' => 'h',
+          'q' => 'a',
+          c => c,
+        })
+        .collect(),
+    };
+    Fen::from(replace_field(&fen, 2, &rights))
+  }
+
+  ///
+  /// FEN with castling rights in X-FEN: "K" and "Q" for the outermost rooks, files of other castling rooks.
+  ///
+  pub(crate) fn to_x_fen(&self, rooks : &CastlingRooks) -> Fen
+  {
+    let squares = self.squares();
+    let mut rights = String::new();
+    for_each_right(rooks, |player, side, file|
+    {
+      let rank = back_rank(player);
+      let rook = piece_char('R', player);
+      let mut outer = if side == KING_SIDE { file + 1..8 } else { 0..file };
+      let outermost = outer.all(|f| squares[rank + f as usize] != rook);
+      rights.push(match (outermost, side)
+      {
+        (true, KING_SIDE) => piece_char('K', player),
+        (true, _) => piece_char('Q', player),
+        (false, _) => file_char(file, player),
+      });
+    });
+    if rights.is_empty()
+    {
+      rights.push('-');
+    }
+    Fen::from(replace_field(&self.pleco_board.fen(), 2, &rights))
+  }
+
+  ///
+  /// Castling moves of Chess960 board, the king goes to the square of the rook.
+  /// The king can't castle out of check, through an attacked square or into check,
+  /// all squares between the king, the rook and their targets must be empty except the king and the rook.
+  ///
+  pub(crate) fn chess960_castling_moves(&self) -> Vec<Move>
+  {
+    let rooks = match &self.castling
+    {
+      Some(rooks) => rooks,
+      None => return Vec::new(),
+    };
+    if self.pleco_board.in_check()
+    {
+      return Vec::new();
+    }
+
+    let player = self.current_turn();
+    let rank = back_rank(player);
+    let squares = self.squares();
+    let king_file = match find_king(&squares, player)
+    {
+      Some(file) => file,
+      None => return Vec::new(),
+    };
+
+    let mut moves = Vec::new();
+    for side in [KING_SIDE, QUEEN_SIDE]
+    {
+      let rook_file = match rooks[player as usize][side]
+      {
+        Some(file) => file,
+        None => continue,
+      };
+      let (king_to, rook_to) = castling_targets(side);
+
+      let files = [king_file, rook_file, king_to, rook_to];
+      let low = *files.iter().min().unwrap();
+      let high = *files.iter().max().unwrap();
+      if (low..=high).any(|f| f != king_file && f != rook_file && squares[rank + f as usize] != '.')
+      {
+        continue;
+      }
+
+      let mut without_king = squares;
+      without_king[rank + king_file as usize] = '.';
+      let mut path = if king_to > king_file { king_file + 1..king_to } else { king_to + 1..king_file };
+      if path.any(|f| is_attacked(&without_king, rank + f as usize, player.other_player()))
+      {
+        continue;
+      }
+
+      let after = castled(&squares, player, king_file, rook_file, side);
+      if is_attacked(&after, rank + king_to as usize, player.other_player())
+      {
+        continue;
+      }
+
+      moves.push(Move::new((rank as u16 + king_file as u16) | (rank as u16 + rook_file as u16) << 6));
+    }
+    moves
+  }
+
+  ///
+  /// True if the move is castling of Chess960 board: the king takes its own rook.
+  ///
+  pub(crate) fn is_chess960_castling(&self, m : Move) -> bool
+  {
+    if self.castling.is_none()
+    {
+      return false;
+    }
+    let piece = self.pleco_board.piece_at_sq(m.get_src());
+    let target = self.pleco_board.piece_at_sq(m.get_dest());
+    piece.type_of() == PieceType::K && target.type_of() == PieceType::R && piece.player() == target.player()
+  }
+
+  ///
+  /// Makes move on Chess960 board and updates castling rights.
+  ///
+  pub(crate) fn apply_chess960(&mut self, m : Move)
+  {
+    let player = self.current_turn();
+    let src = m.get_src().0;
+    let dest = m.get_dest().0;
+    let king_moved = self.pleco_board.piece_at_sq(m.get_src()).type_of() == PieceType::K;
+
+    if self.is_chess960_castling(m)
+    {
+      let side = if dest % 8 > src % 8 { KING_SIDE } else { QUEEN_SIDE };
+      let after = castled(&self.squares(), player, src % 8, dest % 8, side);
+      let fen = self.pleco_board.fen();
+      let fields : Vec<&str> = fen.split_whitespace().collect();
+      let halfmove : u32 = fields.get(4).and_then(|n| n.parse().ok()).unwrap_or(0);
+      let fullmove : u32 = fields.get(5).and_then(|n| n.parse().ok()).unwrap_or(1);
+      let (turn, fullmove) = if player == Player::White { ("b", fullmove) } else { ("w", fullmove + 1) };
+      let fen = format!("{} {} - - {} {}", placement(&after), turn, halfmove + 1, fullmove);
+      self.pleco_board = pleco::Board::from_fen(&fen).expect("castling keeps the position valid");
+    }
+    else
+    {
+      self.pleco_board.apply_move(m);
+    }
+
+    let mut rooks = match self.castling
+    {
+      Some(rooks) => rooks,
+      None => return,
+    };
+    if king_moved
+    {
+      rooks[player as usize] = [None, None];
+    }
+    for owner in [Player::White, Player::Black]
+    {
+      for side in [KING_SIDE, QUEEN_SIDE]
+      {
+        let square = rooks[owner as usize][side].map(|file| (back_rank(owner) + file as usize) as u8);
+        if square == Some(src) || square == Some(dest)
+        {
+          rooks[owner as usize][side] = None;
+        }
+      }
+    }
+    self.castling = Some(rooks);
+
+    if is_classical(&self.squares(), &rooks)
+    {
+      let fen = self.to_x_fen(&rooks);
+      self.pleco_board = pleco::Board::from_fen(&fen).expect("classical castling rights are valid");
+      self.castling = None;
+    }
+  }
+
+  ///
+  /// Part of [Board::hash] for castling rights of Chess960 board, pleco hashes only classical rights.
+  ///
+  pub(crate) fn chess960_castling_key(&self) -> u64
+  {
+    let mut key = 0;
+    if let Some(rooks) = &self.castling
+    {
+      for_each_right(rooks, |player, side, file|
+      {
+        key ^= POLYGLOT_RANDOM[POLYGLOT_CASTLING + 2 * player as usize + side].rotate_left(file as u32 + 1);
+      });
+    }
+    key
+  }
+
+  fn squares(&self) -> Squares
+  {
+    let mut squares = ['.'; 64];
+    for (sq, square) in squares.iter_mut().enumerate()
+    {
+      let piece = self.pleco_board.piece_at_sq(Cell(sq as u8));
+      if piece.player().is_some()
+      {
+        *square = piece.character_lossy();
+      }
+    }
+    squares
+  }
+}
+
+///
+/// Reads castling rights of FEN: "KQkq" and "-", X-FEN or Shredder-FEN. Returns `None` if castling is classical,
+/// otherwise files of castling rooks. "K" and "Q" stand for the outermost rook on the side of the king.
+/// The second value is the castling field for pleco: classical rights as "KQkq", "-" for Chess960.
+///
+
+pub(crate) fn parse_castling(fen : &str) -> Result<(Option<CastlingRooks>, String), String>
+{
+  let fields : Vec<&str> = fen.split_whitespace().collect();
+  let field = match fields.get(2)
+  {
+    Some(field) if *field != "-" => *field,
+    _ => return Ok((None, "-".to_string())),
+  };
+  let squares = parse_placement(fields[0]).ok_or_else(|| "invalid placement of pieces".to_string())?;
+
+  let mut rooks : CastlingRooks = [[None; 2]; 2];
+  for c in field.chars()
+  {
+    let player = if c.is_ascii_uppercase() { Player::White } else { Player::Black };
+    let rank = back_rank(player);
+    let king = find_king(&squares, player).ok_or_else(|| format!("castling right '{}' without the king on the back rank", c))?;
+    let is_rook = |f : &u8| squares[rank + *f as usize] == piece_char('R', player);
+
+    let (side, file) = match c.to_ascii_lowercase()
+    {
+      'k' => (KING_SIDE, (king + 1..8).rev().find(is_rook)),
+      'q' => (QUEEN_SIDE, (0..king).find(is_rook)),
+      'a'..='h' =>
+      {
+        let file = c.to_ascii_lowercase() as u8 - b'a';
+        let side = if file > king { KING_SIDE } else { QUEEN_SIDE };
+        (side, Some(file).filter(is_rook))
+      }
+      _ => return Err(format!("invalid castling right '{}'", c)),
+    };
+    let file = file.ok_or_else(|| format!("castling right '{}' without the rook", c))?;
+    if rooks[player as usize][side].replace(file).is_some()
+    {
+      return Err(format!("castling right '{}' is repeated", c));
+    }
+  }
+
+  if !is_classical(&squares, &rooks)
+  {
+    return Ok((Some(rooks), "-".to_string()));
+  }
+  let mut classical = String::new();
+  for_each_right(&rooks, |player, side, _| classical.push(piece_char(if side == KING_SIDE { 'K' } else { 'Q' }, player)));
+  Ok((None, classical))
+}
+
+///
+/// Replaces field of FEN by its index.
+///
+
+pub(crate) fn replace_field(fen : &str, index : usize, value : &str) -> String
+{
+  fen
+    .split_whitespace()
+    .enumerate()
+    .map(|(i, field)| if i == index { value } else { field })
+    .collect::<Vec<_>>()
+    .join(" ")
+}
+
+///
+/// True if every castling rook is in its corner and the king is on the e-file.
+///
+
+fn is_classical(squares : &Squares, rooks : &CastlingRooks) -> bool
+{
+  let mut classical = true;
+  for_each_right(rooks, |player, side, file|
+  {
+    let corner = if side == KING_SIDE { 7 } else { 0 };
+    classical &= file == corner && find_king(squares, player) == Some(4);
+  });
+  classical
+}
+
+fn for_each_right(rooks : &CastlingRooks, mut f : impl FnMut(Player, usize, u8))
+{
+  for player in [Player::White, Player::Black]
+  {
+    for side in [KING_SIDE, QUEEN_SIDE]
+    {
+      if let Some(file) = rooks[player as usize][side]
+      {
+        f(player, side, file);
+      }
+    }
+  }
+}
+
+fn shredder_castling(rooks : &CastlingRooks) -> String
+{
+  let mut rights = String::new();
+  for_each_right(rooks, |player, _, file| rights.push(file_char(file, player)));
+  if rights.is_empty()
+  {
+    rights.push('-');
+  }
+  rights
+}
+
+///
+/// Squares after castling: the king and the rook go to their targets.
+///
+
+fn castled(squares : &Squares, player : Player, king_file : u8, rook_file : u8, side : usize) -> Squares
+{
+  let rank = back_rank(player);
+  let (king_to, rook_to) = castling_targets(side);
+  let mut after = *squares;
+  after[rank + king_file as usize] = '.';
+  after[rank + rook_file as usize] = '.';
+  after[rank + king_to as usize] = piece_char('K', player);
+  after[rank + rook_to as usize] = piece_char('R', player);
+  after
+}
+
+///
+/// True if a piece of `attacker` attacks the square.
+///
+
+fn is_attacked(squares : &Squares, square : usize, attacker : Player) -> bool
+{
+  let (file, rank) = ((square % 8) as i32, (square / 8) as i32);
+  let piece_at = |df : i32, dr : i32| -> Option<char>
+  {
+    let (f, r) = (file + df, rank + dr);
+    if (0..8).contains(&f) && (0..8).contains(&r) { Some(squares[(r * 8 + f) as usize]) } else { None }
+  };
+  let is = |c : Option<char>, pieces : &[char]| c.is_some_and(|c| pieces.iter().any(|p| piece_char(*p, attacker) == c));
+
+  let pawn_rank = if attacker == Player::White { -1 } else { 1 };
+  if is(piece_at(-1, pawn_rank), &['P']) || is(piece_at(1, pawn_rank), &['P'])
+  {
+    return true;
+  }
+
+  let knights = [(1, 2), (2, 1), (2, -1), (1, -2), (-1, -2), (-2, -1), (-2, 1), (-1, 2)];
+  if knights.iter().any(|(df, dr)| is(piece_at(*df, *dr), &['N']))
+  {
+    return true;
+  }
+
+  let kings = [(1, 0), (1, 1), (0, 1), (-1, 1), (-1, 0), (-1, -1), (0, -1), (1, -1)];
+  if kings.iter().any(|(df, dr)| is(piece_at(*df, *dr), &['K']))
+  {
+    return true;
+  }
+
+  kings.iter().any(|(df, dr)|
+  {
+    let slider = if *df == 0 || *dr == 0 { 'R' } else { 'B' };
+    let mut distance = 1;
+    loop
+    {
+      match piece_at(df * distance, dr * distance)
+      {
+        Some('.') => distance += 1,
+        found => return is(found, &[slider, 'Q']),
+      }
+    }
+  })
+}
+
+fn castling_targets(side : usize) -> (u8, u8)
+{
+  if side == KING_SIDE
+  {
+    (6, 5)
+  }
+  else
+  {
+    (2, 3)
+  }
+}
+
+///
+/// Index of the first square of the back rank of the player.
+///
+
+fn back_rank(player : Player) -> usize
+{
+  if player == Player::White
+  {
+    0
+  }
+  else
+  {
+    56
+  }
+}
+
+fn piece_char(piece : char, player : Player) -> char
+{
+  if player == Player::White
+  {
+    piece
+  }
+  else
+  {
+    piece.to_ascii_lowercase()
+  }
+}
+
+fn file_char(file : u8, player : Player) -> char { piece_char((b'A' + file) as char, player) }
+
+fn find_king(squares : &Squares, player : Player) -> Option<u8>
+{
+  let rank = back_rank(player);
+  (0..8u8).find(|f| squares[rank + *f as usize] == piece_char('K', player))
+}
+
+fn parse_placement(placement : &str) -> Option<Squares>
+{
+  let mut squares = ['.'; 64];
+  let ranks : Vec<&str> = placement.split('/').collect();
+  if ranks.len() != 8
+  {
+    return None;
+  }
+  for (i, rank) in ranks.iter().enumerate()
+  {
+    let mut file = 0;
+    for c in rank.chars()
+    {
+      match c.to_digit(10)
+      {
+        Some(n) => file += n as usize,
+        None if file < 8 =>
+        {
+          squares[(7 - i) * 8 + file] = c;
+          file += 1;
+        }
+        None => return None,
+      }
+    }
+    if file != 8
+    {
+      return None;
+    }
+  }
+  Some(squares)
+}
+
+fn placement(squares : &Squares) -> String
+{
+  let mut ranks = Vec::with_capacity(8);
+  for rank in (0..8).rev()
+  {
+    let mut text = String::new();
+    let mut empty = 0;
+    for c in &squares[rank * 8..rank * 8 + 8]
+    {
+      if *c == '.'
+      {
+        empty += 1;
+        continue;
+      }
+      if empty > 0
+      {
+        text.push_str(&empty.to_string());
+        empty = 0;
+      }
+      text.push(*c);
+    }
+    if empty > 0
+    {
+      text.push_str(&empty.to_string());
+    }
+    ranks.push(text);
+  }
+  ranks.join("/")
+}
+
+fn random_chess960_index() -> u16 { crate::random_below(CHESS960_POSITIONS as u64) as u16 }
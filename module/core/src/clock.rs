@@ -0,0 +1,493 @@
+//!
+//! Chess clock and time controls: sudden death, Fischer increment, Bronstein delay and simple delay.
+//! Time is read from a [TimeSource], so the clock can be driven manually in tests.
+//!
+
+use super::Player;
+
+use std::fmt;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use serde::{Serialize, Deserialize};
+
+///
+/// Source of monotonic time for [Clock]. Only differences between readings matter.
+///
+
+pub trait TimeSource : Send + Sync + fmt::Debug
+{
+  ///
+  /// Current reading of the time source.
+  ///
+  fn now(&self) -> Duration;
+}
+
+///
+/// Time source of the system. Readings are measured from the creation of the source.
+///
+
+#[derive(Debug)]
+pub struct SystemTimeSource
+{
+  #[cfg(not(target_arch = "wasm32"))]
+  start : std::time::Instant,
+  #[cfg(target_arch = "wasm32")]
+  start : f64,
+}
+
+impl SystemTimeSource
+{
+  ///
+  /// Constructs time source which starts from zero.
+  ///
+  pub fn new() -> Self
+  {
+    Self {
+      #[cfg(not(target_arch = "wasm32"))]
+      start : std::time::Instant::now(),
+      #[cfg(target_arch = "wasm32")]
+      start : js_sys::Date::now(),
+    }
+  }
+}
+
+impl Default for SystemTimeSource
+{
+  fn default() -> Self { Self::new() }
+}
+
+impl TimeSource for SystemTimeSource
+{
+  #[cfg(not(target_arch = "wasm32"))]
+  fn now(&self) -> Duration { self.start.elapsed() }
+
+  #[cfg(target_arch = "wasm32")]
+  fn now(&self) -> Duration { Duration::from_secs_f64((js_sys::Date::now() - self.start).max(0.0) / 1000.0) }
+}
+
+///
+/// Time source which is moved forward by hand. Clones share the same time,
+/// so a test can keep a clone and advance the time of the clock.
+///
+
+#[derive(Debug, Clone, Default)]
+pub struct ManualTimeSource
+{
+  now : Arc<Mutex<Duration>>,
+}
+
+impl ManualTimeSource
+{
+  ///
+  /// Constructs time source which stands at zero.
+  ///
+  pub fn new() -> Self { Self::default() }
+
+  ///
+  /// Moves the time forward.
+  ///
+  pub fn advance(&self, duration : Duration) { *self.now.lock().unwrap() += duration; }
+}
+
+impl TimeSource for ManualTimeSource
+{
+  fn now(&self) -> Duration { *self.now.lock().unwrap() }
+}
+
+///
+/// Rules how much time players have and how it is added after moves.
+///
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TimeControl
+{
+  ///
+  /// Whole game has to be played in the base time.
+  ///
+  SuddenDeath
+  {
+    /// Time of each player for the game.
+    base : Duration,
+  },
+  ///
+  /// The increment is added after each move.
+  ///
+  Fischer
+  {
+    /// Time of each player for the game.
+    base : Duration,
+    /// Time added after each move.
+    increment : Duration,
+  },
+  ///
+  /// After each move the time used for it is given back, but not more than the delay.
+  ///
+  Bronstein
+  {
+    /// Time of each player for the game.
+    base : Duration,
+    /// Most time given back after a move.
+    delay : Duration,
+  },
+  ///
+  /// The clock starts counting down only when the delay of the move has passed.
+  ///
+  SimpleDelay
+  {
+    /// Time of each player for the game.
+    base : Duration,
+    /// Time of each move which is not counted.
+    delay : Duration,
+  },
+}
+
+impl TimeControl
+{
+  ///
+  /// Time of each player at the start of the game.
+  ///
+  pub fn base(&self) -> Duration
+  {
+    match *self
+    {
+      TimeControl::SuddenDeath { base }
+      | TimeControl::Fischer { base, .. }
+      | TimeControl::Bronstein { base, .. }
+      | TimeControl::SimpleDelay { base, .. } => base,
+    }
+  }
+
+  ///
+  /// Time a move may take without reducing the clock: the increment, or the delay of delay controls.
+  ///
+  pub fn increment(&self) -> Duration
+  {
+    match *self
+    {
+      TimeControl::SuddenDeath { .. } => Duration::ZERO,
+      TimeControl::Fischer { increment, .. } => increment,
+      TimeControl::Bronstein { delay, .. } | TimeControl::SimpleDelay { delay, .. } => delay,
+    }
+  }
+
+  ///
+  /// Time to deduct from the clock when `elapsed` was spent on a move which is not finished yet.
+  ///
+  fn charge(&self, elapsed : Duration) -> Duration
+  {
+    match *self
+    {
+      TimeControl::SimpleDelay { delay, .. } => elapsed.saturating_sub(delay),
+      _ => elapsed,
+    }
+  }
+
+  ///
+  /// Time to add to the clock when the move which took `elapsed` is finished.
+  ///
+  fn bonus(&self, elapsed : Duration) -> Duration
+  {
+    match *self
+    {
+      TimeControl::Fischer { increment, .. } => increment,
+      TimeControl::Bronstein { delay, .. } => elapsed.min(delay),
+      _ => Duration::ZERO,
+    }
+  }
+}
+
+///
+/// Formats time control in the form accepted by [TimeControl::from_str]: minutes of base time,
+/// then seconds of increment or delay after "+" ( Fischer ), "b" ( Bronstein ) or "d" ( simple delay ).
+///
+
+impl fmt::Display for TimeControl
+{
+  fn fmt(&self, f : &mut fmt::Formatter<'_>) -> fmt::Result
+  {
+    let minutes = self.base().as_secs_f64() / 60.0;
+    match *self
+    {
+      TimeControl::SuddenDeath { .. } => write!(f, "{}", minutes),
+      TimeControl::Fischer { increment, .. } => write!(f, "{}+{}", minutes, increment.as_secs_f64()),
+      TimeControl::Bronstein { delay, .. } => write!(f, "{}b{}", minutes, delay.as_secs_f64()),
+      TimeControl::SimpleDelay { delay, .. } => write!(f, "{}d{}", minutes, delay.as_secs_f64()),
+    }
+  }
+}
+
+///
+/// Error of parsing [TimeControl]. Holds the text which was parsed.
+///
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TimeControlParseError(pub String);
+
+impl fmt::Display for TimeControlParseError
+{
+  fn fmt(&self, f : &mut fmt::Formatter<'_>) -> fmt::Result
+  {
+    write!(f, "'{}' is not a time control, expected for example '5', '3+2', '5b3' or '5d3'", self.0)
+  }
+}
+
+impl std::error::Error for TimeControlParseError {}
+
+impl FromStr for TimeControl
+{
+  type Err = TimeControlParseError;
+
+  ///
+  /// Parses time control like "5" ( 5 minutes ), "3+2" ( 3 minutes, 2 seconds increment ),
+  /// "5b3" ( Bronstein delay of 3 seconds ) or "5d3" ( simple delay of 3 seconds ).
+  ///
+  fn from_str(src : &str) -> Result<Self, Self::Err>
+  {
+    let error = || TimeControlParseError(src.to_string());
+    let seconds = |text : &str| -> Result<Duration, TimeControlParseError>
+    {
+      let value : f64 = text.trim().parse().map_err(|_| error())?;
+      Duration::try_from_secs_f64(value).map_err(|_| error())
+    };
+
+    let text = src.trim();
+    let (base, extra) = match text.find(['+', 'b', 'd'])
+    {
+      Some(pos) => (&text[..pos], Some((&text[pos..pos + 1], &text[pos + 1..]))),
+      None => (text, None),
+    };
+    let base : f64 = base.trim().parse().map_err(|_| error())?;
+    let base = Duration::try_from_secs_f64(base * 60.0).map_err(|_| error())?;
+    if base.is_zero()
+    {
+      return Err(error());
+    }
+
+    Ok(match extra
+    {
+      None => TimeControl::SuddenDeath { base },
+      Some(("+", increment)) => TimeControl::Fischer { base, increment : seconds(increment)? },
+      Some(("b", delay)) => TimeControl::Bronstein { base, delay : seconds(delay)? },
+      Some((_, delay)) => TimeControl::SimpleDelay { base, delay : seconds(delay)? },
+    })
+  }
+}
+
+///
+/// Chess clock of two players.
+/// Only the clock of the player on move runs. Pressing the clock finishes the move, applies the time control
+/// and starts the clock of the opponent. The player whose time is over has flagged.
+///
+/// Serialized clock keeps the time used on the current move and is paused after deserialization.
+///
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(from = "ClockState", into = "ClockState")]
+pub struct Clock
+{
+  control : TimeControl,
+  /// Time left of each player at the beginning of the current move, indexed by [Player].
+  remaining : [Duration; 2],
+  /// Player whose clock runs or is paused, `None` if the clock is stopped.
+  active : Option<Player>,
+  /// Time used on the current move before the last pause.
+  used : Duration,
+  /// Reading of the time source when the clock was started or resumed, `None` if the clock is paused.
+  started : Option<Duration>,
+  time_source : Arc<dyn TimeSource>,
+}
+
+impl Clock
+{
+  ///
+  /// Constructs stopped clock with the system time source.
+  ///
+  pub fn new(control : TimeControl) -> Self
+  {
+    let base = control.base();
+    Self {
+      control,
+      remaining : [base, base],
+      active : None,
+      used : Duration::ZERO,
+      started : None,
+      time_source : Arc::new(SystemTimeSource::new()),
+    }
+  }
+
+  ///
+  /// Replaces source of time. Time used on the current move is kept.
+  ///
+  pub fn with_time_source(mut self, time_source : Arc<dyn TimeSource>) -> Self
+  {
+    if self.started.is_some()
+    {
+      self.used = self.used_on_move();
+      self.started = Some(time_source.now());
+    }
+    self.time_source = time_source;
+    self
+  }
+
+  ///
+  /// Time control of the clock.
+  ///
+  pub fn control(&self) -> TimeControl { self.control }
+
+  ///
+  /// Player whose clock runs or is paused. `None` if the clock is stopped.
+  ///
+  pub fn active(&self) -> Option<Player> { self.active }
+
+  ///
+  /// Clock of the active player is stopped for a while.
+  ///
+  pub fn is_paused(&self) -> bool { self.active.is_some() && self.started.is_none() }
+
+  ///
+  /// Time used on the current move.
+  ///
+  pub fn used_on_move(&self) -> Duration
+  {
+    match self.started
+    {
+      Some(started) => self.used + self.time_source.now().saturating_sub(started),
+      None => self.used,
+    }
+  }
+
+  ///
+  /// Time left of the player. Time of the move being thought on is deducted, delay which is not used yet is not included.
+  ///
+  pub fn remaining(&self, player : Player) -> Duration
+  {
+    let remaining = self.remaining[player as usize];
+    if self.active == Some(player)
+    {
+      remaining.saturating_sub(self.control.charge(self.used_on_move()))
+    }
+    else
+    {
+      remaining
+    }
+  }
+
+  ///
+  /// Player whose time is over.
+  ///
+  pub fn flagged(&self) -> Option<Player>
+  {
+    [Player::White, Player::Black].into_iter().find(|player| self.remaining(*player) == Duration::ZERO)
+  }
+
+  ///
+  /// Starts clock of the player. Time used by the previously active player is deducted without increment or delay.
+  ///
+  pub fn start(&mut self, player : Player)
+  {
+    self.stop();
+    self.active = Some(player);
+    self.started = Some(self.time_source.now());
+  }
+
+  ///
+  /// Finishes the move of the active player and starts the clock of the opponent.
+  /// Applies the increment or delay, unless the player has flagged.
+  ///
+  pub fn press(&mut self)
+  {
+    let player = match self.active
+    {
+      Some(player) => player,
+      None => return,
+    };
+    let elapsed = self.used_on_move();
+    let remaining = self.remaining(player);
+    if remaining > Duration::ZERO
+    {
+      self.remaining[player as usize] = remaining + self.control.bonus(elapsed);
+    }
+    else
+    {
+      self.remaining[player as usize] = Duration::ZERO;
+    }
+    self.used = Duration::ZERO;
+    self.active = Some(player.other_player());
+    self.started = Some(self.time_source.now());
+  }
+
+  ///
+  /// Stops the clock of the active player for a while.
+  ///
+  pub fn pause(&mut self)
+  {
+    self.used = self.used_on_move();
+    self.started = None;
+  }
+
+  ///
+  /// Continues the paused clock.
+  ///
+  pub fn resume(&mut self)
+  {
+    if self.is_paused()
+    {
+      self.started = Some(self.time_source.now());
+    }
+  }
+
+  ///
+  /// Stops the clock. Time used by the active player is deducted without increment or delay.
+  ///
+  pub fn stop(&mut self)
+  {
+    if let Some(player) = self.active
+    {
+      self.remaining[player as usize] = self.remaining(player);
+    }
+    self.active = None;
+    self.used = Duration::ZERO;
+    self.started = None;
+  }
+}
+
+///
+/// Serialized form of [Clock].
+///
+
+#[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ClockState
+{
+  control : TimeControl,
+  remaining : [Duration; 2],
+  /// Index of the active player.
+  active : Option<u8>,
+  used : Duration,
+}
+
+impl From<Clock> for ClockState
+{
+  fn from(src : Clock) -> Self
+  {
+    Self {
+      control : src.control,
+      remaining : src.remaining,
+      active : src.active.map(|player| player as u8),
+      used : src.used_on_move(),
+    }
+  }
+}
+
+impl From<ClockState> for Clock
+{
+  fn from(src : ClockState) -> Self
+  {
+    let mut clock = Clock::new(src.control);
+    clock.remaining = src.remaining;
+    clock.active = src.active.map(|index| if index == Player::White as u8 { Player::White } else { Player::Black });
+    clock.used = src.used;
+    clock
+  }
+}
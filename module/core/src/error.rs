@@ -0,0 +1,150 @@
+//!
+//! Errors of the game mechanics.
+//!
+
+use super::{GameStatus, UCIParseError, SANParseError};
+
+///
+/// Error of any operation on [Board](super::Board) or [Game](super::Game).
+///
+
+#[derive(Debug)]
+pub enum Error
+{
+  ///
+  /// FEN string can't be parsed. Holds the FEN and the reason.
+  ///
+  InvalidFen(String, String),
+  ///
+  /// Chess960 start positions are numbered from 0 to 959.
+  ///
+  InvalidChess960Index(u16),
+  ///
+  /// The move can't be made in the position.
+  ///
+  IllegalMove(UCIParseError),
+  ///
+  /// The move in SAN can't be parsed or made in the position.
+  ///
+  InvalidSan(SANParseError),
+  ///
+  /// The game is finished, no more moves can be made.
+  ///
+  GameOver(GameStatus),
+  ///
+  /// There are no moves to take back.
+  ///
+  NothingToUndo,
+  ///
+  /// There are no taken back moves to make again.
+  ///
+  NothingToRedo,
+  ///
+  /// The game has no such half-move.
+  ///
+  InvalidPly(usize),
+  ///
+  /// Neither threefold repetition nor the fifty-move rule allows to claim a draw.
+  ///
+  DrawNotClaimable,
+  ///
+  /// The game has no clock.
+  ///
+  NoClock,
+  ///
+  /// The clock is paused, moves can't be made until it is resumed.
+  ///
+  ClockPaused,
+  ///
+  /// There is no saved game with the id.
+  ///
+  SaveNotFound(String),
+  ///
+  /// The save was written by a newer version of the game or is broken.
+  ///
+  UnsupportedSaveVersion(u64),
+  ///
+  /// The save doesn't have the layout of its version. Holds the reason.
+  ///
+  InvalidSave(String),
+  ///
+  /// The opening book doesn't have the layout of Polyglot books. Holds the reason.
+  ///
+  InvalidBook(String),
+  ///
+  /// The external engine failed or broke the protocol. Holds the reason.
+  ///
+  Engine(String),
+  ///
+  /// Failure of reading or writing a file.
+  ///
+  Io(std::io::Error),
+  ///
+  /// Failure of serialization or deserialization of the game.
+  ///
+  Serialization(serde_json::Error),
+}
+
+impl std::fmt::Display for Error
+{
+  fn fmt(&self, f : &mut std::fmt::Formatter<'_>) -> std::fmt::Result
+  {
+    match self
+    {
+      Error::InvalidFen(fen, reason) => write!(f, "invalid FEN '{}' : {}", fen, reason),
+      Error::InvalidChess960Index(index) => write!(f, "there is no Chess960 start position {}, use 0 to 959", index),
+      Error::IllegalMove(error) => write!(f, "illegal move : {}", error),
+      Error::InvalidSan(error) => write!(f, "illegal move : {}", error),
+      Error::GameOver(status) => write!(f, "the game is over : {:?}", status),
+      Error::NothingToUndo => write!(f, "there are no moves to undo"),
+      Error::NothingToRedo => write!(f, "there are no moves to redo"),
+      Error::InvalidPly(ply) => write!(f, "the game has no half-move {}", ply),
+      Error::DrawNotClaimable => write!(f, "draw can't be claimed in the position"),
+      Error::NoClock => write!(f, "the game has no clock"),
+      Error::ClockPaused => write!(f, "the clock is paused, resume it to make moves"),
+      Error::SaveNotFound(id) => write!(f, "there is no saved game '{}'", id),
+      Error::UnsupportedSaveVersion(version) => write!(f, "version {} of the save is not supported", version),
+      Error::InvalidSave(reason) => write!(f, "invalid save : {}", reason),
+      Error::InvalidBook(reason) => write!(f, "invalid opening book : {}", reason),
+      Error::Engine(reason) => write!(f, "engine error : {}", reason),
+      Error::Io(error) => write!(f, "i/o error : {}", error),
+      Error::Serialization(error) => write!(f, "serialization error : {}", error),
+    }
+  }
+}
+
+impl std::error::Error for Error
+{
+  fn source(&self) -> Option<&(dyn std::error::Error + 'static)>
+  {
+    match self
+    {
+      Error::IllegalMove(error) => Some(error),
+      Error::InvalidSan(error) => Some(error),
+      Error::Io(error) => Some(error),
+      Error::Serialization(error) => Some(error),
+      _ => None,
+    }
+  }
+}
+
+impl From<UCIParseError> for Error
+{
+  fn from(src : UCIParseError) -> Self { Error::IllegalMove(src) }
+}
+
+impl From<SANParseError> for Error
+{
+  fn from(src : SANParseError) -> Self { Error::InvalidSan(src) }
+}
+
+impl From<std::io::Error> for Error
+{
+  fn from(src : std::io::Error) -> Self { Error::Io(src) }
+}
+
+impl From<serde_json::Error> for Error
+{
+  fn from(src : serde_json::Error) -> Self { Error::Serialization(src) }
+}
+
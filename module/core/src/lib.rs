@@ -6,11 +6,23 @@
 //!
 
 pub mod ai;
-
-use std::fs;
-use std::fs::File;
-use std::io::Write;
-use std::path::Path;
+pub mod chess960;
+pub mod clock;
+pub mod error;
+pub mod san;
+pub mod pgn;
+pub mod perft;
+pub mod save;
+pub mod tablebase;
+pub mod zobrist;
+
+pub use clock::{Clock, TimeControl};
+pub use error::Error;
+pub use save::{SaveHeader, SaveInfo, SaveStore};
+pub use san::SANParseError;
+pub use tablebase::{Tablebase, TablebaseProbe, Wdl};
+
+use ai::Evaluator;
 use std::ops::Deref;
 #[cfg(not(target_arch = "wasm32"))]
 use std::time::{SystemTime, UNIX_EPOCH};
@@ -35,6 +47,8 @@ UCI( String )
 
 Board
   pleco_board : pleco::Board
+  castling : Option<CastlingRooks> // Chess960 only
+  last_move : Option<Move>
 
 HistoryEntry
   fen : String
@@ -54,9 +68,6 @@ Game
 
 */
 
-const SAVES_FOLDER_NAME : &str = "saves";
-const SAVE_FILE_EXTENSION : &str = ".save";
-
 ///
 /// Move in UCI format
 ///
@@ -74,13 +85,170 @@ impl From<Move> for UCI
   fn from(src : Move) -> Self { Self(src.stringify()) }
 }
 
+///
+/// Error of parsing a move in UCI format.
+///
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum UCIParseError
+{
+  ///
+  /// The move has neither 4 nor 5 characters. Holds the actual length.
+  ///
+  InvalidLength(usize),
+  ///
+  /// Source or destination is not a square name like "e2".
+  ///
+  InvalidSquare(String),
+  ///
+  /// Promotion suffix is not one of "q", "r", "b", "n".
+  ///
+  InvalidPromotion(char),
+  ///
+  /// Pawn reaches the last rank, but the promotion piece is not given.
+  ///
+  MissingPromotion,
+  ///
+  /// Promotion piece is given for a move that is not a promotion.
+  ///
+  UnexpectedPromotion,
+  ///
+  /// There is no piece on the source square.
+  ///
+  EmptySource(Cell),
+  ///
+  /// The piece on the source square belongs to the opponent.
+  ///
+  NotYourPiece(Cell),
+  ///
+  /// The move is well-formed, but is not legal in the position.
+  ///
+  IllegalMove(String),
+}
+
+impl std::fmt::Display for UCIParseError
+{
+  fn fmt(&self, f : &mut std::fmt::Formatter<'_>) -> std::fmt::Result
+  {
+    match self
+    {
+      UCIParseError::InvalidLength(len) => write!(f, "UCI move should have 4 or 5 characters, got {}", len),
+      UCIParseError::InvalidSquare(square) => write!(f, "'{}' is not a square", square),
+      UCIParseError::InvalidPromotion(piece) => write!(f, "'{}' is not a promotion piece, expected one of q, r, b, n", piece),
+      UCIParseError::MissingPromotion => write!(f, "promotion piece is missing, for example 'e7e8q'"),
+      UCIParseError::UnexpectedPromotion => write!(f, "the move is not a promotion"),
+      UCIParseError::EmptySource(cell) => write!(f, "there is no piece on {}", cell),
+      UCIParseError::NotYourPiece(cell) => write!(f, "the piece on {} belongs to the opponent", cell),
+      UCIParseError::IllegalMove(uci) => write!(f, "move {} is illegal", uci),
+    }
+  }
+}
+
+impl std::error::Error for UCIParseError {}
+
+const MOVE_FLAG_PROMOTION : u16 = 0b1000;
+const MOVE_FLAG_SHIFT : u16 = 12;
+
+impl UCI
+{
+  ///
+  /// Checks syntax of the move. Returns source, destination and optional promotion piece.
+  /// Null move "0000" is not accepted here.
+  ///
+  pub fn parse(&self) -> Result<(Cell, Cell, Option<PieceType>), UCIParseError>
+  {
+    let src = self.0.trim();
+    let chars : Vec<char> = src.chars().collect();
+    if chars.len() != 4 && chars.len() != 5
+    {
+      return Err(UCIParseError::InvalidLength(chars.len()));
+    }
+
+    let from = cell_from_chars(chars[0], chars[1])?;
+    let to = cell_from_chars(chars[2], chars[3])?;
+    let promotion = match chars.get(4)
+    {
+      None => None,
+      Some('q') => Some(PieceType::Q),
+      Some('r') => Some(PieceType::R),
+      Some('b') => Some(PieceType::B),
+      Some('n') => Some(PieceType::N),
+      Some(piece) => return Err(UCIParseError::InvalidPromotion(*piece)),
+    };
+
+    Ok((from, to, promotion))
+  }
+
+  ///
+  /// Resolves the move against the board. Returns legal [Move] or the reason why the move can't be made.
+  ///
+  pub fn to_move(&self, board : &Board) -> Result<Move, UCIParseError>
+  {
+    let (from, to, promotion) = self.parse()?;
+
+    let piece = board.pleco_board.piece_at_sq(from);
+    match piece.player()
+    {
+      None => return Err(UCIParseError::EmptySource(from)),
+      Some(player) if player != board.current_turn() => return Err(UCIParseError::NotYourPiece(from)),
+      _ => (),
+    }
+
+    let path = format!("{}{}", from, to);
+    let candidates : Vec<Move> = board.legal_moves().into_iter().filter(|m| m.stringify().starts_with(&path)).collect();
+
+    let is_promotion = candidates.iter().any(|m| m.is_promo());
+    match (is_promotion, promotion)
+    {
+      (_, _) if candidates.is_empty() => Err(UCIParseError::IllegalMove(self.0.trim().to_string())),
+      (true, None) => Err(UCIParseError::MissingPromotion),
+      (false, Some(_)) => Err(UCIParseError::UnexpectedPromotion),
+      (true, Some(piece)) => candidates
+        .into_iter()
+        .find(|m| m.promo_piece() == piece)
+        .ok_or_else(|| UCIParseError::IllegalMove(self.0.trim().to_string())),
+      (false, None) => Ok(candidates[0]),
+    }
+  }
+}
+
+pub(crate) fn cell_from_chars(file : char, rank : char) -> Result<Cell, UCIParseError>
+{
+  match (file, rank)
+  {
+    ('a'..='h', '1'..='8') => Ok(Cell((rank as u8 - b'1') * 8 + (file as u8 - b'a'))),
+    _ => Err(UCIParseError::InvalidSquare(format!("{}{}", file, rank))),
+  }
+}
+
+///
+/// Converts move in UCI format to [Move] without a board.
+/// The result carries only squares and promotion piece, flags like capture or castling are not set.
+/// Use [Board::move_from_uci] to get a legal move of a position.
+///
+
 impl TryFrom<UCI> for Move
 {
-  type Error = ();
+  type Error = UCIParseError;
 
-  fn try_from(_src : UCI) -> Result<Self, Self::Error>
+  fn try_from(src : UCI) -> Result<Self, Self::Error>
   {
-    unimplemented!();
+    if src.0.trim() == "0000"
+    {
+      return Ok(Move::null());
+    }
+
+    let (from, to, promotion) = src.parse()?;
+    let flags = match promotion
+    {
+      None => 0,
+      Some(PieceType::N) => MOVE_FLAG_PROMOTION,
+      Some(PieceType::B) => MOVE_FLAG_PROMOTION | 1,
+      Some(PieceType::R) => MOVE_FLAG_PROMOTION | 2,
+      Some(_) => MOVE_FLAG_PROMOTION | 3,
+    };
+
+    Ok(Move::new(from.0 as u16 | (to.0 as u16) << 6 | flags << MOVE_FLAG_SHIFT))
   }
 }
 
@@ -92,6 +260,9 @@ impl TryFrom<UCI> for Move
 pub struct Board
 {
   pleco_board : pleco::Board,
+  /// Castling rooks of Chess960 board, `None` if pleco handles castling.
+  castling : Option<chess960::CastlingRooks>,
+  last_move : Option<Move>,
 }
 
 impl Board
@@ -103,90 +274,91 @@ impl Board
   {
     Self {
       pleco_board : pleco::Board::start_pos(),
+      castling : None,
+      last_move : None,
     }
   }
 
   ///
-  /// Constructs a board from FEN
+  /// Constructs a board from FEN. Castling rights can be given in X-FEN or Shredder-FEN for Chess960 positions.
   ///
-  pub fn from_fen(fen : &Fen) -> Self
+  pub fn from_fen(fen : &Fen) -> Result<Self, Error>
   {
-    match pleco::Board::from_fen(fen)
+    let (castling, pleco_castling) = chess960::parse_castling(fen).map_err(|error| Error::InvalidFen(fen.to_string(), error))?;
+    match pleco::Board::from_fen(&chess960::replace_field(fen, 2, &pleco_castling))
     {
-      Ok(pleco_board) => Self { pleco_board },
-      _ => Self::default(),
+      Ok(pleco_board) => Ok(Self { pleco_board, castling, last_move : None }),
+      Err(error) => Err(Error::InvalidFen(fen.to_string(), format!("{:?}", error))),
     }
   }
 
   ///
   /// Makes move on the board. Accepts move in UCI format.
+  /// Returns new board, the board itself is not changed.
+  ///
+  pub fn make_move(&self, uci_move : UCI) -> Result<Self, Error>
+  {
+    let m = self.move_from_uci(uci_move)?;
+    let mut board = self.clone();
+    board.apply(m);
+    Ok(board)
+  }
+
+  ///
+  /// Returns all legal moves of the side to move. Castling of Chess960 board is the king taking its own rook.
+  ///
+  pub fn legal_moves(&self) -> Vec<Move>
+  {
+    let mut moves : Vec<Move> = self.pleco_board.generate_moves().into_iter().collect();
+    moves.extend(self.chess960_castling_moves());
+    moves
+  }
+
+  ///
+  /// True if the move is castling, classical or Chess960.
+  ///
+  pub fn is_castling(&self, m : Move) -> bool { m.is_castle() || self.is_chess960_castling(m) }
+
   ///
-  pub fn make_move(&mut self, uci_move : UCI) -> Option<Self>
+  /// Applies legal move to the board.
+  ///
+  pub(crate) fn apply(&mut self, m : Move)
   {
-    let mut pleco_board : pleco::Board = self.pleco_board.clone();
-    let result = pleco_board.apply_uci_move(&uci_move.0);
-    if result
+    if self.castling.is_some()
     {
-      Some(Self { pleco_board })
+      self.apply_chess960(m);
     }
     else
     {
-      None
+      self.pleco_board.apply_move(m);
     }
+    self.last_move = Some(m);
   }
 
   ///
   /// Checks if the move is valid. Accepts move in UCI format.
+  /// Returns the reason if the move is not valid.
   ///
-  pub fn move_is_valid(&self, uci_move : UCI) -> bool
-  {
-    match self.move_from_uci(uci_move)
-    {
-      Some(m) => self.pleco_board.pseudo_legal_move(m) && self.pleco_board.legal_move(m),
-      _ => false,
-    }
-  }
+  pub fn move_is_valid(&self, uci_move : UCI) -> Result<(), UCIParseError> { self.move_from_uci(uci_move).map(|_| ()) }
 
   ///
   /// Looks for a valid [Move](https://docs.rs/pleco/0.5.0/pleco/core/piece_move/struct.BitMove.html) from move in UCI format.
   ///
-  pub fn move_from_uci(&self, uci_move : UCI) -> Option<Move>
-  {
-    let all_moves : MoveList = self.pleco_board.generate_moves();
-    all_moves.iter().find(|m| m.stringify() == uci_move.0).cloned()
-  }
+  pub fn move_from_uci(&self, uci_move : UCI) -> Result<Move, UCIParseError> { uci_move.to_move(self) }
 
   ///
   /// Looks for a move that results in the best board state for the current player and applies it
   ///
-  pub fn make_move_ai(&mut self)
-  {
-    let turn = self.pleco_board.turn();
-
-    let best_move = self
-      .pleco_board
-      .generate_moves()
-      .into_iter()
-      .map(|m| {
-        self.pleco_board.apply_move(m);
-        let score = pleco::tools::eval::Eval::eval_low(&self.pleco_board);
-        self.pleco_board.undo_move();
-        (m, score)
-      })
-      .max_by(|(_, a), (_, b)| {
-        if turn == Player::Black
-        {
-          a.cmp(b)
-        }
-        else
-        {
-          b.cmp(a)
-        }
-      })
-      .unwrap()
-      .0;
+  pub fn make_move_ai(&mut self) -> Result<(), Error>
+  {
+    if let Some(status) = self.status_final()
+    {
+      return Err(Error::GameOver(status));
+    }
 
-    self.pleco_board.apply_move(best_move);
+    let best_move = ai::greedy_move(self, &ai::DefaultEvaluator);
+    self.apply(best_move);
+    Ok(())
   }
 
   ///
@@ -195,14 +367,14 @@ impl Board
   pub fn piece_at(&self, sq : u8) -> Piece { self.pleco_board.piece_at_sq(Cell(sq)) }
 
   ///
-  /// Evaluates the score of a [Board] for the current side to move.
+  /// Evaluates the score of a [Board] in centipawns for the current side to move with [ai::DefaultEvaluator].
   ///
-  pub fn score(&self) -> i32
-  {
-    pleco::tools::eval::Eval::eval_low(&self.pleco_board)
-    //0
-    /* ttt : implement me */
-  }
+  pub fn score(&self) -> i32 { ai::DefaultEvaluator.evaluate(self) }
+
+  ///
+  /// True if the current side to move is in check.
+  ///
+  pub fn is_check(&self) -> bool { self.pleco_board.in_check() }
 
   ///
   /// True if the current side to move is in check mate.
@@ -212,7 +384,53 @@ impl Board
   ///
   /// Is the current side to move is in stalemate.
   ///
-  pub fn is_stalemate(&self) -> bool { self.pleco_board.stalemate() }
+  pub fn is_stalemate(&self) -> bool { !self.pleco_board.in_check() && self.legal_moves().is_empty() }
+
+  ///
+  /// Number of half-moves since the last capture or pawn move.
+  ///
+  pub fn halfmove_clock(&self) -> u32 { self.to_fen().split_whitespace().nth(4).and_then(|n| n.parse().ok()).unwrap_or(0) }
+
+  ///
+  /// True if neither side can checkmate: kings only, a single minor piece, or bishops on squares of one color.
+  ///
+  pub fn is_insufficient_material(&self) -> bool
+  {
+    let pieces : Vec<(Cell, PieceType)> = (0 .. 64)
+      .map(|sq| (Cell(sq), self.pleco_board.piece_at_sq(Cell(sq)).type_of()))
+      .filter(|(_, piece)| *piece != PieceType::None && *piece != PieceType::K)
+      .collect();
+
+    match pieces.as_slice()
+    {
+      [] => true,
+      [(_, PieceType::N)] | [(_, PieceType::B)] => true,
+      _ =>
+      {
+        let color = |cell : &Cell| (cell.0 / 8 + cell.0 % 8) % 2;
+        pieces.iter().all(|(_, piece)| *piece == PieceType::B) && pieces.iter().all(|(cell, _)| color(cell) == color(&pieces[0].0))
+      }
+    }
+  }
+
+  ///
+  /// Returns [GameStatus] if the position is final, otherwise `None`.
+  ///
+  fn status_final(&self) -> Option<GameStatus>
+  {
+    if self.is_checkmate()
+    {
+      Some(GameStatus::Checkmate)
+    }
+    else if self.is_stalemate()
+    {
+      Some(GameStatus::Stalemate)
+    }
+    else
+    {
+      None
+    }
+  }
 
   ///
   /// Return the `Player` whose turn it is to move.
@@ -222,7 +440,7 @@ impl Board
   ///
   /// Return the last move played, if any.
   ///
-  pub fn last_move(&self) -> Option<Move> { self.pleco_board.last_move() }
+  pub fn last_move(&self) -> Option<Move> { self.last_move }
 
   ///
   /// Returns pretty-printed string representation of the board
@@ -267,16 +485,23 @@ impl Board
   }
 
   ///
-  /// Creates a 'Fen` string of the board.
+  /// Creates a 'Fen` string of the board. Castling rights of Chess960 board are in X-FEN.
   ///
-  pub fn to_fen(&self) -> Fen { Fen::from(self.pleco_board.fen()) }
+  pub fn to_fen(&self) -> Fen
+  {
+    match &self.castling
+    {
+      Some(rooks) => self.to_x_fen(rooks),
+      None => Fen::from(self.pleco_board.fen()),
+    }
+  }
 }
 
 ///
 ///Positions on the board in [FEN](https://www.chess.com/terms/fen-chess#what-is-fen) format
 ///
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
 pub struct FenString(String);
 
 impl Deref for FenString
@@ -301,16 +526,66 @@ pub type Fen = FenString;
 /// Contains information about move made in the past.
 /// Field `fen` contains representation of the board as FEN string
 /// Field `last_move` information about last [Move]https://docs.rs/pleco/0.5.0/pleco/core/piece_move/struct.BitMove.html)
+/// Field `hash` is the key of the position after the move, see [Board::hash]. It's not serialized, but restored from `fen`.
+/// Field `engine_move` tells whether the move was made by the engine of the game.
 ///
 
 #[derive(Serialize, Deserialize, Debug)]
+#[serde(try_from = "HistoryEntryData")]
 pub struct HistoryEntry
 {
   fen : Fen,
-  #[serde(serialize_with = "move_ser", deserialize_with = "move_der")]
+  #[serde(serialize_with = "move_ser")]
+  last_move : Move,
+  #[serde(skip_serializing)]
+  hash : u64,
+  engine_move : bool,
+}
+
+///
+/// Serialized form of [HistoryEntry].
+///
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct HistoryEntryData
+{
+  fen : Fen,
+  #[serde(deserialize_with = "move_der")]
   last_move : Move,
+  engine_move : bool,
+}
+
+impl TryFrom<HistoryEntryData> for HistoryEntry
+{
+  type Error = Error;
+
+  fn try_from(src : HistoryEntryData) -> Result<Self, Self::Error>
+  {
+    let hash = Board::from_fen(&src.fen)?.hash();
+    Ok(Self { fen : src.fen, last_move : src.last_move, hash, engine_move : src.engine_move })
+  }
+}
+
+///
+/// Search of the engine pondering on the position after the expected reply of the opponent.
+///
+
+#[cfg(not(target_arch = "wasm32"))]
+#[derive(Debug)]
+struct Ponder
+{
+  /// Position after the expected reply.
+  fen : Fen,
+  handle : ai::SearchHandle,
 }
 
+///
+/// True if the engine answered the move of the player with the reply.
+///
+
+fn is_reply(m : &HistoryEntry, reply : &HistoryEntry) -> bool { !m.engine_move && reply.engine_move }
+
 ///
 /// Serialize [Move](https://docs.rs/pleco/0.5.0/pleco/core/piece_move/struct.BitMove.html)
 ///
@@ -331,7 +606,7 @@ pub fn move_der<'de, D : Deserializer<'de>>(d : D) -> Result<Move, D::Error>
 /// Status of the game
 ///
 
-#[derive(Serialize, Deserialize, Debug, PartialEq, Eq)]
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
 pub enum GameStatus
 {
   /// The game is not finished, and the game is still in play.
@@ -342,6 +617,191 @@ pub enum GameStatus
   Stalemate,
   /// Forfeit
   GG,
+  /// A player ran out of time.
+  Timeout,
+  /// Players agreed to a draw.
+  DrawAgreed,
+  /// Draw claimed by a player after the same position occurred three times.
+  ThreefoldRepetition,
+  /// Draw because the same position occurred five times.
+  FivefoldRepetition,
+  /// Draw claimed by a player after fifty moves of each side without a capture or a pawn move.
+  FiftyMoveRule,
+  /// Draw after seventy five moves of each side without a capture or a pawn move.
+  SeventyFiveMoveRule,
+  /// Draw because neither player has enough pieces to checkmate.
+  InsufficientMaterial,
+}
+
+impl GameStatus
+{
+  ///
+  /// True if the game is drawn.
+  ///
+  pub fn is_draw(&self) -> bool
+  {
+    !matches!(self, GameStatus::Continuing | GameStatus::Checkmate | GameStatus::GG | GameStatus::Timeout)
+  }
+}
+
+impl From<Termination> for GameStatus
+{
+  fn from(src : Termination) -> Self
+  {
+    match src
+    {
+      Termination::Checkmate => GameStatus::Checkmate,
+      Termination::Resignation => GameStatus::GG,
+      Termination::Timeout => GameStatus::Timeout,
+      Termination::Stalemate => GameStatus::Stalemate,
+      Termination::Agreement => GameStatus::DrawAgreed,
+      Termination::ThreefoldRepetition => GameStatus::ThreefoldRepetition,
+      Termination::FivefoldRepetition => GameStatus::FivefoldRepetition,
+      Termination::FiftyMoveRule => GameStatus::FiftyMoveRule,
+      Termination::SeventyFiveMoveRule => GameStatus::SeventyFiveMoveRule,
+      Termination::InsufficientMaterial => GameStatus::InsufficientMaterial,
+    }
+  }
+}
+
+///
+/// Reason why the game is finished.
+///
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Termination
+{
+  /// The king of the loser is checkmated.
+  Checkmate,
+  /// The loser resigned.
+  Resignation,
+  /// The loser ran out of time.
+  Timeout,
+  /// The player on move has no legal moves and is not in check.
+  Stalemate,
+  /// Players agreed to a draw.
+  Agreement,
+  /// Draw claimed after the same position occurred three times.
+  ThreefoldRepetition,
+  /// The same position occurred five times.
+  FivefoldRepetition,
+  /// Draw claimed after fifty moves of each side without a capture or a pawn move.
+  FiftyMoveRule,
+  /// Seventy five moves of each side without a capture or a pawn move.
+  SeventyFiveMoveRule,
+  /// Neither player has enough pieces to checkmate.
+  InsufficientMaterial,
+}
+
+impl std::fmt::Display for Termination
+{
+  fn fmt(&self, f : &mut std::fmt::Formatter<'_>) -> std::fmt::Result
+  {
+    let text = match self
+    {
+      Termination::Checkmate => "checkmate",
+      Termination::Resignation => "resignation",
+      Termination::Timeout => "timeout",
+      Termination::Stalemate => "stalemate",
+      Termination::Agreement => "agreement",
+      Termination::ThreefoldRepetition => "threefold repetition",
+      Termination::FivefoldRepetition => "fivefold repetition",
+      Termination::FiftyMoveRule => "fifty-move rule",
+      Termination::SeventyFiveMoveRule => "seventy-five-move rule",
+      Termination::InsufficientMaterial => "insufficient material",
+    };
+    write!(f, "{}", text)
+  }
+}
+
+///
+/// Result of the game: the winner and the reason why the game is finished.
+///
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GameResult
+{
+  /// The game is not finished.
+  Ongoing,
+  /// White won.
+  WhiteWins(Termination),
+  /// Black won.
+  BlackWins(Termination),
+  /// The game is drawn.
+  Draw(Termination),
+}
+
+impl GameResult
+{
+  ///
+  /// Result of the game won by `winner`.
+  ///
+  pub fn win(winner : Player, termination : Termination) -> Self
+  {
+    match winner
+    {
+      Player::White => GameResult::WhiteWins(termination),
+      Player::Black => GameResult::BlackWins(termination),
+    }
+  }
+
+  ///
+  /// Returns the winner, `None` for a draw or an ongoing game.
+  ///
+  pub fn winner(&self) -> Option<Player>
+  {
+    match self
+    {
+      GameResult::WhiteWins(_) => Some(Player::White),
+      GameResult::BlackWins(_) => Some(Player::Black),
+      _ => None,
+    }
+  }
+
+  ///
+  /// Returns the reason why the game is finished, `None` for an ongoing game.
+  ///
+  pub fn termination(&self) -> Option<Termination>
+  {
+    match self
+    {
+      GameResult::Ongoing => None,
+      GameResult::WhiteWins(termination) | GameResult::BlackWins(termination) | GameResult::Draw(termination) => Some(*termination),
+    }
+  }
+
+  ///
+  /// True if the game is finished.
+  ///
+  pub fn is_over(&self) -> bool { *self != GameResult::Ongoing }
+
+  ///
+  /// Result token of PGN: "1-0", "0-1", "1/2-1/2" or "*" for an ongoing game.
+  ///
+  pub fn pgn_token(&self) -> &'static str
+  {
+    match self
+    {
+      GameResult::Ongoing => "*",
+      GameResult::WhiteWins(_) => "1-0",
+      GameResult::BlackWins(_) => "0-1",
+      GameResult::Draw(_) => "1/2-1/2",
+    }
+  }
+}
+
+impl std::fmt::Display for GameResult
+{
+  fn fmt(&self, f : &mut std::fmt::Formatter<'_>) -> std::fmt::Result
+  {
+    match self
+    {
+      GameResult::Ongoing => write!(f, "the game is in progress"),
+      GameResult::WhiteWins(termination) => write!(f, "White wins by {}", termination),
+      GameResult::BlackWins(termination) => write!(f, "Black wins by {}", termination),
+      GameResult::Draw(termination) => write!(f, "draw by {}", termination),
+    }
+  }
 }
 
 ///
@@ -349,18 +809,41 @@ pub enum GameStatus
 ///
 /// Basically Board + History.
 ///
+/// Serialized as a save of the current version, saves of older versions are migrated when deserialized, see [save].
+///
 
 #[derive(Serialize, Deserialize, Debug)]
+#[serde(remote = "Self", deny_unknown_fields)]
 pub struct Game
 {
   #[serde(serialize_with = "board_ser", deserialize_with = "board_der")]
   board : Board,
-  is_forfeited : bool,
+  /// Position the game started from, `None` for the standard starting position.
+  start_fen : Option<Fen>,
   history : Vec<HistoryEntry>,
+  /// Moves taken back by undo, the last one is redone first.
+  #[serde(skip)]
+  redo_stack : Vec<HistoryEntry>,
+  /// Result declared by players: resignation, agreement, claimed draw or timeout.
+  #[serde(default)]
+  declared_result : Option<GameResult>,
+  /// PGN tags like "Event" or "White".
+  #[serde(default)]
+  tags : Vec<(String, String)>,
+  /// Clock of the game, `None` for a game without time control.
+  #[serde(default)]
+  clock : Option<Clock>,
   ///
   /// AI Engine responsible for finding best moves
   ///
   pub ai : Option<ai::Engine>,
+  /// Position after the last move of the engine and the reply of the player the engine expects in it.
+  #[serde(skip)]
+  expected_reply : Option<(Fen, Move)>,
+  /// Search of the engine on the expected reply, see [Game::start_ponder].
+  #[cfg(not(target_arch = "wasm32"))]
+  #[serde(skip)]
+  ponder : Option<Ponder>,
   #[cfg(not(target_arch = "wasm32"))]
   date : SystemTime, // unix timestamp
   #[cfg(target_arch = "wasm32")]
@@ -376,9 +859,16 @@ impl Game
   {
     Self {
       board : Board::default(),
+      start_fen : None,
       history : Vec::new(),
-      is_forfeited : false,
+      redo_stack : Vec::new(),
+      declared_result : None,
+      tags : Vec::new(),
+      clock : None,
       ai : None,
+      expected_reply : None,
+      #[cfg(not(target_arch = "wasm32"))]
+      ponder : None,
       #[cfg(not(target_arch = "wasm32"))]
       date : SystemTime::now(),
       #[cfg(target_arch = "wasm32")]
@@ -390,55 +880,281 @@ impl Game
   /// Constructs a new game from FEN.
   ///
 
-  pub fn from_fen(fen : &str) -> Self
+  pub fn from_fen(fen : &str) -> Result<Self, Error>
   {
-    Self {
-      board : Board::from_fen(&Fen::from(fen.to_owned())),
-      history : Vec::new(),
-      is_forfeited : false,
-      ai : None,
-
-      #[cfg(not(target_arch = "wasm32"))]
-      date : SystemTime::now(),
-      #[cfg(target_arch = "wasm32")]
-      date : js_sys::Date::now(),
-    }
+    let board = Board::from_fen(&Fen::from(fen.to_owned()))?;
+    Ok(Self::from_board(board))
   }
 
   ///
-  /// Generates moves list.
+  /// Constructs a new Chess960 game with start position by index from 0 to 959. See [Board::chess960].
+  /// The game gets PGN tag "Variant" with value "Chess960".
   ///
 
-  pub fn moves_list(&self) -> MoveList { self.board.pleco_board.generate_moves() }
-
-  /* xxx : ? */
+  pub fn chess960(index : u16) -> Result<Self, Error>
+  {
+    let mut game = Self::from_board(Board::chess960(index)?);
+    game.set_tag("Variant", "Chess960");
+    Ok(game)
+  }
 
   ///
-  /// Calling member board
+  /// Constructs a new Chess960 game with random start position.
   ///
 
-  pub fn count_score(&self) -> i32 { self.board.score() }
+  pub fn chess960_random() -> Self
+  {
+    let mut game = Self::from_board(Board::chess960_random());
+    game.set_tag("Variant", "Chess960");
+    game
+  }
+
+  fn from_board(board : Board) -> Self
+  {
+    Self {
+      start_fen : Some(board.to_fen()),
+      board,
+      history : Vec::new(),
+      redo_stack : Vec::new(),
+      declared_result : None,
+      tags : Vec::new(),
+      clock : None,
+      ai : None,
+      expected_reply : None,
+      #[cfg(not(target_arch = "wasm32"))]
+      ponder : None,
+
+      #[cfg(not(target_arch = "wasm32"))]
+      date : SystemTime::now(),
+      #[cfg(target_arch = "wasm32")]
+      date : js_sys::Date::now(),
+    }
+  }
+
+  ///
+  /// True if the game is Chess960: it has PGN tag "Variant" with value "Chess960" or castling of its start position follows Chess960 rules.
+  ///
+
+  pub fn is_chess960(&self) -> bool
+  {
+    self.tag("Variant").is_some_and(|variant| variant.eq_ignore_ascii_case("chess960"))
+      || self.start_board().is_ok_and(|board| board.is_chess960())
+  }
+
+  ///
+  /// Generates list of legal moves.
+  ///
+
+  pub fn moves_list(&self) -> Vec<Move> { self.board.legal_moves() }
+
+  /* xxx : ? */
+
+  ///
+  /// Evaluates the position in centipawns for the side to move with the evaluator of AI engine,
+  /// or with [ai::DefaultEvaluator] if the game has no AI.
+  ///
+
+  pub fn count_score(&self) -> i32 { self.evaluator().evaluate(&self.board) }
+
+  ///
+  /// Evaluation of the position term by term, see [Game::count_score].
+  ///
+
+  pub fn evaluation(&self) -> ai::Evaluation { self.evaluator().breakdown(&self.board) }
+
+  fn evaluator(&self) -> &dyn Evaluator
+  {
+    match &self.ai
+    {
+      Some(engine) => engine.evaluator(),
+      None => &ai::DefaultEvaluator,
+    }
+  }
 
   ///
   /// Makes a move on the board. Accepts move in UCI format. For example, "e2e4".
-  /// Updates histort and returns `true` if move was succesfuly applied, otherwise returns `false`.
+  /// Updates history if move was succesfuly applied, otherwise returns the reason of fail.
   /// The board and history are not changed in case of fail.
   ///
 
-  pub fn make_move(&mut self, uci_move : UCI) -> bool
+  pub fn make_move(&mut self, uci_move : UCI) -> Result<(), Error>
   {
-    let new_board = self.board.make_move(uci_move);
-    let success = new_board.is_some();
-    if success
+    self.ensure_can_move()?;
+    self.board = self.board.make_move(uci_move)?;
+    self.history_push(false);
+    Ok(())
+  }
+
+  ///
+  /// Makes a move on the board. Accepts move in SAN. For example, "Nf3" or "O-O".
+  /// Updates history if move was succesfuly applied, otherwise returns the reason of fail.
+  ///
+
+  pub fn make_move_san(&mut self, san : &str) -> Result<(), Error>
+  {
+    self.ensure_continuing()?;
+    let m = self.board.move_from_san(san)?;
+    self.make_move(UCI::from(m))
+  }
+
+  fn ensure_continuing(&self) -> Result<(), Error>
+  {
+    match self.status()
     {
-      self.board = new_board.unwrap();
-      let last_move = self.board.last_move().unwrap();
-      self.history.push(HistoryEntry {
-        fen : self.board.to_fen(),
-        last_move,
-      });
+      GameStatus::Continuing => Ok(()),
+      status => Err(Error::GameOver(status)),
+    }
+  }
+
+  fn ensure_can_move(&self) -> Result<(), Error>
+  {
+    self.ensure_continuing()?;
+    match &self.clock
+    {
+      Some(clock) if clock.is_paused() => Err(Error::ClockPaused),
+      _ => Ok(()),
+    }
+  }
+
+  fn history_push(&mut self, engine_move : bool)
+  {
+    let last_move = self.board.last_move().unwrap();
+    self.history.push(HistoryEntry {
+      fen : self.board.to_fen(),
+      last_move,
+      hash : self.board.hash(),
+      engine_move,
+    });
+    self.redo_stack.clear();
+
+    if let Some(clock) = &mut self.clock
+    {
+      clock.press();
+    }
+    if self.result().is_over()
+    {
+      self.stop_clock();
+    }
+  }
+
+  ///
+  /// Finishes the game at the current position. Moves taken back can't be made again after it.
+  ///
+  fn declare_result(&mut self, result : GameResult)
+  {
+    self.declared_result = Some(result);
+    self.redo_stack.clear();
+    self.stop_clock();
+  }
+
+  fn stop_clock(&mut self)
+  {
+    if let Some(clock) = &mut self.clock
+    {
+      clock.stop();
+    }
+  }
+
+  ///
+  /// Returns number of half-moves made in the game.
+  ///
+  pub fn ply(&self) -> usize { self.history.len() }
+
+  ///
+  /// Takes back the last move. If the last move is the reply of AI to the move of the player,
+  /// takes back both, so the player is on move again.
+  /// The moves can be made again with [Game::redo] until a new move is made.
+  /// A resignation, an agreed or claimed draw and a loss on time can't be taken back.
+  ///
+  pub fn undo(&mut self) -> Result<(), Error>
+  {
+    if self.history.is_empty()
+    {
+      return Err(Error::NothingToUndo);
     }
-    success
+    if self.declared_result.is_some()
+    {
+      return Err(Error::GameOver(self.status()));
+    }
+    let plies = match self.history.as_slice()
+    {
+      [.., m, reply] if is_reply(m, reply) => 2,
+      _ => 1,
+    };
+    self.goto_ply(self.history.len() - plies)
+  }
+
+  ///
+  /// Makes again the move taken back by [Game::undo]. If the move was answered by AI, makes again the reply too.
+  ///
+  pub fn redo(&mut self) -> Result<(), Error>
+  {
+    if self.redo_stack.is_empty()
+    {
+      return Err(Error::NothingToRedo);
+    }
+    let plies = match self.redo_stack.as_slice()
+    {
+      [.., reply, m] if is_reply(m, reply) => 2,
+      _ => 1,
+    };
+    self.goto_ply(self.history.len() + plies)
+  }
+
+  ///
+  /// Restores the position after `ply` half-moves, 0 is the starting position.
+  /// Moves after the position are kept and can be restored with [Game::redo] or [Game::goto_ply].
+  /// A declared result is cleared only if the position is before it.
+  ///
+  pub fn goto_ply(&mut self, ply : usize) -> Result<(), Error>
+  {
+    if ply > self.history.len() + self.redo_stack.len()
+    {
+      return Err(Error::InvalidPly(ply));
+    }
+    if ply < self.history.len()
+    {
+      self.declared_result = None;
+    }
+
+    while self.history.len() > ply
+    {
+      let entry = self.history.pop().unwrap();
+      self.redo_stack.push(entry);
+    }
+    while self.history.len() < ply
+    {
+      let entry = self.redo_stack.pop().unwrap();
+      self.history.push(entry);
+    }
+
+    let mut board = self.start_board()?;
+    for entry in &self.history
+    {
+      board.apply(entry.last_move);
+    }
+    self.board = board;
+
+    let turn = self.current_turn();
+    let over = self.result().is_over();
+    if let Some(clock) = &mut self.clock
+    {
+      let paused = clock.is_paused();
+      if over
+      {
+        clock.stop();
+      }
+      else if clock.active() != Some(turn)
+      {
+        clock.start(turn);
+        if paused
+        {
+          clock.pause();
+        }
+      }
+    }
+
+    Ok(())
   }
 
   ///
@@ -447,29 +1163,165 @@ impl Game
   pub fn has_ai(&self) -> bool { self.ai.is_some() }
 
   ///
-  /// AI makes the move using internal AI algorithm
-  /// Updates history with the applied move.
+  /// AI makes the move using internal AI algorithm within the limits.
+  /// The depth of the engine is used if the limits have no depth, the clock of the game if the limits have no clock.
+  /// Updates history with the applied move. A null or illegal move of the engine is an error, the game is not changed.
+  /// If the engine ponders on the move just played, see [Game::start_ponder], the pondering search is finished instead,
+  /// otherwise the pondering is stopped.
   ///
-  pub fn make_move_ai(&mut self)
+  pub fn make_move_ai(&mut self, limits : ai::Limits) -> Result<(), Error>
   {
+    self.ensure_can_move()?;
+    let pondered = self.ponder_result();
     match &self.ai
     {
-      Some(engine) => self.board.pleco_board.apply_move(engine.best_move(self.board.clone())),
-      None => self.board.make_move_ai(),
+      Some(engine) =>
+      {
+        let (m, reply) = match pondered
+        {
+          Some(pondered) => pondered,
+          None =>
+          {
+            let limits = self.engine_limits(engine, limits, self.current_turn());
+            let mut reply = None;
+            let m = engine.search(self.board.clone(), limits, ai::CancelHandle::new(), |info|
+            {
+              if info.multipv == 1
+              {
+                reply = info.ponder_move();
+              }
+            });
+            (m, reply)
+          }
+        };
+        if m.is_null() || !self.board.legal_moves().contains(&m)
+        {
+          return Err(Error::Engine(format!("the engine played illegal move '{}'", m.stringify())));
+        }
+        self.board.apply(m);
+        self.expected_reply = reply.map(|reply| (self.board.to_fen(), reply));
+      }
+      None => self.board.make_move_ai()?,
     };
 
-    let last_move = self.board.last_move().unwrap();
-    self.history.push(HistoryEntry {
-      fen : self.board.to_fen(),
-      last_move,
-    });
+    self.history_push(true);
+    Ok(())
+  }
+
+  ///
+  /// Limits of the engine for the move of the player: the depth of the engine if the limits have no depth,
+  /// the clock of the player if the limits have no clock.
+  ///
+  fn engine_limits(&self, engine : &ai::Engine, mut limits : ai::Limits, player : Player) -> ai::Limits
+  {
+    if limits.depth.is_none()
+    {
+      limits.depth = Some(engine.depth);
+    }
+    if let (None, Some(clock)) = (limits.clock, &self.clock)
+    {
+      limits.clock = Some(ai::TimeLeft {
+        remaining : clock.remaining(player),
+        increment : clock.control().increment(),
+        moves_to_go : None,
+      });
+    }
+    limits
+  }
+
+  ///
+  /// Reply to the last move of the engine it expects from the opponent, the second move of its principal variation.
+  /// `None` if the engine has not told it or a move was made or taken back since.
+  ///
+  pub fn expected_reply(&self) -> Option<Move>
+  {
+    match &self.expected_reply
+    {
+      Some((fen, reply)) if *fen == self.board.to_fen() => Some(*reply),
+      _ => None,
+    }
+  }
+
+  ///
+  /// Starts the engine pondering in its own thread: it searches the position after [Game::expected_reply] while the opponent thinks.
+  /// The limits apply to the move of the engine from the ponder hit. If the opponent plays the expected reply,
+  /// [Game::make_move_ai] finishes the pondering search, otherwise it stops the pondering and searches again.
+  /// Returns the expected reply, `None` if there is nothing to ponder on.
+  ///
+  #[cfg(not(target_arch = "wasm32"))]
+  pub fn start_ponder(&mut self, limits : ai::Limits) -> Option<Move>
+  {
+    self.stop_ponder();
+    if self.result().is_over()
+    {
+      return None;
+    }
+    let reply = self.expected_reply().filter(|reply| self.board.legal_moves().contains(reply))?;
+    let engine = self.ai.as_ref()?;
+    let mut board = self.board.clone();
+    board.apply(reply);
+    let limits = self.engine_limits(engine, limits, board.current_turn());
+    let handle = engine.start_ponder(board.clone(), limits);
+    self.ponder = Some(Ponder { fen : board.to_fen(), handle });
+    Some(reply)
   }
 
+  ///
+  /// True if the engine ponders, see [Game::start_ponder].
+  ///
+  #[cfg(not(target_arch = "wasm32"))]
+  pub fn is_pondering(&self) -> bool { self.ponder.is_some() }
+
+  ///
+  /// Stops the pondering of the engine without waiting for it.
+  ///
+  #[cfg(not(target_arch = "wasm32"))]
+  pub fn stop_ponder(&mut self)
+  {
+    if let Some(ponder) = self.ponder.take()
+    {
+      ponder.handle.cancel();
+    }
+  }
+
+  ///
+  /// Best move and expected reply of the pondering search if the opponent played the expected reply,
+  /// the search is finished by its limits. Otherwise the pondering is stopped and `None` returned.
+  ///
+  #[cfg(not(target_arch = "wasm32"))]
+  fn ponder_result(&mut self) -> Option<(Move, Option<Move>)>
+  {
+    let ponder = self.ponder.take()?;
+    if ponder.fen != self.board.to_fen()
+    {
+      ponder.handle.cancel();
+      return None;
+    }
+    ponder.handle.ponder_hit();
+    let mut reply = None;
+    while let Some(info) = ponder.handle.next_info()
+    {
+      if info.multipv == 1
+      {
+        reply = info.ponder_move();
+      }
+    }
+    Some((ponder.handle.wait(), reply))
+  }
+
+  #[cfg(target_arch = "wasm32")]
+  fn ponder_result(&mut self) -> Option<(Move, Option<Move>)> { None }
+
   ///
   /// Return the [Player] whose turn it is to move.
   ///
   pub fn current_turn(&self) -> Player { self.board.current_turn() }
 
+  ///
+  /// Returns the current position.
+  ///
+  pub fn board(&self) -> &Board { &self.board }
+
   ///
   /// Prints board to the terminal.
   ///
@@ -480,33 +1332,191 @@ impl Game
   ///
   pub fn history_print(&self)
   {
-    for mov in &self.history
+    match self.history_movetext()
+    {
+      Ok(movetext) => println!("{}", movetext),
+      Err(_) =>
+      {
+        for mov in &self.history
+        {
+          println!("{}", mov.last_move);
+        }
+      }
+    }
+  }
+
+  ///
+  /// Returns the board the game started from.
+  ///
+  pub fn start_board(&self) -> Result<Board, Error>
+  {
+    match &self.start_fen
     {
-      println!("{}", mov.last_move);
+      Some(fen) => Board::from_fen(fen),
+      None => Ok(Board::default()),
     }
   }
 
+  ///
+  /// Returns history of moves in SAN. For example: ["e4", "e5", "Nf3"].
+  ///
+  pub fn history_san(&self) -> Result<Vec<String>, Error>
+  {
+    let mut board = self.start_board()?;
+    let mut moves = Vec::with_capacity(self.history.len());
+    for entry in &self.history
+    {
+      moves.push(board.move_to_san(entry.last_move)?);
+      board.apply(entry.last_move);
+    }
+    Ok(moves)
+  }
+
+  ///
+  /// Returns history of moves in SAN with move numbers. For example: "1. e4 e5 2. Nf3".
+  ///
+  pub fn history_movetext(&self) -> Result<String, Error>
+  {
+    let start = self.start_board()?;
+    let mut number : u32 = start.to_fen().split_whitespace().nth(5).and_then(|n| n.parse().ok()).unwrap_or(1);
+    let mut turn = start.current_turn();
+
+    let mut movetext = String::new();
+    for (i, san) in self.history_san()?.iter().enumerate()
+    {
+      if turn == Player::White
+      {
+        movetext.push_str(&format!("{}. ", number));
+      }
+      else if i == 0
+      {
+        movetext.push_str(&format!("{}... ", number));
+      }
+      movetext.push_str(san);
+      movetext.push(' ');
+
+      if turn == Player::Black
+      {
+        number += 1;
+      }
+      turn = turn.other_player();
+    }
+
+    Ok(movetext.trim_end().to_string())
+  }
+
   ///
   /// Returns current game status as [GameStatus].
   ///
   pub fn status(&self) -> GameStatus
   {
-    if self.is_forfeited
+    match self.result().termination()
+    {
+      Some(termination) => termination.into(),
+      None => GameStatus::Continuing,
+    }
+  }
+
+  ///
+  /// Returns result of the game as [GameResult] with the winner and the reason why the game is finished.
+  ///
+  pub fn result(&self) -> GameResult
+  {
+    if let Some(result) = self.declared_result
+    {
+      return result;
+    }
+
+    if let Some(player) = self.clock.as_ref().and_then(Clock::flagged)
     {
-      return GameStatus::GG;
+      return GameResult::win(player.other_player(), Termination::Timeout);
     }
 
     if self.board.is_checkmate()
     {
-      return GameStatus::Checkmate;
+      return GameResult::win(self.current_turn().other_player(), Termination::Checkmate);
     }
 
     if self.board.is_stalemate()
     {
-      return GameStatus::Stalemate;
+      return GameResult::Draw(Termination::Stalemate);
+    }
+
+    if self.board.is_insufficient_material()
+    {
+      return GameResult::Draw(Termination::InsufficientMaterial);
+    }
+
+    if self.board.halfmove_clock() >= 150
+    {
+      return GameResult::Draw(Termination::SeventyFiveMoveRule);
+    }
+
+    if self.repetitions() >= 5
+    {
+      return GameResult::Draw(Termination::FivefoldRepetition);
     }
 
-    GameStatus::Continuing
+    GameResult::Ongoing
+  }
+
+  ///
+  /// Returns how many times the current position occurred in the game, including the current one.
+  /// Positions are equal if pieces, side to move, castling rights and en passant square are equal.
+  ///
+  pub fn repetitions(&self) -> usize
+  {
+    let current = self.board.hash();
+    self.hashes().into_iter().filter(|hash| *hash == current).count()
+  }
+
+  ///
+  /// Returns keys of positions of the game, see [Board::hash]. The first key is of the starting position,
+  /// then the key of the position after each half-move.
+  ///
+  pub fn hashes(&self) -> Vec<u64>
+  {
+    let start = self.start_board().map(|board| board.hash()).unwrap_or_default();
+    std::iter::once(start).chain(self.history.iter().map(|entry| entry.hash)).collect()
+  }
+
+  ///
+  /// Returns the draw the player on move can claim: [GameStatus::ThreefoldRepetition] or [GameStatus::FiftyMoveRule].
+  ///
+  pub fn draw_claimable(&self) -> Option<GameStatus>
+  {
+    if self.status() != GameStatus::Continuing
+    {
+      None
+    }
+    else if self.repetitions() >= 3
+    {
+      Some(GameStatus::ThreefoldRepetition)
+    }
+    else if self.board.halfmove_clock() >= 100
+    {
+      Some(GameStatus::FiftyMoveRule)
+    }
+    else
+    {
+      None
+    }
+  }
+
+  ///
+  /// Claims a draw by threefold repetition or by the fifty-move rule. Returns status of the drawn game.
+  ///
+  pub fn claim_draw(&mut self) -> Result<GameStatus, Error>
+  {
+    self.ensure_continuing()?;
+    let status = self.draw_claimable().ok_or(Error::DrawNotClaimable)?;
+    let termination = match status
+    {
+      GameStatus::ThreefoldRepetition => Termination::ThreefoldRepetition,
+      _ => Termination::FiftyMoveRule,
+    };
+    self.declare_result(GameResult::Draw(termination));
+    Ok(status)
   }
 
   ///
@@ -533,30 +1543,95 @@ impl Game
   pub fn piece_at(&self, sq : u8) -> Piece { self.board.piece_at(sq) }
 
   ///
-  /// Saves game to file
+  /// Saves game to a file in the default [SaveStore]. Returns path of the file.
   ///
-  pub fn save(&self) -> std::io::Result<String>
+  pub fn save(&self) -> Result<String, Error>
   {
-    fs::create_dir_all(SAVES_FOLDER_NAME)?;
+    let store = SaveStore::default();
+    let id = store.save(self)?;
+    Ok(store.path(&id).display().to_string())
+  }
 
-    let serialized = serde_json::to_string(&self).unwrap();
-    let file_id = get_unix_timestamp(None);
-    let filename = format!("{}/{}{}", SAVES_FOLDER_NAME, file_id, SAVE_FILE_EXTENSION);
-    let filepath = Path::new(&filename);
+  ///
+  /// Unix timestamp in seconds when the game was started.
+  ///
+  #[cfg(not(target_arch = "wasm32"))]
+  pub fn timestamp(&self) -> u64 { get_unix_timestamp(Some(self.date)) }
 
-    let mut file = File::create(filepath).unwrap();
+  ///
+  /// Unix timestamp in seconds when the game was started.
+  ///
+  #[cfg(target_arch = "wasm32")]
+  pub fn timestamp(&self) -> u64 { (self.date / 1000.0) as u64 }
 
-    match file.write_all(serialized.as_bytes())
+  ///
+  /// Gives ability to forfeit. The player on move loses.
+  ///
+  pub fn forfeit(&mut self) -> Result<(), Error> { self.resign(self.current_turn()) }
+
+  ///
+  /// The player resigns, the opponent wins. Any player can resign, not only the player on move.
+  ///
+  pub fn resign(&mut self, player : Player) -> Result<(), Error>
+  {
+    self.ensure_continuing()?;
+    self.declare_result(GameResult::win(player.other_player(), Termination::Resignation));
+    Ok(())
+  }
+
+  ///
+  /// Finishes the game with a draw agreed by players.
+  ///
+  pub fn agree_draw(&mut self) -> Result<(), Error>
+  {
+    self.ensure_continuing()?;
+    self.declare_result(GameResult::Draw(Termination::Agreement));
+    Ok(())
+  }
+
+  ///
+  /// Attaches the clock to the game and starts it for the player on move.
+  /// The clock of a finished game is not started.
+  ///
+  pub fn set_clock(&mut self, mut clock : Clock)
+  {
+    if !self.result().is_over()
     {
-      Ok(_) => Ok(filename),
-      Err(error) => Err(error),
+      clock.start(self.current_turn());
     }
+    self.clock = Some(clock);
+  }
+
+  ///
+  /// Returns the clock of the game, `None` for a game without time control.
+  ///
+  pub fn clock(&self) -> Option<&Clock> { self.clock.as_ref() }
+
+  ///
+  /// Stops the clock of the player on move for a while. Moves can't be made until [Game::resume].
+  ///
+  pub fn pause(&mut self) -> Result<(), Error>
+  {
+    self.ensure_continuing()?;
+    self.clock.as_mut().ok_or(Error::NoClock)?.pause();
+    Ok(())
   }
 
   ///
-  /// Gives ability to forfeit
+  /// Continues the paused clock. The clock of a loaded game is paused and is continued by this method too.
   ///
-  pub fn forfeit(&mut self) { self.is_forfeited = true }
+  pub fn resume(&mut self) -> Result<(), Error>
+  {
+    self.ensure_continuing()?;
+    let turn = self.current_turn();
+    let clock = self.clock.as_mut().ok_or(Error::NoClock)?;
+    match clock.active()
+    {
+      Some(_) => clock.resume(),
+      None => clock.start(turn),
+    }
+    Ok(())
+  }
 
   // FOLLOWING METHODS ARE ADDED FOR MULTIPLAYER FUNCTIONALITY
 
@@ -568,7 +1643,7 @@ impl Game
   ///
   /// Checks validity of a given move for `MultiPlayer`.
   ///
-  pub fn move_is_valid(&self, uci_move : UCI) -> bool { self.board.move_is_valid(uci_move) }
+  pub fn move_is_valid(&self, uci_move : UCI) -> bool { self.board.move_is_valid(uci_move).is_ok() }
 }
 
 ///
@@ -591,6 +1666,25 @@ pub fn get_unix_timestamp(start : Option<SystemTime>) -> u64
   since_the_epoch.as_secs()
 }
 
+///
+/// Random number less than the bound, which is not zero. Not suitable for cryptography.
+///
+
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) fn random_below(bound : u64) -> u64
+{
+  use std::hash::{BuildHasher, Hasher};
+  let hasher = std::collections::hash_map::RandomState::new().build_hasher();
+  hasher.finish() % bound
+}
+
+///
+/// Random number less than the bound, which is not zero.
+///
+
+#[cfg(target_arch = "wasm32")]
+pub(crate) fn random_below(bound : u64) -> u64 { ((js_sys::Math::random() * bound as f64) as u64).min(bound - 1) }
+
 ///
 /// Serialize game to string.
 ///
@@ -604,5 +1698,5 @@ pub fn board_ser<S : Serializer>(board : &Board, s : S) -> Result<S::Ok, S::Erro
 pub fn board_der<'de, D : Deserializer<'de>>(d : D) -> Result<Board, D::Error>
 {
   let fen : String = Deserialize::deserialize(d)?;
-  Ok(Board::from_fen(&Fen::from(fen)))
+  Board::from_fen(&Fen::from(fen)).map_err(serde::de::Error::custom)
 }
@@ -0,0 +1,96 @@
+//!
+//! Performance test of move generation: number of leaf positions of the tree of legal moves of given depth.
+//! Known numbers for well-known positions tell whether move generation is correct.
+//! Chess960 boards are counted by cloning the board for each move, pleco can't undo their castling.
+//!
+
+use super::{Board, Move};
+
+impl Board
+{
+  ///
+  /// Counts positions reachable from the position in exactly `depth` half-moves.
+  /// Depth 0 counts the position itself.
+  ///
+  pub fn perft(&self, depth : u16) -> u64
+  {
+    if self.is_chess960()
+    {
+      return perft_chess960(self, depth);
+    }
+    let mut board = self.pleco_board.clone();
+    perft_pleco(&mut board, depth)
+  }
+
+  ///
+  /// Counts positions reachable after each legal move in `depth - 1` more half-moves.
+  /// Moves are sorted by their UCI notation. Sum of counts is [Board::perft] of the same depth.
+  ///
+  pub fn divide(&self, depth : u16) -> Vec<(Move, u64)>
+  {
+    if depth == 0
+    {
+      return Vec::new();
+    }
+
+    let mut counts : Vec<(Move, u64)> = self
+      .legal_moves()
+      .into_iter()
+      .map(|m|
+      {
+        let mut board = self.clone();
+        board.apply(m);
+        (m, board.perft(depth - 1))
+      })
+      .collect();
+    counts.sort_by_key(|(m, _)| m.stringify());
+    counts
+  }
+}
+
+fn perft_pleco(board : &mut pleco::Board, depth : u16) -> u64
+{
+  if depth == 0
+  {
+    return 1;
+  }
+
+  let moves = board.generate_moves();
+  if depth == 1
+  {
+    return moves.len() as u64;
+  }
+
+  let mut count = 0;
+  for m in moves.iter()
+  {
+    board.apply_move(*m);
+    count += perft_pleco(board, depth - 1);
+    board.undo_move();
+  }
+  count
+}
+
+fn perft_chess960(board : &Board, depth : u16) -> u64
+{
+  if depth == 0
+  {
+    return 1;
+  }
+
+  let moves = board.legal_moves();
+  if depth == 1
+  {
+    return moves.len() as u64;
+  }
+
+  moves
+    .into_iter()
+    .map(|m|
+    {
+      let mut after = board.clone();
+      after.apply(m);
+      after.perft(depth - 1)
+    })
+    .sum()
+}
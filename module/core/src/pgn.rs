@@ -0,0 +1,670 @@
+//!
+//! Portable Game Notation ( PGN ) of games.
+//!
+
+use std::io::BufRead;
+use super::{Error, Game, GameResult, Termination};
+
+/// Tags every exported game has, in the order of the standard, with values used when the tag is not set.
+const SEVEN_TAG_ROSTER : [(&str, &str); 7] = [
+  ("Event", "?"),
+  ("Site", "?"),
+  ("Date", "????.??.??"),
+  ("Round", "?"),
+  ("White", "?"),
+  ("Black", "?"),
+  ("Result", "*"),
+];
+
+/// Lines of movetext are wrapped to fit into this width.
+const PGN_LINE_WIDTH : usize = 79;
+
+impl Game
+{
+  ///
+  /// Returns value of PGN tag. For example: `game.tag("White")`.
+  ///
+  pub fn tag(&self, name : &str) -> Option<&str>
+  {
+    self.tags.iter().find(|(n, _)| n == name).map(|(_, value)| value.as_str())
+  }
+
+  ///
+  /// Sets value of PGN tag. Tags "Result", "Termination", "SetUp" and "FEN" are ignored on export, they are taken from the game itself.
+  ///
+  pub fn set_tag(&mut self, name : &str, value : &str)
+  {
+    match self.tags.iter_mut().find(|(n, _)| n == name)
+    {
+      Some(tag) => tag.1 = value.to_string(),
+      None => self.tags.push((name.to_string(), value.to_string())),
+    }
+  }
+
+  ///
+  /// Exports the game in PGN: Seven Tag Roster, other tags, movetext in SAN and the result.
+  /// Games started from FEN get tags "SetUp" and "FEN".
+  ///
+  pub fn to_pgn(&self) -> Result<String, Error>
+  {
+    let result = self.result().pgn_token();
+    let mut pgn = String::new();
+
+    for (name, default) in SEVEN_TAG_ROSTER
+    {
+      let value = match name
+      {
+        "Result" => result.to_string(),
+        "Date" => self.tag(name).map(str::to_string).unwrap_or_else(|| self.pgn_date()),
+        _ => self.tag(name).unwrap_or(default).to_string(),
+      };
+      push_tag(&mut pgn, name, &value);
+    }
+
+    if self.result().termination() == Some(Termination::Timeout)
+    {
+      push_tag(&mut pgn, "Termination", "time forfeit");
+    }
+
+    if let Some(fen) = &self.start_fen
+    {
+      push_tag(&mut pgn, "SetUp", "1");
+      push_tag(&mut pgn, "FEN", fen);
+    }
+
+    for (name, value) in &self.tags
+    {
+      let reserved = SEVEN_TAG_ROSTER.iter().any(|(n, _)| *n == name.as_str()) || ["Termination", "SetUp", "FEN"].contains(&name.as_str());
+      if !reserved
+      {
+        push_tag(&mut pgn, name, value);
+      }
+    }
+
+    pgn.push('\n');
+
+    let movetext = self.history_movetext()?;
+    let mut line_len = 0;
+    for token in movetext.split_whitespace().chain(std::iter::once(result))
+    {
+      if line_len > 0 && line_len + 1 + token.len() > PGN_LINE_WIDTH
+      {
+        pgn.push('\n');
+        line_len = 0;
+      }
+      else if line_len > 0
+      {
+        pgn.push(' ');
+        line_len += 1;
+      }
+      pgn.push_str(token);
+      line_len += token.len();
+    }
+    pgn.push('\n');
+
+    Ok(pgn)
+  }
+
+  ///
+  /// Date of the game in format of PGN: "YYYY.MM.DD".
+  ///
+  fn pgn_date(&self) -> String
+  {
+    let seconds = self.timestamp() as i64;
+    let (year, month, day) = civil_from_days(seconds.div_euclid(86400));
+    format!("{:04}.{:02}.{:02}", year, month, day)
+  }
+}
+
+///
+/// Error of reading PGN with location of the problem.
+///
+
+#[derive(Debug)]
+pub struct PgnError
+{
+  ///
+  /// Line of the input, starting from 1.
+  ///
+  pub line : usize,
+  ///
+  /// Column of the line, starting from 1.
+  ///
+  pub column : usize,
+  ///
+  /// What is wrong.
+  ///
+  pub kind : PgnErrorKind,
+}
+
+///
+/// Kind of [PgnError].
+///
+
+#[derive(Debug)]
+pub enum PgnErrorKind
+{
+  ///
+  /// Failure of reading the input.
+  ///
+  Io(std::io::Error),
+  ///
+  /// The input is not valid PGN.
+  ///
+  Syntax(String),
+  ///
+  /// The record is valid PGN, but the game can't be played. For example, a move is illegal or FEN is invalid.
+  ///
+  Game(Error),
+}
+
+impl std::fmt::Display for PgnError
+{
+  fn fmt(&self, f : &mut std::fmt::Formatter<'_>) -> std::fmt::Result
+  {
+    write!(f, "line {}, column {} : ", self.line, self.column)?;
+    match &self.kind
+    {
+      PgnErrorKind::Io(error) => write!(f, "{}", error),
+      PgnErrorKind::Syntax(reason) => write!(f, "{}", reason),
+      PgnErrorKind::Game(error) => write!(f, "{}", error),
+    }
+  }
+}
+
+impl std::error::Error for PgnError
+{
+  fn source(&self) -> Option<&(dyn std::error::Error + 'static)>
+  {
+    match &self.kind
+    {
+      PgnErrorKind::Io(error) => Some(error),
+      PgnErrorKind::Syntax(_) => None,
+      PgnErrorKind::Game(error) => Some(error),
+    }
+  }
+}
+
+impl Game
+{
+  ///
+  /// Imports the first game of PGN.
+  ///
+  pub fn from_pgn(pgn : &str) -> Result<Self, PgnError>
+  {
+    PgnReader::new(pgn.as_bytes()).next().unwrap_or_else(|| {
+      Err(PgnError {
+        line : 1,
+        column : 1,
+        kind : PgnErrorKind::Syntax("no game found".to_string()),
+      })
+    })
+  }
+}
+
+type Location = (usize, usize);
+
+///
+/// Streaming reader of PGN. Yields one [Game] per record.
+///
+/// Comments, NAGs and variations are skipped, only the main line is played.
+/// After an error the reader skips the rest of the record and continues with the next one.
+/// If the final position doesn't finish the game, the result of the record is declared: a resignation,
+/// a loss on time by tag "Termination" or an agreed draw.
+///
+
+pub struct PgnReader<R : BufRead>
+{
+  reader : R,
+  line : Vec<char>,
+  line_number : usize,
+  pos : usize,
+  eof : bool,
+}
+
+impl<R : BufRead> std::fmt::Debug for PgnReader<R>
+{
+  fn fmt(&self, f : &mut std::fmt::Formatter<'_>) -> std::fmt::Result
+  {
+    write!(f, "PgnReader{{ line : {}, column : {} }}", self.line_number, self.pos + 1)
+  }
+}
+
+impl<R : BufRead> PgnReader<R>
+{
+  ///
+  /// Constructs a reader of PGN from any buffered input: a file, a string, a network stream.
+  ///
+  pub fn new(reader : R) -> Self
+  {
+    Self {
+      reader,
+      line : Vec::new(),
+      line_number : 0,
+      pos : 0,
+      eof : false,
+    }
+  }
+
+  fn error(&self, location : Location, kind : PgnErrorKind) -> PgnError
+  {
+    PgnError {
+      line : location.0,
+      column : location.1,
+      kind,
+    }
+  }
+
+  fn syntax_error(&self, location : Location, reason : &str) -> PgnError
+  {
+    self.error(location, PgnErrorKind::Syntax(reason.to_string()))
+  }
+
+  fn location(&self) -> Location { (self.line_number, self.pos + 1) }
+
+  fn bump(&mut self) { self.pos += 1; }
+
+  fn skip_line(&mut self) { self.pos = self.line.len(); }
+
+  ///
+  /// Returns next char, reading next line of the input if required. `None` at the end of the input.
+  ///
+  fn peek(&mut self) -> Result<Option<char>, PgnError>
+  {
+    while self.pos >= self.line.len()
+    {
+      if self.eof
+      {
+        return Ok(None);
+      }
+
+      let mut buf = String::new();
+      match self.reader.read_line(&mut buf)
+      {
+        Ok(0) => self.eof = true,
+        Ok(_) =>
+        {
+          self.line = buf.chars().collect();
+          self.line_number += 1;
+          self.pos = 0;
+        }
+        Err(error) =>
+        {
+          self.eof = true;
+          return Err(self.error((self.line_number + 1, 1), PgnErrorKind::Io(error)));
+        }
+      }
+    }
+
+    Ok(Some(self.line[self.pos]))
+  }
+
+  ///
+  /// Returns next char of the current line. `None` at the end of the line.
+  ///
+  fn peek_in_line(&self) -> Option<char>
+  {
+    match self.line.get(self.pos)
+    {
+      Some('\n') | Some('\r') | None => None,
+      Some(c) => Some(*c),
+    }
+  }
+
+  fn skip_spaces_in_line(&mut self)
+  {
+    while matches!(self.peek_in_line(), Some(c) if c.is_whitespace())
+    {
+      self.bump();
+    }
+  }
+
+  ///
+  /// Skips whitespaces, rest of line comments and escape lines. Returns next char.
+  ///
+  fn skip_trivia(&mut self) -> Result<Option<char>, PgnError>
+  {
+    loop
+    {
+      match self.peek()?
+      {
+        Some(c) if c.is_whitespace() || c == '\u{feff}' => self.bump(),
+        Some(';') => self.skip_line(),
+        Some('%') if self.pos == 0 => self.skip_line(),
+        other => return Ok(other),
+      }
+    }
+  }
+
+  fn read_tag(&mut self) -> Result<(String, String), PgnError>
+  {
+    self.bump();
+    self.skip_spaces_in_line();
+
+    let mut name = String::new();
+    while let Some(c) = self.peek_in_line()
+    {
+      if !(c.is_alphanumeric() || c == '_')
+      {
+        break;
+      }
+      name.push(c);
+      self.bump();
+    }
+    if name.is_empty()
+    {
+      return Err(self.syntax_error(self.location(), "expected name of the tag"));
+    }
+
+    self.skip_spaces_in_line();
+    if self.peek_in_line() != Some('"')
+    {
+      return Err(self.syntax_error(self.location(), "expected value of the tag in quotes"));
+    }
+    let start = self.location();
+    self.bump();
+
+    let mut value = String::new();
+    loop
+    {
+      match self.peek_in_line()
+      {
+        None => return Err(self.syntax_error(start, "unterminated value of the tag")),
+        Some('"') =>
+        {
+          self.bump();
+          break;
+        }
+        Some('\\') =>
+        {
+          self.bump();
+          if let Some(c) = self.peek_in_line()
+          {
+            value.push(c);
+            self.bump();
+          }
+        }
+        Some(c) =>
+        {
+          value.push(c);
+          self.bump();
+        }
+      }
+    }
+
+    self.skip_spaces_in_line();
+    if self.peek_in_line() != Some(']')
+    {
+      return Err(self.syntax_error(self.location(), "expected ']' after value of the tag"));
+    }
+    self.bump();
+
+    Ok((name, value))
+  }
+
+  fn skip_comment(&mut self) -> Result<(), PgnError>
+  {
+    let start = self.location();
+    self.bump();
+    loop
+    {
+      match self.peek()?
+      {
+        None => return Err(self.syntax_error(start, "unterminated comment")),
+        Some('}') =>
+        {
+          self.bump();
+          return Ok(());
+        }
+        Some(_) => self.bump(),
+      }
+    }
+  }
+
+  ///
+  /// Reads a symbol of movetext: move number, move in SAN or result.
+  ///
+  fn read_symbol(&mut self) -> Result<String, PgnError>
+  {
+    let mut symbol = String::new();
+    while let Some(c) = self.peek()?
+    {
+      if c.is_whitespace() || "{}()[];$".contains(c) || (c == '*' && !symbol.is_empty())
+      {
+        break;
+      }
+      symbol.push(c);
+      self.bump();
+      if c == '*'
+      {
+        break;
+      }
+    }
+    Ok(symbol)
+  }
+
+  fn read_record(&mut self) -> Result<Option<Game>, PgnError>
+  {
+    if self.skip_trivia()?.is_none()
+    {
+      return Ok(None);
+    }
+
+    let mut failed : Option<PgnError> = None;
+    let mut tags : Vec<(String, String)> = Vec::new();
+    let mut fen_location = self.location();
+    let mut game : Option<Game> = None;
+    let mut variations : Vec<Location> = Vec::new();
+    let mut result_token : Option<String> = None;
+
+    while let Some(c) = self.skip_trivia()?
+    {
+      let location = self.location();
+      match c
+      {
+        '[' if variations.is_empty() && game.is_none() => match self.read_tag()
+        {
+          Ok((name, value)) =>
+          {
+            if name == "FEN"
+            {
+              fen_location = location;
+            }
+            tags.push((name, value));
+          }
+          Err(error) =>
+          {
+            failed.get_or_insert(error);
+            self.skip_line();
+          }
+        },
+        '[' if variations.is_empty() => break,
+        '{' => self.skip_comment()?,
+        '(' =>
+        {
+          self.bump();
+          variations.push(location);
+        }
+        ')' =>
+        {
+          self.bump();
+          if variations.pop().is_none()
+          {
+            failed.get_or_insert(self.syntax_error(location, "unexpected ')' outside of a variation"));
+          }
+        }
+        '$' =>
+        {
+          self.bump();
+          let mut digits = 0;
+          while matches!(self.peek_in_line(), Some(c) if c.is_ascii_digit())
+          {
+            self.bump();
+            digits += 1;
+          }
+          if digits == 0
+          {
+            failed.get_or_insert(self.syntax_error(location, "expected number of NAG after '$'"));
+          }
+        }
+        '[' | ']' | '}' =>
+        {
+          self.bump();
+          failed.get_or_insert(self.syntax_error(location, &format!("unexpected '{}'", c)));
+        }
+        _ =>
+        {
+          let symbol = self.read_symbol()?;
+          if matches!(symbol.as_str(), "1-0" | "0-1" | "1/2-1/2" | "*")
+          {
+            if variations.is_empty()
+            {
+              result_token = Some(symbol);
+              break;
+            }
+            continue;
+          }
+
+          let san = strip_move_number(&symbol);
+          if san.is_empty() || san.chars().all(|c| c == '!' || c == '?') || !variations.is_empty() || failed.is_some()
+          {
+            continue;
+          }
+
+          if game.is_none()
+          {
+            match game_from_tags(&tags)
+            {
+              Ok(g) => game = Some(g),
+              Err(error) =>
+              {
+                failed = Some(self.error(fen_location, PgnErrorKind::Game(error)));
+                continue;
+              }
+            }
+          }
+
+          if let Err(error) = game.as_mut().unwrap().make_move_san(san)
+          {
+            failed = Some(self.error(location, PgnErrorKind::Game(error)));
+          }
+        }
+      }
+    }
+
+    if let Some(location) = variations.first()
+    {
+      failed.get_or_insert(self.syntax_error(*location, "unterminated variation"));
+    }
+    if let Some(error) = failed
+    {
+      return Err(error);
+    }
+
+    let mut game = match game
+    {
+      Some(game) => game,
+      None => game_from_tags(&tags).map_err(|error| self.error(fen_location, PgnErrorKind::Game(error)))?,
+    };
+    let token = result_token.or_else(|| game.tag("Result").map(str::to_string));
+    if let Some(result) = token.and_then(|token| recorded_result(&game, &token))
+    {
+      if !game.result().is_over()
+      {
+        game.declare_result(result);
+      }
+    }
+    Ok(Some(game))
+  }
+}
+
+impl<R : BufRead> Iterator for PgnReader<R>
+{
+  type Item = Result<Game, PgnError>;
+
+  fn next(&mut self) -> Option<Self::Item> { self.read_record().transpose() }
+}
+
+///
+/// Creates the game of the record. Starts from tag "FEN" if it is given.
+///
+
+fn game_from_tags(tags : &[(String, String)]) -> Result<Game, Error>
+{
+  let mut game = match tags.iter().find(|(name, _)| name == "FEN")
+  {
+    Some((_, fen)) => Game::from_fen(fen)?,
+    None => Game::default(),
+  };
+  for (name, value) in tags
+  {
+    if name != "FEN" && name != "SetUp"
+    {
+      game.set_tag(name, value);
+    }
+  }
+  Ok(game)
+}
+
+///
+/// Result recorded by the token of the movetext or the tag "Result", `None` for "*".
+/// A win on time is told by the tag "Termination", other wins are resignations and other draws are agreed.
+///
+
+fn recorded_result(game : &Game, token : &str) -> Option<GameResult>
+{
+  let timeout = game.tag("Termination") == Some("time forfeit");
+  match token
+  {
+    "1-0" => Some(GameResult::WhiteWins(if timeout { Termination::Timeout } else { Termination::Resignation })),
+    "0-1" => Some(GameResult::BlackWins(if timeout { Termination::Timeout } else { Termination::Resignation })),
+    "1/2-1/2" => Some(GameResult::Draw(Termination::Agreement)),
+    _ => None,
+  }
+}
+
+///
+/// Removes move number like "12." or "12..." from the symbol. Castling "0-0" is kept as is.
+///
+
+fn strip_move_number(symbol : &str) -> &str
+{
+  if symbol.starts_with("0-0")
+  {
+    return symbol;
+  }
+  let rest = symbol.trim_start_matches(|c : char| c.is_ascii_digit());
+  if rest.len() < symbol.len() && rest.starts_with('.')
+  {
+    rest.trim_start_matches('.')
+  }
+  else
+  {
+    symbol
+  }
+}
+
+fn push_tag(pgn : &mut String, name : &str, value : &str)
+{
+  let value = value.replace('\\', "\\\\").replace('"', "\\\"");
+  pgn.push_str(&format!("[{} \"{}\"]\n", name, value));
+}
+
+///
+/// Converts days since 1970-01-01 to year, month and day of the Gregorian calendar.
+/// Algorithm `civil_from_days` by Howard Hinnant.
+///
+
+fn civil_from_days(days : i64) -> (i64, u32, u32)
+{
+  let z = days + 719468;
+  let era = (if z >= 0 { z } else { z - 146096 }) / 146097;
+  let doe = z - era * 146097;
+  let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+  let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+  let mp = (5 * doy + 2) / 153;
+  let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+  let month = (if mp < 10 { mp + 3 } else { mp - 9 }) as u32;
+  let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+  (year, month, day)
+}
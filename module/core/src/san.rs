@@ -0,0 +1,276 @@
+//!
+//! Standard Algebraic Notation ( SAN ) of moves. For example: "Nf3", "exd5", "O-O", "e8=Q+".
+//!
+
+use super::{cell_from_chars, Board, Cell, Move, PieceType};
+
+///
+/// Error of parsing or formatting a move in SAN.
+///
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SANParseError
+{
+  ///
+  /// The text is not a move in SAN.
+  ///
+  InvalidSyntax(String),
+  ///
+  /// The move is well-formed, but is not legal in the position.
+  ///
+  IllegalMove(String),
+  ///
+  /// More than one legal move matches. The move needs a source file or rank.
+  ///
+  AmbiguousMove(String),
+  ///
+  /// Pawn reaches the last rank, but the promotion piece is not given.
+  ///
+  MissingPromotion(String),
+}
+
+impl std::fmt::Display for SANParseError
+{
+  fn fmt(&self, f : &mut std::fmt::Formatter<'_>) -> std::fmt::Result
+  {
+    match self
+    {
+      SANParseError::InvalidSyntax(san) => write!(f, "'{}' is not a move in SAN", san),
+      SANParseError::IllegalMove(san) => write!(f, "move {} is illegal", san),
+      SANParseError::AmbiguousMove(san) => write!(f, "move {} is ambiguous, add file or rank of the piece", san),
+      SANParseError::MissingPromotion(san) => write!(f, "promotion piece is missing in {}, for example 'e8=Q'", san),
+    }
+  }
+}
+
+impl std::error::Error for SANParseError {}
+
+impl Board
+{
+  ///
+  /// Formats legal move in SAN. Adds "+" for check and "#" for checkmate.
+  ///
+  pub fn move_to_san(&self, m : Move) -> Result<String, SANParseError>
+  {
+    let legal = self.legal_moves();
+    if !legal.contains(&m)
+    {
+      return Err(SANParseError::IllegalMove(m.stringify()));
+    }
+
+    let mut san = String::new();
+    let src = m.get_src();
+    let dest = m.get_dest();
+
+    if self.is_castling(m)
+    {
+      san.push_str(if is_king_side(m) { "O-O" } else { "O-O-O" });
+    }
+    else
+    {
+      let piece = self.pleco_board.piece_at_sq(src).type_of();
+      if piece == PieceType::P
+      {
+        if m.is_capture()
+        {
+          san.push(file_char(src));
+          san.push('x');
+        }
+        san.push_str(&dest.to_string());
+        if m.is_promo()
+        {
+          san.push('=');
+          san.push(piece_char(m.promo_piece()));
+        }
+      }
+      else
+      {
+        san.push(piece_char(piece));
+
+        let rivals : Vec<Cell> = legal
+          .iter()
+          .filter(|l| **l != m && !self.is_castling(**l) && l.get_dest() == dest)
+          .filter(|l| self.pleco_board.piece_at_sq(l.get_src()).type_of() == piece)
+          .map(|l| l.get_src())
+          .collect();
+        if !rivals.is_empty()
+        {
+          if rivals.iter().all(|r| r.0 % 8 != src.0 % 8)
+          {
+            san.push(file_char(src));
+          }
+          else if rivals.iter().all(|r| r.0 / 8 != src.0 / 8)
+          {
+            san.push(rank_char(src));
+          }
+          else
+          {
+            san.push(file_char(src));
+            san.push(rank_char(src));
+          }
+        }
+
+        if m.is_capture()
+        {
+          san.push('x');
+        }
+        san.push_str(&dest.to_string());
+      }
+    }
+
+    let mut after = self.clone();
+    after.apply(m);
+    if after.is_checkmate()
+    {
+      san.push('#');
+    }
+    else if after.pleco_board.in_check()
+    {
+      san.push('+');
+    }
+
+    Ok(san)
+  }
+
+  ///
+  /// Looks for a legal [Move] from move in SAN.
+  /// Check and annotation suffixes like "+", "#", "!?" are ignored, castling accepts both "O-O" and "0-0".
+  ///
+  pub fn move_from_san(&self, san : &str) -> Result<Move, SANParseError>
+  {
+    let text = san.trim().trim_end_matches(['+', '#', '!', '?']);
+    let syntax_error = || SANParseError::InvalidSyntax(san.trim().to_string());
+    let legal = self.legal_moves();
+
+    let castle = match text
+    {
+      "O-O" | "0-0" => Some(true),
+      "O-O-O" | "0-0-0" => Some(false),
+      _ => None,
+    };
+    if let Some(king_side) = castle
+    {
+      return legal
+        .iter()
+        .find(|m| self.is_castling(**m) && is_king_side(**m) == king_side)
+        .cloned()
+        .ok_or_else(|| SANParseError::IllegalMove(text.to_string()));
+    }
+
+    let mut chars : Vec<char> = text.chars().collect();
+    let piece = match chars.first()
+    {
+      Some('N') => PieceType::N,
+      Some('B') => PieceType::B,
+      Some('R') => PieceType::R,
+      Some('Q') => PieceType::Q,
+      Some('K') => PieceType::K,
+      Some(_) => PieceType::P,
+      None => return Err(syntax_error()),
+    };
+    if piece != PieceType::P
+    {
+      chars.remove(0);
+    }
+
+    let mut promotion = None;
+    if let Some(pos) = chars.iter().position(|c| *c == '=')
+    {
+      if piece != PieceType::P || pos + 2 != chars.len()
+      {
+        return Err(syntax_error());
+      }
+      promotion = Some(promotion_from_char(chars[pos + 1]).ok_or_else(syntax_error)?);
+      chars.truncate(pos);
+    }
+    else if piece == PieceType::P && chars.len() > 2
+    {
+      if let Some(p) = chars.last().and_then(|c| promotion_from_char(*c))
+      {
+        promotion = Some(p);
+        chars.pop();
+      }
+    }
+
+    if chars.len() < 2
+    {
+      return Err(syntax_error());
+    }
+    let dest = cell_from_chars(chars[chars.len() - 2], chars[chars.len() - 1]).map_err(|_| syntax_error())?;
+    chars.truncate(chars.len() - 2);
+    if chars.last() == Some(&'x')
+    {
+      chars.pop();
+    }
+
+    let mut from_file = None;
+    let mut from_rank = None;
+    for c in chars
+    {
+      match c
+      {
+        'a'..='h' if from_file.is_none() && from_rank.is_none() => from_file = Some(c as u8 - b'a'),
+        '1'..='8' if from_rank.is_none() => from_rank = Some(c as u8 - b'1'),
+        _ => return Err(syntax_error()),
+      }
+    }
+
+    let candidates : Vec<Move> = legal
+      .iter()
+      .filter(|m| !self.is_castling(**m) && m.get_dest() == dest)
+      .filter(|m| self.pleco_board.piece_at_sq(m.get_src()).type_of() == piece)
+      .filter(|m| from_file.is_none_or(|file| m.get_src().0 % 8 == file))
+      .filter(|m| from_rank.is_none_or(|rank| m.get_src().0 / 8 == rank))
+      .filter(|m| match promotion
+      {
+        Some(p) => m.is_promo() && m.promo_piece() == p,
+        None => true,
+      })
+      .cloned()
+      .collect();
+
+    match candidates.len()
+    {
+      0 => Err(SANParseError::IllegalMove(text.to_string())),
+      1 => Ok(candidates[0]),
+      _ if candidates.iter().all(|m| m.is_promo()) => Err(SANParseError::MissingPromotion(text.to_string())),
+      _ => Err(SANParseError::AmbiguousMove(text.to_string())),
+    }
+  }
+}
+
+///
+/// Castling is king side if the king goes to the h-file side.
+/// Works both when destination is the rook square and when it is the king square.
+///
+
+fn is_king_side(m : Move) -> bool { m.get_dest().0 % 8 > m.get_src().0 % 8 }
+
+fn file_char(cell : Cell) -> char { (b'a' + cell.0 % 8) as char }
+
+fn rank_char(cell : Cell) -> char { (b'1' + cell.0 / 8) as char }
+
+fn piece_char(piece : PieceType) -> char
+{
+  match piece
+  {
+    PieceType::N => 'N',
+    PieceType::B => 'B',
+    PieceType::R => 'R',
+    PieceType::Q => 'Q',
+    PieceType::K => 'K',
+    _ => 'P',
+  }
+}
+
+fn promotion_from_char(c : char) -> Option<PieceType>
+{
+  match c
+  {
+    'N' => Some(PieceType::N),
+    'B' => Some(PieceType::B),
+    'R' => Some(PieceType::R),
+    'Q' => Some(PieceType::Q),
+    _ => None,
+  }
+}
@@ -0,0 +1,499 @@
+//!
+//! Storage of saved games in a directory. Each game is a file "<id>.save" with the serialized game.
+//!
+//! Save is a JSON object with field "version" and fields of [Game]. Saves of older versions are migrated
+//! to the current version on load, step by step. Saves without version are saves of version 1.
+//! Serde serializes and deserializes [Game] as a save too.
+//!
+
+use super::{get_unix_timestamp, Error, Game, GameResult, GameStatus, Player, Termination};
+
+use serde::{Serialize, Deserialize, Serializer, Deserializer};
+use serde_json::{Map, Value};
+use std::fs;
+use std::io::{ErrorKind, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+///
+/// Default directory of saves, relative to the working directory.
+///
+
+pub const SAVES_FOLDER_NAME : &str = "saves";
+
+///
+/// Extension of files with saved games.
+///
+
+pub const SAVE_FILE_EXTENSION : &str = "save";
+
+///
+/// Number of saves made by the process, makes names of temporary files unique.
+///
+
+static SAVES_COUNTER : AtomicUsize = AtomicUsize::new(0);
+
+///
+/// Version of the format of saves written by [Game::to_save].
+///
+/// 1. Format before versioning: flag "is_forfeited" instead of the result.
+/// 2. Fields "start_fen", "declared_result", "tags" and "clock", flag "is_forfeited" is removed.
+/// 3. Field "evaluator" of the engine.
+/// 4. Field "book" of the engine.
+/// 5. Field "tablebase" of the engine.
+/// 6. Field "skill" of the engine.
+/// 7. Field "engine_move" of moves of the history.
+///
+
+pub const SAVE_FORMAT_VERSION : u64 = 7;
+
+///
+/// Migration of a save from its version to the next one.
+///
+
+type Migration = fn(&mut Map<String, Value>) -> Result<(), Error>;
+
+///
+/// Migrations indexed by the version they migrate from, starting with version 1.
+///
+
+const MIGRATIONS : [Migration; (SAVE_FORMAT_VERSION - 1) as usize] = [migrate_v1_to_v2, migrate_v2_to_v3, migrate_v3_to_v4, migrate_v4_to_v5, migrate_v5_to_v6, migrate_v6_to_v7];
+
+impl Game
+{
+  ///
+  /// Serializes the game to the save of the current version.
+  ///
+  pub fn to_save(&self) -> Result<String, Error> { Ok(serde_json::to_string(&self.save_object()?)?) }
+
+  ///
+  /// Deserializes the game from the save of any supported version.
+  /// Unknown versions, fields and AI algorithms are errors.
+  ///
+  pub fn from_save(save : &str) -> Result<Self, Error>
+  {
+    match serde_json::from_str(save)?
+    {
+      Value::Object(save) => Self::from_save_object(save),
+      _ => Err(Error::InvalidSave("save is not an object".to_string())),
+    }
+  }
+
+  fn save_object(&self) -> Result<Map<String, Value>, Error>
+  {
+    let mut save = match Game::serialize(self, serde_json::value::Serializer)?
+    {
+      Value::Object(save) => save,
+      _ => unreachable!("game is serialized to an object"),
+    };
+    save.insert("version".to_string(), SAVE_FORMAT_VERSION.into());
+    Ok(save)
+  }
+
+  fn from_save_object(mut save : Map<String, Value>) -> Result<Self, Error>
+  {
+    let version = save_version(&save)?;
+    save.remove("version");
+    for migration in &MIGRATIONS[version as usize - 1..]
+    {
+      migration(&mut save)?;
+    }
+
+    Ok(Game::deserialize(Value::Object(save))?)
+  }
+}
+
+///
+/// Version of the save, checked to be supported.
+///
+
+fn save_version(save : &Map<String, Value>) -> Result<u64, Error>
+{
+  let version = match save.get("version")
+  {
+    None => 1,
+    Some(version) => version.as_u64().ok_or_else(|| Error::InvalidSave(format!("version {} is not a number", version)))?,
+  };
+  if version == 0 || version > SAVE_FORMAT_VERSION
+  {
+    return Err(Error::UnsupportedSaveVersion(version));
+  }
+  Ok(version)
+}
+
+impl Serialize for Game
+{
+  fn serialize<S : Serializer>(&self, serializer : S) -> Result<S::Ok, S::Error>
+  {
+    self.save_object().map_err(serde::ser::Error::custom)?.serialize(serializer)
+  }
+}
+
+impl<'de> Deserialize<'de> for Game
+{
+  fn deserialize<D : Deserializer<'de>>(deserializer : D) -> Result<Self, D::Error>
+  {
+    let save = Map::deserialize(deserializer)?;
+    Game::from_save_object(save).map_err(serde::de::Error::custom)
+  }
+}
+
+///
+/// Replaces flag "is_forfeited" by the result: the player on move forfeited. Adds fields with their defaults.
+///
+
+fn migrate_v1_to_v2(save : &mut Map<String, Value>) -> Result<(), Error>
+{
+  let forfeited = match save.remove("is_forfeited")
+  {
+    None => false,
+    Some(Value::Bool(forfeited)) => forfeited,
+    Some(value) => return Err(Error::InvalidSave(format!("is_forfeited {} is not a boolean", value))),
+  };
+
+  let declared_result = if forfeited
+  {
+    let turn = save
+      .get("board")
+      .and_then(Value::as_str)
+      .and_then(|fen| fen.split_whitespace().nth(1))
+      .ok_or_else(|| Error::InvalidSave("board is not a FEN".to_string()))?;
+    let loser = if turn == "b" { Player::Black } else { Player::White };
+    serde_json::to_value(GameResult::win(loser.other_player(), Termination::Resignation))?
+  }
+  else
+  {
+    Value::Null
+  };
+
+  insert_new(save, "start_fen", Value::Null)?;
+  insert_new(save, "declared_result", declared_result)?;
+  insert_new(save, "tags", Value::Array(Vec::new()))?;
+  insert_new(save, "clock", Value::Null)
+}
+
+///
+/// Adds the evaluator of the engine: the default one, which engines used before.
+///
+
+fn migrate_v2_to_v3(save : &mut Map<String, Value>) -> Result<(), Error>
+{
+  match engine(save)?
+  {
+    Some(engine) => insert_new(engine, "evaluator", Value::from("default")),
+    None => Ok(()),
+  }
+}
+
+///
+/// Adds the opening book of the engine: no book.
+///
+
+fn migrate_v3_to_v4(save : &mut Map<String, Value>) -> Result<(), Error>
+{
+  match engine(save)?
+  {
+    Some(engine) => insert_new(engine, "book", Value::Null),
+    None => Ok(()),
+  }
+}
+
+///
+/// Adds the endgame tablebase of the engine: no tablebase.
+///
+
+fn migrate_v4_to_v5(save : &mut Map<String, Value>) -> Result<(), Error>
+{
+  match engine(save)?
+  {
+    Some(engine) => insert_new(engine, "tablebase", Value::Null),
+    None => Ok(()),
+  }
+}
+
+///
+/// Adds the skill level of the engine: full strength.
+///
+
+fn migrate_v5_to_v6(save : &mut Map<String, Value>) -> Result<(), Error>
+{
+  match engine(save)?
+  {
+    Some(engine) => insert_new(engine, "skill", Value::Null),
+    None => Ok(()),
+  }
+}
+
+///
+/// Marks moves of the history as moves of the player: older saves don't tell engine moves apart.
+///
+
+fn migrate_v6_to_v7(save : &mut Map<String, Value>) -> Result<(), Error>
+{
+  match save.get_mut("history")
+  {
+    Some(Value::Array(history)) =>
+    {
+      for entry in history
+      {
+        match entry
+        {
+          Value::Object(entry) => insert_new(entry, "engine_move", Value::Bool(false))?,
+          _ => return Err(Error::InvalidSave(format!("move {} is not an object", entry))),
+        }
+      }
+      Ok(())
+    }
+    Some(value) => Err(Error::InvalidSave(format!("history {} is not an array", value))),
+    None => Ok(()),
+  }
+}
+
+///
+/// Fields of the engine of the save, `None` if the game is played without engine.
+///
+
+fn engine(save : &mut Map<String, Value>) -> Result<Option<&mut Map<String, Value>>, Error>
+{
+  match save.get_mut("ai")
+  {
+    None | Some(Value::Null) => Ok(None),
+    Some(Value::Object(engine)) => Ok(Some(engine)),
+    Some(value) => Err(Error::InvalidSave(format!("ai {} is not an object", value))),
+  }
+}
+
+///
+/// Adds field introduced by a migration. The field can't be in the save already, it's unknown in the older version.
+///
+
+fn insert_new(save : &mut Map<String, Value>, name : &str, value : Value) -> Result<(), Error>
+{
+  if save.contains_key(name)
+  {
+    return Err(Error::InvalidSave(format!("unknown field '{}'", name)));
+  }
+  save.insert(name.to_string(), value);
+  Ok(())
+}
+
+///
+/// Saved game in the list of [SaveStore::list].
+///
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SaveInfo
+{
+  ///
+  /// Identifier of the save for [SaveStore::load] and [SaveStore::delete].
+  ///
+  pub id : String,
+  ///
+  /// Brief description of the game, or the reason why the save can't be read.
+  ///
+  pub header : Result<SaveHeader, String>,
+}
+
+///
+/// Brief description of a saved game. It's read without the engine of the game,
+/// so books, tablebases and external engines are not opened.
+///
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SaveHeader
+{
+  ///
+  /// Version of the format of the save, see [SAVE_FORMAT_VERSION].
+  ///
+  pub version : u64,
+  ///
+  /// Unix timestamp in seconds when the game was started.
+  ///
+  pub date : u64,
+  ///
+  /// Name of the white player from PGN tag "White".
+  ///
+  pub white : Option<String>,
+  ///
+  /// Name of the black player from PGN tag "Black".
+  ///
+  pub black : Option<String>,
+  ///
+  /// Status of the game when it was saved.
+  ///
+  pub status : GameStatus,
+  ///
+  /// Number of half-moves made in the game.
+  ///
+  pub moves : usize,
+}
+
+///
+/// Directory with saved games.
+///
+
+#[derive(Debug, Clone)]
+pub struct SaveStore
+{
+  root : PathBuf,
+}
+
+impl Default for SaveStore
+{
+  fn default() -> Self { Self::new(SAVES_FOLDER_NAME) }
+}
+
+impl SaveStore
+{
+  ///
+  /// Constructs store of saves in the directory. The directory is created on the first save.
+  ///
+  pub fn new(root : impl Into<PathBuf>) -> Self { Self { root : root.into() } }
+
+  ///
+  /// Directory of the saves.
+  ///
+  pub fn root(&self) -> &Path { &self.root }
+
+  ///
+  /// Path of the file of the save.
+  ///
+  pub fn path(&self, id : &str) -> PathBuf { self.root.join(format!("{}.{}", id, SAVE_FILE_EXTENSION)) }
+
+  ///
+  /// Saves the game and returns id of the save. The id is the unix timestamp of saving,
+  /// saves made in the same second get suffixes "-1", "-2" and so on.
+  /// The file appears complete or not at all, existing saves are never overwritten.
+  ///
+  pub fn save(&self, game : &Game) -> Result<String, Error>
+  {
+    fs::create_dir_all(&self.root)?;
+    let serialized = game.to_save()?;
+    let timestamp = get_unix_timestamp(None);
+
+    let counter = SAVES_COUNTER.fetch_add(1, Ordering::Relaxed);
+    let temp = self.root.join(format!(".{}-{}-{}.tmp", timestamp, std::process::id(), counter));
+    let result = fs::File::create(&temp)
+      .and_then(|mut file| file.write_all(serialized.as_bytes()).and_then(|_| file.sync_all()))
+      .map_err(Error::from)
+      .and_then(|_| self.publish(&temp, timestamp));
+    let _ = fs::remove_file(&temp);
+    result
+  }
+
+  ///
+  /// Gives the written file the first free id. Hard link fails if the target exists, so a save can't be overwritten.
+  ///
+  fn publish(&self, temp : &Path, timestamp : u64) -> Result<String, Error>
+  {
+    for suffix in 0u32..
+    {
+      let id = if suffix == 0 { timestamp.to_string() } else { format!("{}-{}", timestamp, suffix) };
+      match fs::hard_link(temp, self.path(&id))
+      {
+        Ok(_) => return Ok(id),
+        Err(error) if error.kind() == ErrorKind::AlreadyExists => continue,
+        Err(error) => return Err(error.into()),
+      }
+    }
+    unreachable!()
+  }
+
+  ///
+  /// Loads the saved game.
+  ///
+  pub fn load(&self, id : &str) -> Result<Game, Error>
+  {
+    let path = self.checked_path(id)?;
+    let serialized = fs::read_to_string(path).map_err(|error| match error.kind()
+    {
+      ErrorKind::NotFound => Error::SaveNotFound(id.to_string()),
+      _ => error.into(),
+    })?;
+    Game::from_save(&serialized)
+  }
+
+  ///
+  /// Deletes the saved game.
+  ///
+  pub fn delete(&self, id : &str) -> Result<(), Error>
+  {
+    let path = self.checked_path(id)?;
+    fs::remove_file(path).map_err(|error| match error.kind()
+    {
+      ErrorKind::NotFound => Error::SaveNotFound(id.to_string()),
+      _ => error.into(),
+    })
+  }
+
+  ///
+  /// Lists saved games, the newest first. Files which can't be read as a game are listed last with the error.
+  /// Missing directory means there are no saves.
+  ///
+  pub fn list(&self) -> Result<Vec<SaveInfo>, Error>
+  {
+    let entries = match fs::read_dir(&self.root)
+    {
+      Ok(entries) => entries,
+      Err(error) if error.kind() == ErrorKind::NotFound => return Ok(Vec::new()),
+      Err(error) => return Err(error.into()),
+    };
+
+    let mut saves = Vec::new();
+    for entry in entries
+    {
+      let path = entry?.path();
+      if path.extension().and_then(|e| e.to_str()) != Some(SAVE_FILE_EXTENSION)
+      {
+        continue;
+      }
+      let id = match path.file_stem().and_then(|s| s.to_str())
+      {
+        Some(id) => id.to_string(),
+        None => continue,
+      };
+      let header = fs::read_to_string(&path).map_err(Error::from).and_then(|serialized| read_header(&serialized));
+      saves.push(SaveInfo { id, header : header.map_err(|error| error.to_string()) });
+    }
+
+    let date = |info : &SaveInfo| info.header.as_ref().map(|header| header.date).ok();
+    saves.sort_by(|a, b| date(b).cmp(&date(a)).then_with(|| b.id.cmp(&a.id)));
+    Ok(saves)
+  }
+
+  ///
+  /// Path of the save. Ids which would point outside of the directory are rejected.
+  ///
+  fn checked_path(&self, id : &str) -> Result<PathBuf, Error>
+  {
+    if id.is_empty() || id.starts_with('.') || id.contains(['/', '\\'])
+    {
+      return Err(Error::SaveNotFound(id.to_string()));
+    }
+    Ok(self.path(id))
+  }
+}
+
+///
+/// Reads the header of the save. The engine is replaced by none before the game is deserialized.
+///
+
+fn read_header(serialized : &str) -> Result<SaveHeader, Error>
+{
+  let mut save = match serde_json::from_str(serialized)?
+  {
+    Value::Object(save) => save,
+    _ => return Err(Error::InvalidSave("save is not an object".to_string())),
+  };
+  let version = save_version(&save)?;
+  save.insert("ai".to_string(), Value::Null);
+  let game = Game::from_save_object(save)?;
+  Ok(SaveHeader {
+    version,
+    date : game.timestamp(),
+    white : game.tag("White").map(str::to_string),
+    black : game.tag("Black").map(str::to_string),
+    status : game.status(),
+    moves : game.ply(),
+  })
+}
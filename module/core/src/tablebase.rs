@@ -0,0 +1,262 @@
+//!
+//! Syzygy endgame tablebases: win/draw/loss ( WDL ) and distance to zeroing ( DTZ ) of positions with few pieces.
+//! Tables are read from a local directory with shakmaty-syzygy, positions are passed to it by FEN.
+//! On wasm there is no file system, tablebases are always empty.
+//!
+
+use super::{Board, Error, Move, PieceType};
+
+use serde::{Serialize, Deserialize};
+use std::path::{Path, PathBuf};
+#[cfg(not(target_arch = "wasm32"))]
+use std::sync::Arc;
+
+///
+/// Result of a position for the side to move with perfect play.
+/// Cursed wins and blessed losses are drawn by the fifty-move rule.
+///
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Wdl
+{
+  /// The side to move loses.
+  Loss,
+  /// The side to move loses, but the opponent can't win before the fifty-move rule.
+  BlessedLoss,
+  /// The position is drawn.
+  Draw,
+  /// The side to move wins, but not before the fifty-move rule.
+  CursedWin,
+  /// The side to move wins.
+  Win,
+}
+
+///
+/// Exact result of a position from the tablebase.
+///
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TablebaseProbe
+{
+  /// Result for the side to move.
+  pub wdl : Wdl,
+  /// Half-moves to the next capture or pawn move with perfect play, negative if the side to move loses, zero for draws.
+  pub dtz : i32,
+  /// Move which keeps the result and reaches zeroing fastest, `None` if there are no legal moves.
+  pub best_move : Option<Move>,
+}
+
+///
+/// Syzygy tablebase read from a directory. Tables are opened when they are probed first.
+/// The tablebase is serialized as its directory. A directory which can't be read any more
+/// is deserialized without tables, see [Tablebase::is_loaded].
+///
+
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(from = "TablebaseState", into = "TablebaseState")]
+pub struct Tablebase
+{
+  directory : PathBuf,
+  #[cfg(not(target_arch = "wasm32"))]
+  tables : Option<Arc<shakmaty_syzygy::Tablebase<shakmaty::Chess>>>,
+}
+
+impl Tablebase
+{
+  ///
+  /// Adds all tables of the directory. Directory without tables is an error.
+  ///
+  #[cfg(not(target_arch = "wasm32"))]
+  pub fn open(directory : impl AsRef<Path>) -> Result<Self, Error>
+  {
+    let mut tables = shakmaty_syzygy::Tablebase::new();
+    let count = tables.add_directory(directory.as_ref())?;
+    if count == 0
+    {
+      let reason = format!("there are no Syzygy tables in '{}'", directory.as_ref().display());
+      return Err(std::io::Error::new(std::io::ErrorKind::NotFound, reason).into());
+    }
+    Ok(Self { directory : directory.as_ref().to_path_buf(), tables : Some(Arc::new(tables)) })
+  }
+
+  ///
+  /// Tablebases can't be read without file system.
+  ///
+  #[cfg(target_arch = "wasm32")]
+  pub fn open(_directory : impl AsRef<Path>) -> Result<Self, Error>
+  {
+    Err(std::io::Error::new(std::io::ErrorKind::Unsupported, "tablebases are not supported on wasm").into())
+  }
+
+  #[cfg(not(target_arch = "wasm32"))]
+  fn unloaded(directory : PathBuf) -> Self { Self { directory, tables : None } }
+
+  #[cfg(target_arch = "wasm32")]
+  fn unloaded(directory : PathBuf) -> Self { Self { directory } }
+
+  ///
+  /// False if the directory of a deserialized tablebase couldn't be read. Such a tablebase has no tables.
+  ///
+  #[cfg(not(target_arch = "wasm32"))]
+  pub fn is_loaded(&self) -> bool { self.tables.is_some() }
+
+  ///
+  /// Tablebases are never loaded on wasm.
+  ///
+  #[cfg(target_arch = "wasm32")]
+  pub fn is_loaded(&self) -> bool { false }
+
+  ///
+  /// Directory of the tables.
+  ///
+  pub fn directory(&self) -> &Path { &self.directory }
+
+  ///
+  /// Most pieces of positions the tablebase has tables for, kings included. Zero for an empty tablebase.
+  ///
+  #[cfg(not(target_arch = "wasm32"))]
+  pub fn max_pieces(&self) -> usize { self.tables.as_ref().map_or(0, |tables| tables.max_pieces()) }
+
+  ///
+  /// Most pieces of positions the tablebase has tables for, always zero on wasm.
+  ///
+  #[cfg(target_arch = "wasm32")]
+  pub fn max_pieces(&self) -> usize { 0 }
+
+  ///
+  /// True if the position has few enough pieces and no castling rights to be in the tablebase.
+  /// Doesn't tell whether the tables of the position are in the directory.
+  ///
+  pub fn covers(&self, board : &Board) -> bool
+  {
+    let pieces = (0..64).filter(|sq| board.piece_at(*sq).type_of() != PieceType::None).count();
+    pieces <= self.max_pieces() && !has_castling_rights(board)
+  }
+
+  ///
+  /// Result of the position for the side to move, ignoring how many half-moves were played without zeroing.
+  /// `None` if the position is not in the tablebase.
+  ///
+  #[cfg(not(target_arch = "wasm32"))]
+  pub fn probe_wdl(&self, board : &Board) -> Option<Wdl>
+  {
+    if !self.covers(board)
+    {
+      return None;
+    }
+    let wdl = self.tables.as_ref()?.probe_wdl_after_zeroing(&position(board)?).ok()?;
+    Some(match wdl
+    {
+      shakmaty_syzygy::Wdl::Loss => Wdl::Loss,
+      shakmaty_syzygy::Wdl::BlessedLoss => Wdl::BlessedLoss,
+      shakmaty_syzygy::Wdl::Draw => Wdl::Draw,
+      shakmaty_syzygy::Wdl::CursedWin => Wdl::CursedWin,
+      shakmaty_syzygy::Wdl::Win => Wdl::Win,
+    })
+  }
+
+  ///
+  /// Positions are never in the tablebase on wasm.
+  ///
+  #[cfg(target_arch = "wasm32")]
+  pub fn probe_wdl(&self, _board : &Board) -> Option<Wdl> { None }
+
+  ///
+  /// Half-moves to the next capture or pawn move with perfect play, negative if the side to move loses.
+  /// `None` if the position is not in the tablebase.
+  ///
+  #[cfg(not(target_arch = "wasm32"))]
+  pub fn probe_dtz(&self, board : &Board) -> Option<i32>
+  {
+    if !self.covers(board)
+    {
+      return None;
+    }
+    self.tables.as_ref()?.probe_dtz(&position(board)?).ok().map(|dtz| dtz.ignore_rounding().0)
+  }
+
+  ///
+  /// Positions are never in the tablebase on wasm.
+  ///
+  #[cfg(target_arch = "wasm32")]
+  pub fn probe_dtz(&self, _board : &Board) -> Option<i32> { None }
+
+  ///
+  /// Move which keeps the result of the position and reaches zeroing fastest.
+  /// `None` if the position is not in the tablebase or has no legal moves.
+  ///
+  #[cfg(not(target_arch = "wasm32"))]
+  pub fn best_move(&self, board : &Board) -> Option<Move>
+  {
+    if !self.covers(board)
+    {
+      return None;
+    }
+    let position = position(board)?;
+    let (m, _) = self.tables.as_ref()?.best_move(&position).ok()??;
+    let uci = m.to_uci(shakmaty::CastlingMode::Standard).to_string();
+    board.legal_moves().into_iter().find(|legal| legal.stringify() == uci)
+  }
+
+  ///
+  /// Positions are never in the tablebase on wasm.
+  ///
+  #[cfg(target_arch = "wasm32")]
+  pub fn best_move(&self, _board : &Board) -> Option<Move> { None }
+}
+
+impl std::fmt::Debug for Tablebase
+{
+  fn fmt(&self, f : &mut std::fmt::Formatter<'_>) -> std::fmt::Result
+  {
+    f.debug_struct("Tablebase").field("directory", &self.directory).field("max_pieces", &self.max_pieces()).finish()
+  }
+}
+
+impl Board
+{
+  ///
+  /// Exact result of the position from the tablebase: WDL, DTZ and the best move.
+  /// `None` if the position is not in the tablebase.
+  ///
+  pub fn tablebase_probe(&self, tablebase : &Tablebase) -> Option<TablebaseProbe>
+  {
+    let wdl = tablebase.probe_wdl(self)?;
+    let dtz = tablebase.probe_dtz(self)?;
+    Some(TablebaseProbe { wdl, dtz, best_move : tablebase.best_move(self) })
+  }
+}
+
+///
+/// Position of shakmaty with the same FEN.
+///
+
+#[cfg(not(target_arch = "wasm32"))]
+fn position(board : &Board) -> Option<shakmaty::Chess>
+{
+  let fen : shakmaty::fen::Fen = board.to_fen().parse().ok()?;
+  fen.into_position(shakmaty::CastlingMode::Standard).ok()
+}
+
+fn has_castling_rights(board : &Board) -> bool { board.to_fen().split_whitespace().nth(2).is_some_and(|castling| castling != "-") }
+
+///
+/// Serialized form of [Tablebase].
+///
+
+#[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct TablebaseState
+{
+  directory : PathBuf,
+}
+
+impl From<Tablebase> for TablebaseState
+{
+  fn from(src : Tablebase) -> Self { Self { directory : src.directory } }
+}
+
+impl From<TablebaseState> for Tablebase
+{
+  fn from(src : TablebaseState) -> Self { Tablebase::open(&src.directory).unwrap_or_else(|_| Tablebase::unloaded(src.directory)) }
+}
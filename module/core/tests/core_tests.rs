@@ -1,4 +1,5 @@
 use game_chess_core::*;
+use game_chess_core::ai::Evaluator;
 
 /*
 cargo test test_trivial -- --show-output
@@ -11,7 +12,7 @@ fn test_trivial()
   let mut game = Game::default();
   let target_move = "a2a4";
   game.board_print();
-  game.make_move(target_move.into());
+  game.make_move(target_move.into()).unwrap();
   game.board_print();
   assert_eq!(game.status(), GameStatus::Continuing);
   assert_eq!(game.last_move().unwrap().0, target_move);
@@ -33,7 +34,7 @@ fn test_board_from_fen()
 {
   //src is board after "a2a4" move from starting position
   let src = "rnbqkbnr/pppppppp/8/8/P7/8/1PPPPPPP/RNBQKBNR b KQkq - 0 1".to_string();
-  let board = Board::from_fen(&Fen::from(src.clone()));
+  let board = Board::from_fen(&Fen::from(src.clone())).unwrap();
   assert_eq!(*board.to_fen(), src);
 }
 
@@ -50,7 +51,7 @@ fn test_game_import()
 fn test_game_export()
 {
   let mut game = Game::default();
-  game.make_move("a2a4".into());
+  game.make_move("a2a4".into()).unwrap();
   let serialized = serde_json::to_string(&game);
   assert_eq!(serialized.is_ok(), true);
 }
@@ -72,3 +73,1513 @@ fn test_print_board()
      1 | R N B Q K B N R \n  ------------------\n    a b c d e f g h"
   );
 }
+
+#[test]
+fn test_uci_to_move()
+{
+  let m = Move::try_from(UCI::from("e7e8q")).unwrap();
+  assert_eq!(m.get_src(), Cell(52));
+  assert_eq!(m.get_dest(), Cell(60));
+  assert_eq!(m.promo_piece(), PieceType::Q);
+  assert!(Move::try_from(UCI::from("0000")).unwrap().is_null());
+  assert_eq!(Move::try_from(UCI::from("e2e")).unwrap_err(), UCIParseError::InvalidLength(3));
+  assert_eq!(Move::try_from(UCI::from("e2i4")).unwrap_err(), UCIParseError::InvalidSquare("i4".to_string()));
+  assert_eq!(Move::try_from(UCI::from("e7e8k")).unwrap_err(), UCIParseError::InvalidPromotion('k'));
+}
+
+#[test]
+fn test_move_from_uci()
+{
+  let board = Board::from_fen(&Fen::from("4k3/P7/8/8/8/8/8/4K3 w - - 0 1".to_string())).unwrap();
+  assert_eq!(board.move_from_uci("a7a8".into()), Err(UCIParseError::MissingPromotion));
+  assert_eq!(board.move_from_uci("a7a8n".into()).unwrap().promo_piece(), PieceType::N);
+  assert_eq!(board.move_from_uci("e1e2q".into()), Err(UCIParseError::UnexpectedPromotion));
+  assert_eq!(board.move_from_uci("b2b3".into()), Err(UCIParseError::EmptySource(Cell(9))));
+  assert_eq!(board.move_from_uci("e8d8".into()), Err(UCIParseError::NotYourPiece(Cell(60))));
+  assert_eq!(board.move_from_uci("e1e3".into()), Err(UCIParseError::IllegalMove("e1e3".to_string())));
+  assert!(board.move_is_valid("e1d2".into()).is_ok());
+}
+
+#[test]
+fn test_errors()
+{
+  let fen = Board::from_fen(&Fen::from("not a fen".to_string()));
+  assert!(matches!(fen, Err(Error::InvalidFen(..))));
+
+  let mut game = Game::default();
+  assert!(matches!(
+    game.make_move("e2e5".into()),
+    Err(Error::IllegalMove(UCIParseError::IllegalMove(_)))
+  ));
+  assert!(game.last_move().is_none());
+
+  let mut game = Game::from_fen("7k/6Q1/6K1/8/8/8/8/8 b - - 0 1").unwrap();
+  assert_eq!(game.status(), GameStatus::Checkmate);
+  assert!(matches!(game.make_move_ai(ai::Limits::default()), Err(Error::GameOver(GameStatus::Checkmate))));
+  assert!(matches!(game.make_move("h8g8".into()), Err(Error::GameOver(GameStatus::Checkmate))));
+}
+
+#[test]
+fn test_move_to_san()
+{
+  let board = Board::default();
+  assert_eq!(board.move_to_san(board.move_from_uci("e2e4".into()).unwrap()).unwrap(), "e4");
+  assert_eq!(board.move_to_san(board.move_from_uci("g1f3".into()).unwrap()).unwrap(), "Nf3");
+
+  let san = |fen : &str, uci : &str| {
+    let board = Board::from_fen(&Fen::from(fen.to_string())).unwrap();
+    board.move_to_san(board.move_from_uci(uci.into()).unwrap()).unwrap()
+  };
+  assert_eq!(san("4k3/8/8/8/8/8/8/R4RK1 w - - 0 1", "a1d1"), "Rad1");
+  assert_eq!(san("4k3/8/8/R7/8/8/8/R3K3 w - - 0 1", "a1a3"), "R1a3");
+  assert_eq!(san("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1", "e1g1"), "O-O");
+  assert_eq!(san("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1", "e1c1"), "O-O-O");
+  assert_eq!(san("4k3/P7/8/8/8/8/8/4K3 w - - 0 1", "a7a8q"), "a8=Q+");
+  assert_eq!(san("rnbqkbnr/ppp1pppp/8/3p4/4P3/8/PPPP1PPP/RNBQKBNR w KQkq d6 0 2", "e4d5"), "exd5");
+}
+
+#[test]
+fn test_move_from_san()
+{
+  let board = Board::from_fen(&Fen::from("r3k2r/8/8/8/8/8/8/RN2K2R w KQkq - 0 1".to_string())).unwrap();
+  assert_eq!(UCI::from(board.move_from_san("O-O").unwrap()).0, "e1g1");
+  assert_eq!(UCI::from(board.move_from_san("Nd2").unwrap()).0, "b1d2");
+  assert_eq!(board.move_from_san("Nc3+"), Ok(board.move_from_uci("b1c3".into()).unwrap()));
+  assert_eq!(board.move_from_san("Qd4"), Err(SANParseError::IllegalMove("Qd4".to_string())));
+  assert_eq!(board.move_from_san("Z9"), Err(SANParseError::InvalidSyntax("Z9".to_string())));
+
+  let board = Board::from_fen(&Fen::from("4k3/8/8/8/8/8/8/1N1NK3 w - - 0 1".to_string())).unwrap();
+  assert_eq!(board.move_from_san("Nc3"), Err(SANParseError::AmbiguousMove("Nc3".to_string())));
+  assert_eq!(UCI::from(board.move_from_san("Nbc3").unwrap()).0, "b1c3");
+  assert_eq!(UCI::from(board.move_from_san("Ndc3").unwrap()).0, "d1c3");
+
+  let board = Board::from_fen(&Fen::from("4k3/P7/8/8/8/8/8/4K3 w - - 0 1".to_string())).unwrap();
+  assert_eq!(board.move_from_san("a8"), Err(SANParseError::MissingPromotion("a8".to_string())));
+  assert_eq!(UCI::from(board.move_from_san("a8=N").unwrap()).0, "a7a8n");
+}
+
+#[test]
+fn test_game_san_history()
+{
+  let mut game = Game::default();
+  for san in ["f3", "e5", "g4", "Qh4#"]
+  {
+    game.make_move_san(san).unwrap();
+  }
+  assert_eq!(game.status(), GameStatus::Checkmate);
+  assert_eq!(game.history_san().unwrap(), vec!["f3", "e5", "g4", "Qh4#"]);
+  assert_eq!(game.history_movetext().unwrap(), "1. f3 e5 2. g4 Qh4#");
+
+  let mut game = Game::from_fen("rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1").unwrap();
+  game.make_move_san("e5").unwrap();
+  game.make_move_san("Nf3").unwrap();
+  assert_eq!(game.history_movetext().unwrap(), "1... e5 2. Nf3");
+}
+
+#[test]
+fn test_game_to_pgn()
+{
+  let mut game = Game::default();
+  game.set_tag("White", "Alice");
+  game.set_tag("Date", "2022.02.04");
+  game.set_tag("Annotator", "Bob \"The Best\"");
+  for san in ["f3", "e5", "g4", "Qh4#"]
+  {
+    game.make_move_san(san).unwrap();
+  }
+  assert_eq!(
+    game.to_pgn().unwrap(),
+    "[Event \"?\"]\n\
+     [Site \"?\"]\n\
+     [Date \"2022.02.04\"]\n\
+     [Round \"?\"]\n\
+     [White \"Alice\"]\n\
+     [Black \"?\"]\n\
+     [Result \"0-1\"]\n\
+     [Annotator \"Bob \\\"The Best\\\"\"]\n\
+     \n\
+     1. f3 e5 2. g4 Qh4# 0-1\n"
+  );
+}
+
+#[test]
+fn test_game_to_pgn_from_fen()
+{
+  let src = r#"{"board":"rnbqkbnr/pppppppp/8/8/P7/8/1PPPPPPP/RNBQKBNR b KQkq - 0 1","history":[{"fen":"rnbqkbnr/pppppppp/8/8/P7/8/1PPPPPPP/RNBQKBNR b KQkq - 0 1","last_move":5640}],"date":{"secs_since_epoch":1643988263,"nanos_since_epoch":27317000},"is_forfeited":false}"#;
+  let game : Game = serde_json::from_str(src).unwrap();
+  let pgn = game.to_pgn().unwrap();
+  assert!(pgn.contains("[Date \"2022.02.04\"]\n"));
+  assert!(pgn.ends_with("\n1. a4 *\n"));
+
+  let mut game = Game::from_fen("4k3/8/8/8/8/8/8/4K2R w K - 0 10").unwrap();
+  game.make_move_san("O-O").unwrap();
+  let pgn = game.to_pgn().unwrap();
+  assert!(pgn.contains("[Result \"*\"]\n[SetUp \"1\"]\n[FEN \"4k3/8/8/8/8/8/8/4K2R w K - 0 10\"]\n"));
+  assert!(pgn.ends_with("\n10. O-O *\n"));
+}
+
+#[test]
+fn test_pgn_reader()
+{
+  let src = r#"[Event "First"]
+[White "Alice"]
+[Black "Bob"]
+[Result "0-1"]
+
+1. f3 {a weak move} e5 $2 2. g4?? (2. e4 Nc6 (2... Nf6) 3. d4) 2... Qh4# 0-1
+
+; second game starts from position
+[Event "Second"]
+[SetUp "1"]
+[FEN "4k3/8/8/8/8/8/8/4K2R w K - 0 10"]
+
+10.O-O Kd7 *
+
+[Event "Third"]
+
+1. e4 e5 2. Ke3 *
+
+[Event "Fourth"]
+
+1. d4 d5 1/2-1/2
+"#;
+  let games : Vec<_> = pgn::PgnReader::new(src.as_bytes()).collect();
+  assert_eq!(games.len(), 4);
+
+  let first = games[0].as_ref().unwrap();
+  assert_eq!(first.tag("White"), Some("Alice"));
+  assert_eq!(first.status(), GameStatus::Checkmate);
+  assert_eq!(first.history_san().unwrap(), vec!["f3", "e5", "g4", "Qh4#"]);
+
+  let second = games[1].as_ref().unwrap();
+  assert_eq!(second.tag("Event"), Some("Second"));
+  assert_eq!(second.history_movetext().unwrap(), "10. O-O Kd7");
+
+  let error = games[2].as_ref().unwrap_err();
+  assert_eq!((error.line, error.column), (17, 13));
+  assert!(matches!(error.kind, pgn::PgnErrorKind::Game(Error::InvalidSan(_))));
+
+  let fourth = games[3].as_ref().unwrap();
+  assert_eq!(fourth.history_san().unwrap(), vec!["d4", "d5"]);
+  assert_eq!(fourth.result(), GameResult::Draw(Termination::Agreement));
+}
+
+#[test]
+fn test_pgn_reader_result()
+{
+  // decisive result of a position which is not finished is a resignation
+  let mut game = Game::from_pgn("[Result \"1-0\"]\n\n1. e4 e5 1-0\n").unwrap();
+  assert_eq!(game.result(), GameResult::WhiteWins(Termination::Resignation));
+  assert!(game.to_pgn().unwrap().ends_with("1. e4 e5 1-0\n"));
+  assert!(matches!(game.make_move_san("Nf3"), Err(Error::GameOver(_))));
+
+  // the tag is used without the token, "Termination" tells a win on time
+  let game = Game::from_pgn("[Result \"0-1\"]\n[Termination \"time forfeit\"]\n\n1. e4 e5\n").unwrap();
+  assert_eq!(game.result(), GameResult::BlackWins(Termination::Timeout));
+
+  // result of the final position wins over the recorded one
+  let game = Game::from_pgn("1. f3 e5 2. g4 Qh4# 1-0\n").unwrap();
+  assert_eq!(game.result(), GameResult::BlackWins(Termination::Checkmate));
+
+  let game = Game::from_pgn("[Result \"1-0\"]\n\n1. e4 *\n").unwrap();
+  assert_eq!(game.result(), GameResult::Ongoing);
+}
+
+#[test]
+fn test_pgn_reader_syntax_errors()
+{
+  let error = Game::from_pgn("[Event \"Broken]\n\n1. e4 *\n").unwrap_err();
+  assert_eq!((error.line, error.column), (1, 8));
+  assert!(matches!(error.kind, pgn::PgnErrorKind::Syntax(_)));
+
+  let error = Game::from_pgn("1. e4 (1. d4 d5 *\n").unwrap_err();
+  assert_eq!((error.line, error.column), (1, 7));
+
+  let error = Game::from_pgn("[FEN \"bad fen\"]\n\n*\n").unwrap_err();
+  assert_eq!((error.line, error.column), (1, 1));
+  assert!(matches!(error.kind, pgn::PgnErrorKind::Game(Error::InvalidFen(..))));
+}
+
+#[test]
+fn test_undo_redo()
+{
+  let mut game = Game::default();
+  assert!(matches!(game.undo(), Err(Error::NothingToUndo)));
+  for san in ["e4", "e5", "Nf3"]
+  {
+    game.make_move_san(san).unwrap();
+  }
+
+  game.undo().unwrap();
+  assert_eq!(game.ply(), 2);
+  assert_eq!(game.current_turn(), Player::White);
+  assert_eq!(game.last_move().unwrap().0, "e7e5");
+
+  game.goto_ply(0).unwrap();
+  assert_eq!(game.board_state_printable(), Board::default().to_pretty_string());
+  assert!(game.last_move().is_none());
+
+  game.redo().unwrap();
+  game.goto_ply(3).unwrap();
+  assert_eq!(game.history_san().unwrap(), vec!["e4", "e5", "Nf3"]);
+  assert!(matches!(game.redo(), Err(Error::NothingToRedo)));
+  assert!(matches!(game.goto_ply(4), Err(Error::InvalidPly(4))));
+
+  game.goto_ply(1).unwrap();
+  game.make_move_san("c5").unwrap();
+  assert!(matches!(game.redo(), Err(Error::NothingToRedo)));
+  assert_eq!(game.history_san().unwrap(), vec!["e4", "c5"]);
+
+  // declared results are kept at their position and can't be undone
+  game.resign(Player::White).unwrap();
+  game.goto_ply(game.ply()).unwrap();
+  assert_eq!(game.result(), GameResult::BlackWins(Termination::Resignation));
+  assert!(matches!(game.undo(), Err(Error::GameOver(GameStatus::GG))));
+  assert!(matches!(game.goto_ply(3), Err(Error::InvalidPly(3))));
+  game.goto_ply(1).unwrap();
+  assert_eq!(game.result(), GameResult::Ongoing);
+
+  let mut game = Game::default();
+  game.make_move_san("e4").unwrap();
+  game.make_move_san("e5").unwrap();
+  game.undo().unwrap();
+  game.agree_draw().unwrap();
+  assert!(matches!(game.redo(), Err(Error::NothingToRedo)));
+  assert!(matches!(game.undo(), Err(Error::GameOver(GameStatus::DrawAgreed))));
+  assert_eq!(game.result(), GameResult::Draw(Termination::Agreement));
+}
+
+#[test]
+fn test_undo_with_ai()
+{
+  let mut game = Game::default();
+  game.ai = Some(ai::Engine::new_with_depth("random".to_string(), 1).unwrap());
+  game.make_move_san("e4").unwrap();
+  game.make_move_ai(ai::Limits::default()).unwrap();
+  assert_eq!(game.ply(), 2);
+
+  game.undo().unwrap();
+  assert_eq!(game.ply(), 0);
+  assert_eq!(game.current_turn(), Player::White);
+
+  game.redo().unwrap();
+  assert_eq!(game.ply(), 2);
+  assert_eq!(game.history_san().unwrap()[0], "e4");
+
+  // the move of the player without a reply of AI is taken back alone
+  game.make_move_san("d4").unwrap();
+  game.undo().unwrap();
+  assert_eq!(game.ply(), 2);
+  assert_eq!(game.current_turn(), Player::White);
+  game.undo().unwrap();
+  assert_eq!(game.ply(), 0);
+  game.redo().unwrap();
+  assert_eq!(game.ply(), 2);
+  game.redo().unwrap();
+  assert_eq!(game.ply(), 3);
+  assert_eq!(game.history_san().unwrap()[2], "d4");
+
+  // AI playing the first move of the game has no move of the player to take back with it
+  let mut game = Game::default();
+  game.ai = Some(ai::Engine::new_with_depth("random".to_string(), 1).unwrap());
+  game.make_move_ai(ai::Limits::default()).unwrap();
+  game.make_move_san("e5").unwrap();
+  game.undo().unwrap();
+  assert_eq!(game.ply(), 1);
+  assert_eq!(game.current_turn(), Player::Black);
+}
+
+///
+/// Algorithm which always plays the same move, legal or not.
+///
+
+struct FixedMoveAlgorithm(u16);
+
+impl ai::Algorithm for FixedMoveAlgorithm
+{
+  fn name(&self) -> &'static str { "Fixed move" }
+
+  fn short_name(&self) -> &'static str { "fixed" }
+
+  fn best_move(&self, _board : Board, _evaluator : &dyn ai::Evaluator, _control : &mut ai::SearchControl<'_>) -> Move { Move::new(self.0) }
+}
+
+#[test]
+fn test_illegal_ai_move()
+{
+  // null move and e2e5
+  for raw in [0, 12 | 36 << 6]
+  {
+    let mut game = Game::default();
+    game.ai = Some(ai::Engine::with_algorithm(Box::new(FixedMoveAlgorithm(raw)), 1));
+    assert!(matches!(game.make_move_ai(ai::Limits::default()), Err(Error::Engine(_))));
+    assert_eq!(game.ply(), 0);
+    assert_eq!(game.board().to_fen(), Board::default().to_fen());
+  }
+}
+
+#[test]
+fn test_draw_by_insufficient_material()
+{
+  let status = |fen : &str| Game::from_fen(fen).unwrap().status();
+  assert_eq!(status("8/8/8/4k3/8/8/8/4K3 w - - 0 1"), GameStatus::InsufficientMaterial);
+  assert_eq!(status("8/8/8/4k3/8/8/8/3NK3 w - - 0 1"), GameStatus::InsufficientMaterial);
+  assert_eq!(status("5b2/8/8/4k3/8/8/8/2B1K3 w - - 0 1"), GameStatus::InsufficientMaterial);
+  assert_eq!(status("2b5/8/8/4k3/8/8/8/2B1K3 w - - 0 1"), GameStatus::Continuing);
+  assert_eq!(status("8/8/8/4k3/8/8/8/2NNK3 w - - 0 1"), GameStatus::Continuing);
+  assert_eq!(status("8/8/8/4k3/8/8/P7/4K3 w - - 0 1"), GameStatus::Continuing);
+}
+
+#[test]
+fn test_draw_by_move_rules()
+{
+  // fifty moves without a capture or a pawn move are not a stalemate
+  let fen = "8/8/8/4k3/8/8/8/R3K3 w - - 100 80";
+  assert!(!Board::from_fen(&Fen::from(fen.to_owned())).unwrap().is_stalemate());
+  let game = Game::from_fen(fen).unwrap();
+  assert_eq!(game.status(), GameStatus::Continuing);
+
+  let mut game = Game::from_fen("8/8/8/4k3/8/8/8/R3K3 w - - 99 80").unwrap();
+  assert_eq!(game.draw_claimable(), None);
+  assert!(matches!(game.claim_draw(), Err(Error::DrawNotClaimable)));
+  game.make_move_san("Ra2").unwrap();
+  assert_eq!(game.status(), GameStatus::Continuing);
+  assert_eq!(game.draw_claimable(), Some(GameStatus::FiftyMoveRule));
+  assert_eq!(game.claim_draw().unwrap(), GameStatus::FiftyMoveRule);
+  assert_eq!(game.status(), GameStatus::FiftyMoveRule);
+  assert!(matches!(game.make_move_san("Kd5"), Err(Error::GameOver(GameStatus::FiftyMoveRule))));
+
+  let game = Game::from_fen("8/8/8/4k3/8/8/8/R3K3 w - - 150 100").unwrap();
+  assert_eq!(game.status(), GameStatus::SeventyFiveMoveRule);
+}
+
+#[test]
+fn test_draw_by_repetition()
+{
+  let mut game = Game::default();
+  for san in ["Nf3", "Nf6", "Ng1", "Ng8", "Nf3", "Nf6", "Ng1"]
+  {
+    game.make_move_san(san).unwrap();
+  }
+  assert_eq!(game.draw_claimable(), None);
+  game.make_move_san("Ng8").unwrap();
+  assert_eq!(game.repetitions(), 3);
+  assert_eq!(game.status(), GameStatus::Continuing);
+  assert_eq!(game.draw_claimable(), Some(GameStatus::ThreefoldRepetition));
+
+  for san in ["Nf3", "Nf6", "Ng1", "Ng8", "Nf3", "Nf6", "Ng1", "Ng8"]
+  {
+    game.make_move_san(san).unwrap();
+  }
+  assert_eq!(game.repetitions(), 5);
+  assert_eq!(game.status(), GameStatus::FivefoldRepetition);
+}
+
+#[test]
+fn test_game_result()
+{
+  let mut game = Game::default();
+  for san in ["f3", "e5", "g4", "Qh4#"]
+  {
+    game.make_move_san(san).unwrap();
+  }
+  assert_eq!(game.result(), GameResult::BlackWins(Termination::Checkmate));
+  assert_eq!(game.result().winner(), Some(Player::Black));
+  assert!(matches!(game.resign(Player::Black), Err(Error::GameOver(GameStatus::Checkmate))));
+
+  let mut game = Game::default();
+  game.make_move_san("e4").unwrap();
+  game.resign(Player::White).unwrap();
+  assert_eq!(game.result(), GameResult::BlackWins(Termination::Resignation));
+  assert_eq!(game.status(), GameStatus::GG);
+  assert!(game.to_pgn().unwrap().ends_with("1. e4 0-1\n"));
+
+  let mut game = Game::default();
+  game.forfeit().unwrap();
+  assert_eq!(game.result(), GameResult::BlackWins(Termination::Resignation));
+
+  let mut game = Game::from_fen("8/8/8/4k3/8/8/8/4K3 w - - 0 1").unwrap();
+  assert_eq!(game.result(), GameResult::Draw(Termination::InsufficientMaterial));
+  assert!(game.result().is_over());
+  assert_eq!(game.result().winner(), None);
+  assert!(game.agree_draw().is_err());
+
+  let mut game = Game::default();
+  game.agree_draw().unwrap();
+  assert_eq!(game.status(), GameStatus::DrawAgreed);
+
+  let serialized = serde_json::to_string(&GameResult::WhiteWins(Termination::Timeout)).unwrap();
+  let deserialized : GameResult = serde_json::from_str(&serialized).unwrap();
+  assert_eq!(deserialized, GameResult::WhiteWins(Termination::Timeout));
+}
+
+#[test]
+fn test_time_control_parse()
+{
+  use std::time::Duration;
+  let minutes = |m : u64| Duration::from_secs(m * 60);
+
+  assert_eq!("5".parse::<TimeControl>(), Ok(TimeControl::SuddenDeath { base : minutes(5) }));
+  assert_eq!("3+2".parse::<TimeControl>(), Ok(TimeControl::Fischer { base : minutes(3), increment : Duration::from_secs(2) }));
+  assert_eq!("5b3".parse::<TimeControl>(), Ok(TimeControl::Bronstein { base : minutes(5), delay : Duration::from_secs(3) }));
+  assert_eq!("5d3".parse::<TimeControl>(), Ok(TimeControl::SimpleDelay { base : minutes(5), delay : Duration::from_secs(3) }));
+  assert_eq!("0.5".parse::<TimeControl>(), Ok(TimeControl::SuddenDeath { base : Duration::from_secs(30) }));
+  assert_eq!(TimeControl::Fischer { base : minutes(3), increment : Duration::from_secs(2) }.to_string(), "3+2");
+
+  for invalid in ["", "0", "-5", "5+", "5x3", "five", "5+-1"]
+  {
+    assert!(invalid.parse::<TimeControl>().is_err(), "{}", invalid);
+  }
+}
+
+#[test]
+fn test_clock_controls()
+{
+  use std::sync::Arc;
+  use std::time::Duration;
+  use game_chess_core::clock::ManualTimeSource;
+  let secs = Duration::from_secs;
+  let clock = |control : &str, time : &ManualTimeSource|
+  {
+    let mut clock = Clock::new(control.parse().unwrap()).with_time_source(Arc::new(time.clone()));
+    clock.start(Player::White);
+    clock
+  };
+
+  let time = ManualTimeSource::new();
+  let mut sudden_death = clock("1", &time);
+  time.advance(secs(10));
+  assert_eq!(sudden_death.remaining(Player::White), secs(50));
+  sudden_death.press();
+  assert_eq!(sudden_death.active(), Some(Player::Black));
+  time.advance(secs(5));
+  assert_eq!(sudden_death.remaining(Player::White), secs(50));
+  assert_eq!(sudden_death.remaining(Player::Black), secs(55));
+
+  let time = ManualTimeSource::new();
+  let mut fischer = clock("1+5", &time);
+  time.advance(secs(10));
+  fischer.press();
+  assert_eq!(fischer.remaining(Player::White), secs(55));
+
+  let time = ManualTimeSource::new();
+  let mut bronstein = clock("1b5", &time);
+  time.advance(secs(10));
+  assert_eq!(bronstein.remaining(Player::White), secs(50));
+  bronstein.press();
+  assert_eq!(bronstein.remaining(Player::White), secs(55));
+  time.advance(secs(2));
+  bronstein.press();
+  assert_eq!(bronstein.remaining(Player::Black), secs(60));
+
+  let time = ManualTimeSource::new();
+  let mut simple_delay = clock("1d5", &time);
+  time.advance(secs(3));
+  assert_eq!(simple_delay.remaining(Player::White), secs(60));
+  time.advance(secs(7));
+  assert_eq!(simple_delay.remaining(Player::White), secs(55));
+  simple_delay.press();
+  assert_eq!(simple_delay.remaining(Player::White), secs(55));
+
+  let time = ManualTimeSource::new();
+  let mut paused = clock("1", &time);
+  time.advance(secs(10));
+  paused.pause();
+  assert!(paused.is_paused());
+  time.advance(secs(100));
+  assert_eq!(paused.flagged(), None);
+  paused.resume();
+  time.advance(secs(10));
+  assert_eq!(paused.remaining(Player::White), secs(40));
+  time.advance(secs(40));
+  assert_eq!(paused.flagged(), Some(Player::White));
+}
+
+#[test]
+fn test_game_clock()
+{
+  use std::sync::Arc;
+  use std::time::Duration;
+  use game_chess_core::clock::ManualTimeSource;
+  let secs = Duration::from_secs;
+
+  let mut game = Game::default();
+  assert!(matches!(game.pause(), Err(Error::NoClock)));
+
+  let time = ManualTimeSource::new();
+  game.set_clock(Clock::new("1+2".parse().unwrap()).with_time_source(Arc::new(time.clone())));
+  time.advance(secs(10));
+  game.make_move_san("e4").unwrap();
+  assert_eq!(game.clock().unwrap().remaining(Player::White), secs(52));
+  assert_eq!(game.clock().unwrap().active(), Some(Player::Black));
+
+  game.pause().unwrap();
+  time.advance(secs(100));
+  assert!(matches!(game.make_move_san("e5"), Err(Error::ClockPaused)));
+  game.resume().unwrap();
+  time.advance(secs(20));
+  game.make_move_san("e5").unwrap();
+  assert_eq!(game.clock().unwrap().remaining(Player::Black), secs(42));
+
+  let serialized = serde_json::to_string(&game).unwrap();
+  let mut loaded : Game = serde_json::from_str(&serialized).unwrap();
+  assert!(loaded.clock().unwrap().is_paused());
+  assert_eq!(loaded.clock().unwrap().remaining(Player::White), secs(52));
+  loaded.resume().unwrap();
+  loaded.make_move_san("Nf3").unwrap();
+
+  time.advance(secs(52));
+  assert_eq!(game.result(), GameResult::BlackWins(Termination::Timeout));
+  assert_eq!(game.status(), GameStatus::Timeout);
+  assert!(matches!(game.make_move_san("Nf3"), Err(Error::GameOver(GameStatus::Timeout))));
+  assert!(game.to_pgn().unwrap().contains("[Termination \"time forfeit\"]"));
+
+  let time = ManualTimeSource::new();
+  let mut game = Game::default();
+  game.set_clock(Clock::new("1".parse().unwrap()).with_time_source(Arc::new(time.clone())));
+  game.make_move_san("e4").unwrap();
+  game.resign(Player::Black).unwrap();
+  assert_eq!(game.clock().unwrap().active(), None);
+  time.advance(secs(100));
+  assert_eq!(game.result(), GameResult::WhiteWins(Termination::Resignation));
+}
+
+#[test]
+fn test_save_store()
+{
+  let root = std::env::temp_dir().join(format!("game_chess_saves_{}", std::process::id()));
+  let _ = std::fs::remove_dir_all(&root);
+  let store = SaveStore::new(&root);
+  assert_eq!(store.list().unwrap(), Vec::new());
+
+  let mut game = Game::default();
+  game.set_tag("White", "Alice");
+  game.make_move_san("e4").unwrap();
+  let first = store.save(&game).unwrap();
+  game.make_move_san("e5").unwrap();
+  game.resign(Player::White).unwrap();
+  let second = store.save(&game).unwrap();
+  assert_ne!(first, second);
+
+  let saves = store.list().unwrap();
+  assert_eq!(saves.len(), 2);
+  let info = saves.iter().find(|info| info.id == second).unwrap().header.as_ref().unwrap();
+  assert_eq!(info.version, save::SAVE_FORMAT_VERSION);
+  assert_eq!(info.white.as_deref(), Some("Alice"));
+  assert_eq!(info.black, None);
+  assert_eq!(info.status, GameStatus::GG);
+  assert_eq!(info.moves, 2);
+  assert_eq!(info.date, game.timestamp());
+
+  let loaded = store.load(&first).unwrap();
+  assert_eq!(loaded.ply(), 1);
+  assert_eq!(loaded.board_state_printable(), {
+    let mut expected = Game::default();
+    expected.make_move_san("e4").unwrap();
+    expected.board_state_printable()
+  });
+
+  store.delete(&first).unwrap();
+  assert!(matches!(store.load(&first), Err(Error::SaveNotFound(_))));
+  assert!(matches!(store.delete(&first), Err(Error::SaveNotFound(_))));
+  assert!(matches!(store.load("../secret"), Err(Error::SaveNotFound(_))));
+  assert_eq!(store.list().unwrap().len(), 1);
+
+  // saves are listed without their engines, unreadable saves are listed last with the error
+  let unknown_algorithm = include_str!("saves/v2.save").replace("\"random\"", "\"deep_blue\"");
+  std::fs::write(root.join("v2.save"), unknown_algorithm).unwrap();
+  std::fs::write(root.join("broken.save"), "not a game").unwrap();
+  let saves = store.list().unwrap();
+  assert_eq!(saves.len(), 3);
+  assert!(store.load("v2").is_err());
+  let info = saves.iter().find(|info| info.id == "v2").unwrap().header.as_ref().unwrap();
+  assert_eq!(info.version, 2);
+  assert_eq!(info.status, GameStatus::Continuing);
+  assert_eq!(saves.last().unwrap().id, "broken");
+  assert!(saves.last().unwrap().header.is_err());
+  assert!(matches!(store.load("broken"), Err(Error::Serialization(_))));
+
+  std::fs::remove_dir_all(&root).unwrap();
+}
+
+#[test]
+fn test_save_versions()
+{
+  use std::time::Duration;
+
+  let game = Game::from_save(include_str!("saves/v1.save")).unwrap();
+  assert_eq!(game.history_san().unwrap(), vec!["e4", "e5", "Nf3"]);
+  assert_eq!(game.status(), GameStatus::Continuing);
+  assert_eq!(game.ai.as_ref().unwrap().depth, 3);
+  assert_eq!(game.timestamp(), 1650000000);
+  assert!(game.clock().is_none());
+
+  let game = Game::from_save(include_str!("saves/v1_forfeited.save")).unwrap();
+  assert_eq!(game.result(), GameResult::WhiteWins(Termination::Resignation));
+  assert!(!game.has_ai());
+
+  let game = Game::from_save(include_str!("saves/v2.save")).unwrap();
+  assert_eq!(game.history_san().unwrap(), vec!["e4", "e5"]);
+  assert_eq!(game.tag("White"), Some("Alice"));
+  assert_eq!(game.tag("Black"), Some("Bob"));
+  let clock = game.clock().unwrap();
+  assert!(clock.is_paused());
+  assert_eq!(clock.control(), TimeControl::Fischer { base : Duration::from_secs(180), increment : Duration::from_secs(2) });
+  assert_eq!(clock.remaining(Player::White), Duration::from_secs(165));
+  assert_eq!(clock.remaining(Player::Black), Duration::from_secs(175));
+  assert_eq!(game.ai.as_ref().unwrap().evaluator().name(), "default");
+
+  let save = game.to_save().unwrap();
+  assert!(save.contains(&format!("\"version\":{}", save::SAVE_FORMAT_VERSION)));
+  let loaded = Game::from_save(&save).unwrap();
+  assert_eq!(loaded.history_san().unwrap(), game.history_san().unwrap());
+  assert_eq!(loaded.clock().unwrap().remaining(Player::White), Duration::from_secs(165));
+  assert_eq!(loaded.to_save().unwrap(), save);
+
+  let game = Game::from_save(include_str!("saves/v3.save")).unwrap();
+  assert_eq!(game.tag("White"), Some("Carol"));
+  assert!(game.clock().is_none());
+  let engine = game.ai.as_ref().unwrap();
+  assert_eq!(engine.algorithm_name(), "greedy");
+  assert_eq!(engine.evaluator().name(), "pleco");
+
+  // book of the engine is read from its path relative to the working directory
+  let game = Game::from_save(include_str!("saves/v4.save")).unwrap();
+  assert_eq!(game.tag("White"), Some("Erin"));
+  let book = game.ai.as_ref().unwrap().book().unwrap();
+  assert!(book.is_loaded());
+  assert_eq!(book.len(), 1);
+  assert_eq!(book.mode(), ai::BookMode::Best);
+  assert_eq!(UCI::from(book.choose(&Board::default()).unwrap()).0, "e2e4");
+
+  // tablebase of the engine is read from tests/syzygy, without tables there the game is loaded without them
+  let game = Game::from_save(include_str!("saves/v5.save")).unwrap();
+  assert_eq!(game.tag("White"), Some("Grace"));
+  let tablebase = game.ai.as_ref().unwrap().tablebase().unwrap();
+  assert_eq!(tablebase.directory(), std::path::Path::new("tests/syzygy"));
+  assert_eq!(tablebase.is_loaded(), Tablebase::open("tests/syzygy").is_ok());
+
+  let game = Game::from_save(include_str!("saves/v6.save")).unwrap();
+  assert_eq!(game.tag("White"), Some("Ivan"));
+  assert_eq!(game.ai.as_ref().unwrap().skill(), Some(ai::Skill::new(7)));
+
+  // moves of older saves are moves of the player, so they are taken back one by one
+  let mut game = Game::from_save(include_str!("saves/v6.save")).unwrap();
+  game.undo().unwrap();
+  assert_eq!(game.ply(), 1);
+  let mut game = Game::from_save(include_str!("saves/v7.save")).unwrap();
+  assert_eq!(game.tag("White"), Some("Kate"));
+  game.undo().unwrap();
+  assert_eq!(game.ply(), 0);
+}
+
+#[test]
+fn test_save_errors()
+{
+  let v2 = include_str!("saves/v2.save");
+
+  let future = v2.replace("\"version\":2", "\"version\":99");
+  assert!(matches!(Game::from_save(&future), Err(Error::UnsupportedSaveVersion(99))));
+
+  let unknown_field = v2.replace("\"tags\"", "\"colour\":\"white\",\"tags\"");
+  assert!(matches!(Game::from_save(&unknown_field), Err(Error::Serialization(_))));
+
+  for field in ["\"evaluator\":\"pleco\"", "\"book\":null", "\"tablebase\":null", "\"skill\":7"]
+  {
+    let newer_field = v2.replace("\"depth\":2", &format!("\"depth\":2,{}", field));
+    assert!(matches!(Game::from_save(&newer_field), Err(Error::InvalidSave(_))), "{}", field);
+  }
+  let newer_field = include_str!("saves/v6.save").replace("\"last_move\":5900", "\"last_move\":5900,\"engine_move\":true");
+  assert!(matches!(Game::from_save(&newer_field), Err(Error::InvalidSave(_))));
+
+  let unknown_algorithm = v2.replace("\"random\"", "\"deep_blue\"");
+  match Game::from_save(&unknown_algorithm)
+  {
+    Err(Error::Serialization(error)) => assert!(error.to_string().contains("deep_blue")),
+    other => panic!("unexpected {:?}", other),
+  }
+
+  let v1 = include_str!("saves/v1.save");
+  let unknown_field = v1.replace("\"is_forfeited\"", "\"start_fen\":null,\"clock\":1,\"is_forfeited\"");
+  assert!(matches!(Game::from_save(&unknown_field), Err(Error::InvalidSave(_))));
+  assert!(matches!(Game::from_save("[]"), Err(Error::InvalidSave(_))));
+
+  // serde reads and writes saves too
+  let game : Game = serde_json::from_str(v1).unwrap();
+  assert_eq!(game.ply(), 3);
+  let serialized = serde_json::to_string(&game).unwrap();
+  assert!(serialized.contains(&format!("\"version\":{}", save::SAVE_FORMAT_VERSION)));
+  let deserialized : Game = serde_json::from_str(&serialized).unwrap();
+  assert_eq!(deserialized.history_san().unwrap(), game.history_san().unwrap());
+  assert!(serde_json::from_str::<Game>(&future).is_err());
+}
+
+#[test]
+fn test_zobrist()
+{
+  let polyglot = [
+    ("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1", 0x463b96181691fc9c),
+    ("rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq - 0 1", 0x823c9b50fd114196),
+    ("rnbqkbnr/ppp1pppp/8/3p4/4P3/8/PPPP1PPP/RNBQKBNR w KQkq - 0 2", 0x0756b94461c50fb0),
+    ("rnbqkbnr/ppp1pppp/8/3pP3/8/8/PPPP1PPP/RNBQKBNR b KQkq - 0 2", 0x662fafb965db29d4),
+    ("rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPP1PPP/RNBQKBNR w KQkq f6 0 3", 0x22a48b5a8e47ff78),
+    ("rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPPKPPP/RNBQ1BNR b kq - 1 3", 0x652a607ca3f242c1),
+    ("rnbq1bnr/ppp1pkpp/8/3pPp2/8/8/PPPPKPPP/RNBQ1BNR w - - 2 4", 0x00fdd303c946bdd9),
+    ("rnbqkbnr/p1pppppp/8/8/PpP4P/8/1P1PPPP1/RNBQKBNR b KQkq c3 0 3", 0x3c8123ea7b067637),
+    ("rnbqkbnr/p1pppppp/8/8/P6P/R1p5/1P1PPPP1/1NBQKBNR b Kkq - 1 4", 0x5c3f9b829b279560),
+  ];
+  for (fen, key) in polyglot
+  {
+    let board = Board::from_fen(&Fen::from(fen.to_string())).unwrap();
+    assert_eq!(board.polyglot_key(), key, "{}", fen);
+  }
+
+  let mut game = Game::default();
+  let mut transposed = Game::default();
+  for (san, transposed_san) in [("Nf3", "Nc3"), ("Nf6", "Nf6"), ("Nc3", "Nf3")]
+  {
+    game.make_move_san(san).unwrap();
+    transposed.make_move_san(transposed_san).unwrap();
+  }
+  let board = Board::from_fen(&game.board().to_fen()).unwrap();
+  assert_eq!(board.hash(), game.board().hash());
+  assert_eq!(game.hashes().last(), Some(&board.hash()));
+  assert_eq!(game.hashes().last(), transposed.hashes().last());
+  assert_eq!(game.hashes().len(), game.ply() + 1);
+  assert_eq!(game.hashes()[0], Board::default().hash());
+  assert_ne!(game.hashes()[1], game.hashes()[0]);
+
+  let loaded = Game::from_save(&game.to_save().unwrap()).unwrap();
+  assert_eq!(loaded.hashes(), game.hashes());
+
+  let king_h8 = Board::from_fen(&Fen::from("7k/R7/8/8/8/8/8/1R4K1 b - - 0 1".to_string())).unwrap();
+  let king_g7 = Board::from_fen(&Fen::from("8/R5k1/8/8/8/8/8/1R4K1 b - - 0 1".to_string())).unwrap();
+  assert_ne!(king_h8.hash(), king_g7.hash());
+}
+
+#[test]
+fn test_perft()
+{
+  let positions : [(&str, &[u64]); 16] = [
+    ("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1", &[20, 400, 8902, 197281]),
+    ("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1", &[48, 2039, 97862]),
+    ("8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1", &[14, 191, 2812, 43238]),
+    ("r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1", &[6, 264, 9467]),
+    ("r2q1rk1/pP1p2pp/Q4n2/bbp1p3/Np6/1B3NBn/pPPP1PPP/R3K2R b KQ - 0 1", &[6, 264, 9467]),
+    ("rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8", &[44, 1486, 62379]),
+    ("r4rk1/1pp1qppp/p1np1n2/2b1p1B1/2B1P1b1/P1NP1N2/1PP1QPPP/R4RK1 w - - 0 10", &[46, 2079, 89890]),
+    // en passant
+    ("8/8/8/5k2/3p4/8/4P3/4K3 w - - 0 1", &[6, 54, 343]),
+    ("8/8/1k6/2b5/2pP4/8/5K2/8 b - d3 0 1", &[15, 126, 1928]),
+    ("8/8/8/8/k2Pp2Q/8/8/3K4 b - d3 0 1", &[6, 136, 863]),
+    ("3k4/3p4/8/K1P4r/8/8/8/8 b - - 0 1", &[18, 92, 1670]),
+    ("8/5bk1/8/2Pp4/8/1K6/8/8 w - d6 0 1", &[8, 104, 736]),
+    // promotion
+    ("n1n5/PPPk4/8/8/8/8/4Kppp/5N1N b - - 0 1", &[24, 496, 9483]),
+    ("8/P1k5/K7/8/8/8/8/8 w - - 0 1", &[6, 27, 273]),
+    ("2b5/kpPp4/1p1P4/1P6/6p1/4p1P1/4PpPK/5B2 w - - 0 1", &[1, 1]),
+    ("4k3/1P6/8/8/8/8/K7/8 w - - 0 1", &[9, 40, 472]),
+  ];
+
+  for (fen, counts) in positions
+  {
+    let board = Board::from_fen(&Fen::from(fen.to_string())).unwrap();
+    assert_eq!(board.perft(0), 1);
+    for (depth, count) in counts.iter().enumerate()
+    {
+      assert_eq!(board.perft(depth as u16 + 1), *count, "perft {} of {}", depth + 1, fen);
+    }
+  }
+}
+
+#[test]
+fn test_divide()
+{
+  let board = Board::default();
+  let divide = board.divide(3);
+  assert_eq!(divide.len(), 20);
+  assert_eq!(divide.iter().map(|(_, count)| count).sum::<u64>(), board.perft(3));
+  assert_eq!(UCI::from(divide[0].0).0, "a2a3");
+  assert_eq!(divide.iter().find(|(m, _)| UCI::from(*m).0 == "e2e4").unwrap().1, 600);
+  assert!(board.divide(0).is_empty());
+}
+
+#[test]
+fn test_chess960_start_positions()
+{
+  let classical = Board::chess960(518).unwrap();
+  assert!(!classical.is_chess960());
+  assert_eq!(classical.to_fen(), Board::default().to_fen());
+
+  let board = Board::chess960(0).unwrap();
+  assert!(board.is_chess960());
+  assert_eq!(*board.to_fen(), "bbqnnrkr/pppppppp/8/8/8/8/PPPPPPPP/BBQNNRKR w KQkq - 0 1");
+  assert_eq!(*board.to_shredder_fen(), "bbqnnrkr/pppppppp/8/8/8/8/PPPPPPPP/BBQNNRKR w HFhf - 0 1");
+  assert!(Board::chess960(959).unwrap().to_fen().starts_with("rkrnnqbb/pppppppp/8/8/8/8/PPPPPPPP/RKRNNQBB w "));
+  assert!(matches!(Board::chess960(960), Err(Error::InvalidChess960Index(960))));
+
+  let fen = Board::chess960_random().to_fen();
+  let ranks : Vec<&str> = fen.split(['/', ' ']).collect();
+  assert_eq!(ranks[0].to_uppercase(), ranks[7]);
+
+  let game = Game::chess960(0).unwrap();
+  assert!(game.is_chess960());
+  assert_eq!(game.tag("Variant"), Some("Chess960"));
+  let pgn = game.to_pgn().unwrap();
+  assert!(pgn.contains("[Variant \"Chess960\"]"));
+  assert!(pgn.contains("[FEN \"bbqnnrkr/pppppppp/8/8/8/8/PPPPPPPP/BBQNNRKR w KQkq - 0 1\"]"));
+  assert!(!Game::default().is_chess960());
+}
+
+#[test]
+fn test_chess960_fen()
+{
+  let shredder = Board::from_fen(&Fen::from("rr2k2r/8/8/8/8/8/8/RR2K2R w HBhb - 0 1".to_string())).unwrap();
+  assert!(shredder.is_chess960());
+  assert_eq!(*shredder.to_fen(), "rr2k2r/8/8/8/8/8/8/RR2K2R w KBkb - 0 1");
+  let x_fen = Board::from_fen(&shredder.to_fen()).unwrap();
+  assert_eq!(x_fen.to_shredder_fen(), shredder.to_shredder_fen());
+  assert_eq!(x_fen.hash(), shredder.hash());
+
+  let board = Board::from_fen(&Fen::from("r1k1r2q/p1ppp1pp/8/8/8/8/P1PPP1PP/R1K1R2Q w KQkq - 0 1".to_string())).unwrap();
+  assert!(board.is_chess960());
+  assert_eq!(*board.to_shredder_fen(), "r1k1r2q/p1ppp1pp/8/8/8/8/P1PPP1PP/R1K1R2Q w EAea - 0 1");
+
+  let classical = Board::from_fen(&Fen::from("r3k2r/8/8/8/8/8/8/R3K2R w HAha - 0 1".to_string())).unwrap();
+  assert!(!classical.is_chess960());
+  assert_eq!(*classical.to_fen(), "r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1");
+
+  for fen in ["4k3/8/8/8/8/8/8/4K3 w K - 0 1", "4k3/8/8/8/8/8/8/R3K2R w C - 0 1", "4k3/8/8/8/8/8/8/R3K2R w KX - 0 1"]
+  {
+    assert!(matches!(Board::from_fen(&Fen::from(fen.to_string())), Err(Error::InvalidFen(_, _))), "{}", fen);
+  }
+}
+
+#[test]
+fn test_chess960_perft()
+{
+  let positions : [(&str, &[u64]); 14] = [
+    ("bqnb1rkr/pp3ppp/3ppn2/2p5/5P2/P2P4/NPP1P1PP/BQ1BNRKR w HFhf - 2 9", &[21, 528, 12189]),
+    ("2nnrbkr/p1qppppp/8/1ppb4/6PP/3PP3/PPP2P2/BQNNRBKR w HEhe - 1 9", &[21, 807, 18002]),
+    ("b1q1rrkb/pppppppp/3nn3/8/P7/1PPP4/4PPPP/BQNNRKRB w GE - 1 9", &[20, 479, 10471]),
+    ("q1nrkrbn/pp1pppp1/2p4p/8/P7/5Pb1/BPPPPNPP/Q1NRKRB1 w FDfd - 0 9", &[22, 558, 12911]),
+    ("rb1knnbr/1pp1ppp1/p2p3p/5q2/3B2P1/3P1P2/PPP1P2P/RBQKNN1R w HAha - 0 9", &[34, 1360, 44096]),
+    ("rkrnnqbb/p1ppp2p/Qp6/4Pp2/5p2/8/PPPP2PP/RKRNN1BB w CAca - 0 9", &[35, 929, 32020]),
+    // X-FEN
+    ("r1k1r2q/p1ppp1pp/8/8/8/8/P1PPP1PP/R1K1R2Q w KQkq - 0 1", &[23, 522, 12333]),
+    ("r1k2r1q/p1ppp1pp/8/8/8/8/P1PPP1PP/R1K2R1Q w KQkq - 0 1", &[28, 738, 20218]),
+    ("8/8/8/4B2b/6nN/8/5P2/2R1K2k w Q - 0 1", &[34, 318, 9002]),
+    ("2r5/8/8/8/8/8/6PP/k2KR3 w K - 0 1", &[17, 242, 3931]),
+    ("4r3/3k4/8/8/8/8/6PP/qR1K1R2 w KQ - 0 1", &[19, 628, 12858]),
+    ("4rrk1/pbbp2p1/1ppnp3/3n1pqp/3N1PQP/1PPNP3/PBBP2P1/4RRK1 w Ff - 0 1", &[42, 1743, 71908]),
+    ("8/8/8/B2p3Q/2qPp1P1/b7/2P2PkP/4K2R b K - 0 1", &[26, 611, 14583]),
+    ("r2r3k/p7/3p4/8/8/P6P/8/R3K2R b KQq - 0 1", &[14, 206, 3672]),
+  ];
+
+  for (fen, counts) in positions
+  {
+    let board = Board::from_fen(&Fen::from(fen.to_string())).unwrap();
+    for (depth, count) in counts.iter().enumerate()
+    {
+      assert_eq!(board.perft(depth as u16 + 1), *count, "perft {} of {}", depth + 1, fen);
+    }
+  }
+}
+
+#[test]
+fn test_chess960_castling()
+{
+  let mut game = Game::from_fen("1r2k2r/8/8/8/8/8/8/1R2K1R1 w GBhb - 0 1").unwrap();
+  assert!(game.is_chess960());
+  assert!(game.moves_list().iter().any(|m| UCI::from(*m).0 == "e1g1"));
+
+  game.make_move_san("O-O").unwrap();
+  assert_eq!(game.last_move().unwrap().0, "e1g1");
+  assert_eq!(*game.board().to_fen(), "1r2k2r/8/8/8/8/8/8/1R3RK1 b kq - 1 1");
+  game.make_move(UCI::from("e8b8")).unwrap();
+  assert_eq!(*game.board().to_fen(), "2kr3r/8/8/8/8/8/8/1R3RK1 w - - 2 2");
+  assert!(!game.board().is_chess960());
+  assert_eq!(game.history_san().unwrap(), vec!["O-O", "O-O-O"]);
+
+  let loaded = Game::from_save(&game.to_save().unwrap()).unwrap();
+  assert!(loaded.is_chess960());
+  assert_eq!(loaded.board().to_fen(), game.board().to_fen());
+  assert_eq!(loaded.history_san().unwrap(), vec!["O-O", "O-O-O"]);
+  assert_eq!(loaded.hashes(), game.hashes());
+
+  let mut game = loaded;
+  game.undo().unwrap();
+  game.undo().unwrap();
+  assert_eq!(*game.board().to_fen(), "1r2k2r/8/8/8/8/8/8/1R2K1R1 w KQkq - 0 1");
+  game.make_move(UCI::from("e1b1")).unwrap();
+  assert_eq!(game.history_san().unwrap(), vec!["O-O-O"]);
+  assert_eq!(*game.board().to_fen(), "1r2k2r/8/8/8/8/8/8/2KR2R1 b kq - 1 1");
+
+  let board = Board::from_fen(&Fen::from("1r1rk1r1/8/8/8/8/8/8/1R2KbR1 w GBgb - 0 1".to_string())).unwrap();
+  assert!(board.move_from_san("O-O").is_err());
+  assert!(board.move_from_san("O-O-O").is_err());
+}
+
+#[test]
+fn test_default_evaluator()
+{
+  let evaluator = ai::DefaultEvaluator;
+  assert_eq!(evaluator.evaluate(&Board::default()), 0);
+
+  let evaluation = evaluator.breakdown(&Board::default());
+  let names : Vec<&str> = evaluation.terms.iter().map(|term| term.name).collect();
+  assert_eq!(names, vec!["material", "piece-square", "mobility", "king safety", "pawn structure"]);
+  assert_eq!(evaluation.terms[0].white, 4000);
+  assert_eq!(evaluation.terms[0].black, 4000);
+
+  // the same position with colors swapped gets the opposite score
+  let white = Board::from_fen(&Fen::from("r1bqkb1r/pppp1ppp/2n2n2/4p3/2B1P3/5N2/PPPP1PPP/RNBQK2R w KQkq - 4 4".to_string())).unwrap();
+  let black = Board::from_fen(&Fen::from("rnbqk2r/pppp1ppp/5n2/2b1p3/4P3/2N2N2/PPPP1PPP/R1BQKB1R b KQkq - 4 4".to_string())).unwrap();
+  assert_eq!(evaluator.evaluate(&white), evaluator.evaluate(&black));
+  assert_eq!(evaluator.breakdown(&white).white_score(), -evaluator.breakdown(&black).white_score());
+
+  let up_a_queen = Board::from_fen(&Fen::from("rnb1kbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR b KQkq - 0 1".to_string())).unwrap();
+  let evaluation = evaluator.breakdown(&up_a_queen);
+  assert_eq!(evaluation.terms[0].score(), 900);
+  assert!(evaluation.white_score() > 800);
+  assert_eq!(evaluation.total(), -evaluation.white_score());
+  assert_eq!(evaluator.evaluate(&up_a_queen), evaluation.total());
+  assert_eq!(up_a_queen.score(), evaluation.total());
+
+  // doubled and isolated, but passed pawns
+  let pawns = Board::from_fen(&Fen::from("4k3/8/8/8/8/2P5/2P5/4K3 w - - 0 1".to_string())).unwrap();
+  let structure = evaluator.breakdown(&pawns).terms[4].clone();
+  assert_eq!(structure.white, -12 - 2 * 12 + 10 + 5);
+  assert_eq!(structure.black, 0);
+}
+
+struct MaterialEvaluator;
+
+impl ai::Evaluator for MaterialEvaluator
+{
+  fn name(&self) -> &'static str { "material" }
+
+  fn evaluate(&self, board : &Board) -> i32
+  {
+    let score : i32 = (0..64)
+      .map(|sq| board.piece_at(sq))
+      .map(|piece| match (piece.player(), piece.type_of())
+      {
+        (Some(player), PieceType::Q) if player == board.current_turn() => 9,
+        (Some(_), PieceType::Q) => -9,
+        _ => 0,
+      })
+      .sum();
+    score
+  }
+}
+
+#[test]
+fn test_custom_evaluator()
+{
+  let mut engine = ai::Engine::new_with_depth("greedy".to_string(), 1).unwrap();
+  assert_eq!(engine.evaluator().name(), "default");
+  engine.set_evaluator(Box::new(MaterialEvaluator));
+  assert_eq!(engine.evaluator().name(), "material");
+
+  // greedy takes the queen
+  let board = Board::from_fen(&Fen::from("4k3/8/8/3q4/8/8/8/3RK3 w - - 0 1".to_string())).unwrap();
+  assert_eq!(UCI::from(engine.best_move(board.clone())).0, "d1d5");
+
+  let mut game = Game::from_fen("4k3/8/8/3q4/8/8/8/3RK3 w - - 0 1").unwrap();
+  game.ai = Some(engine);
+  assert_eq!(game.count_score(), -9);
+  let evaluation = game.evaluation();
+  assert_eq!(evaluation.terms.len(), 1);
+  assert_eq!(evaluation.terms[0].name, "material");
+  assert_eq!(evaluation.total(), -9);
+  game.make_move_ai(ai::Limits::default()).unwrap();
+  assert_eq!(game.last_move().unwrap().0, "d1d5");
+
+  // evaluators are serialized by name
+  let engine = ai::Engine::new_with_depth("greedy".to_string(), 2).unwrap();
+  let serialized = serde_json::to_string(&engine).unwrap();
+  assert_eq!(serialized, r#"{"algorithm":"greedy","depth":2,"evaluator":"default"}"#);
+  let engine : ai::Engine = serde_json::from_str(r#"{"algorithm":"greedy","depth":2,"evaluator":"pleco"}"#).unwrap();
+  assert_eq!(engine.evaluator().name(), "pleco");
+  let engine : ai::Engine = serde_json::from_str(r#"{"algorithm":"greedy","depth":2}"#).unwrap();
+  assert_eq!(engine.evaluator().name(), "default");
+  assert!(serde_json::from_str::<ai::Engine>(r#"{"algorithm":"greedy","depth":2,"evaluator":"unknown"}"#).is_err());
+}
+
+#[test]
+fn test_alpha_beta()
+{
+  let engine = ai::Engine::new_with_depth("alpha_beta".to_string(), 4).unwrap();
+
+  // mate in two with two rooks
+  let mut game = Game::from_fen("7k/8/8/8/8/8/R7/1R4K1 w - - 0 1").unwrap();
+  for _ in 0..3
+  {
+    let m = engine.best_move(game.board().clone());
+    game.make_move(UCI::from(m)).unwrap();
+  }
+  assert_eq!(game.status(), GameStatus::Checkmate);
+
+  // knight fork wins the rook
+  let board = Board::from_fen(&Fen::from("r3k3/8/8/3N4/8/8/8/4K3 w - - 0 1".to_string())).unwrap();
+  assert_eq!(UCI::from(engine.best_move(board)).0, "d5c7");
+
+  // defended pawn is not taken
+  let board = Board::from_fen(&Fen::from("4k3/8/4p3/3p4/8/8/8/3QK3 w - - 0 1".to_string())).unwrap();
+  assert_ne!(UCI::from(engine.best_move(board)).0, "d1d5");
+
+  // no legal moves
+  let board = Board::from_fen(&Fen::from("7k/6Q1/6K1/8/8/8/8/8 b - - 0 1".to_string())).unwrap();
+  assert!(engine.best_move(board).is_null());
+}
+
+#[test]
+fn test_search_limits()
+{
+  use std::time::{Duration, Instant};
+
+  let budget = ai::Limits::clock(Duration::from_secs(60), Duration::from_secs(2)).time_budget();
+  assert_eq!(budget, Some(Duration::from_millis(3500)));
+  let limits = ai::Limits { movetime : Some(Duration::from_secs(1)), ..ai::Limits::clock(Duration::from_secs(60), Duration::ZERO) };
+  assert_eq!(limits.time_budget(), Some(Duration::from_secs(1)));
+  assert_eq!(ai::Limits::clock(Duration::from_millis(40), Duration::ZERO).time_budget(), Some(Duration::ZERO));
+  assert_eq!(ai::Limits::infinite().time_budget(), None);
+  assert!(ai::Limits::default().is_empty());
+
+  let engine = ai::Engine::new_with_depth("alpha_beta".to_string(), 4).unwrap();
+  let board = Board::default();
+  let legal = board.legal_moves();
+
+  // nodes
+  let mut infos = Vec::new();
+  let m = engine.search(board.clone(), ai::Limits::nodes(3000), ai::CancelHandle::new(), |info| infos.push(info.clone()));
+  assert!(legal.contains(&m));
+  assert!(!infos.is_empty());
+  assert!(infos.iter().all(|info| info.nodes <= 3000));
+  assert!(infos.windows(2).all(|pair| pair[0].depth < pair[1].depth));
+
+  // empty limits use the depth of the engine
+  let mut infos = Vec::new();
+  engine.search(board.clone(), ai::Limits::default(), ai::CancelHandle::new(), |info| infos.push(info.clone()));
+  assert_eq!(infos.last().unwrap().depth, 4);
+
+  // movetime
+  let start = Instant::now();
+  let m = engine.best_move_with_limits(board.clone(), ai::Limits::movetime(Duration::from_millis(100)));
+  assert!(legal.contains(&m));
+  assert!(start.elapsed() < Duration::from_secs(2));
+
+  // cancellation of search in its own thread
+  let handle = engine.start(board.clone(), ai::Limits::infinite());
+  let info = handle.next_info().unwrap();
+  assert_eq!(info.depth, 1);
+  let m = handle.stop();
+  assert!(legal.contains(&m));
+
+  // cancelled before the start, still a legal move
+  let cancel = ai::CancelHandle::new();
+  cancel.cancel();
+  assert!(legal.contains(&engine.search(board.clone(), ai::Limits::infinite(), cancel, |_| ())));
+
+  // pleco searchers are searched by alpha-beta within time, and can be cancelled within depth
+  let pleco = ai::Engine::new_with_depth("iterative".to_string(), 4).unwrap();
+  let start = Instant::now();
+  assert!(legal.contains(&pleco.best_move_with_limits(board.clone(), ai::Limits::movetime(Duration::from_millis(100)))));
+  assert!(start.elapsed() < Duration::from_secs(2));
+  let handle = pleco.start(board.clone(), ai::Limits::depth(10));
+  std::thread::sleep(Duration::from_millis(50));
+  let start = Instant::now();
+  assert!(legal.contains(&handle.stop()));
+  assert!(start.elapsed() < Duration::from_secs(1));
+
+  // AI opponent respects the clock of the game
+  let mut game = Game::default();
+  game.ai = Some(ai::Engine::new_with_depth("alpha_beta".to_string(), ai::MAX_DEPTH).unwrap());
+  game.set_clock(Clock::new(TimeControl::SuddenDeath { base : Duration::from_secs(1) }));
+  let start = Instant::now();
+  game.make_move_ai(ai::Limits::default()).unwrap();
+  assert!(start.elapsed() < Duration::from_secs(1));
+  assert_eq!(game.status(), GameStatus::Continuing);
+}
+
+#[test]
+fn test_analysis()
+{
+  assert_eq!(ai::Score::Centipawns(35).to_string(), "+0.35");
+  assert_eq!(ai::Score::Centipawns(-120).to_string(), "-1.20");
+  assert_eq!(ai::Score::Mate(3).to_string(), "#3");
+  assert_eq!(ai::Score::Mate(-2).to_string(), "#-2");
+  assert_eq!(format!("{:>6}", ai::Score::Mate(1)), "    #1");
+
+  let engine = ai::Engine::new_with_depth("alpha_beta".to_string(), 4).unwrap();
+
+  // mate in two
+  let board = Board::from_fen(&Fen::from("7k/8/8/8/8/8/R7/1R4K1 w - - 0 1".to_string())).unwrap();
+  let analysis = engine.analyze(board.clone(), ai::Limits::default(), 1);
+  assert_eq!(analysis.score(), Some(ai::Score::Mate(2)));
+  assert_eq!(analysis.lines.len(), 1);
+  let line = &analysis.lines[0];
+  assert_eq!(line.pv[0], analysis.best_move());
+  assert_eq!(line.to_san(&board).len(), line.pv.len());
+  assert!(analysis.nodes > 0);
+
+  // best lines with different first moves
+  let board = Board::default();
+  let analysis = engine.analyze(board.clone(), ai::Limits::depth(3), 3);
+  assert_eq!(analysis.depth, 3);
+  assert_eq!(analysis.lines.len(), 3);
+  assert!(analysis.lines.iter().all(|line| matches!(line.score, ai::Score::Centipawns(_))));
+  let first : Vec<Move> = analysis.lines.iter().map(|line| line.pv[0]).collect();
+  assert!(first.iter().all(|m| board.legal_moves().contains(m)));
+  assert!(first[0] != first[1] && first[1] != first[2] && first[0] != first[2]);
+  assert!(analysis.to_string().starts_with("depth 3, nodes "));
+
+  // more lines than legal moves
+  let board = Board::from_fen(&Fen::from("7k/8/8/8/8/8/8/K6q w - - 0 1".to_string())).unwrap();
+  let analysis = engine.analyze(board.clone(), ai::Limits::depth(2), 5);
+  assert_eq!(analysis.lines.len(), board.legal_moves().len());
+
+  // algorithm without reports gives its move scored by the evaluator
+  let greedy = ai::Engine::new_with_depth("greedy".to_string(), 1).unwrap();
+  let analysis = greedy.analyze(Board::default(), ai::Limits::default(), 3);
+  assert_eq!(analysis.depth, 0);
+  assert_eq!(analysis.lines.len(), 1);
+
+  // no legal moves
+  let board = Board::from_fen(&Fen::from("7k/6Q1/6K1/8/8/8/8/8 b - - 0 1".to_string())).unwrap();
+  let analysis = engine.analyze(board, ai::Limits::default(), 1);
+  assert!(analysis.lines.is_empty());
+  assert!(analysis.best_move().is_null());
+}
+
+fn book_entry(key : u64, raw_move : u16, weight : u16) -> Vec<u8>
+{
+  let mut entry = Vec::new();
+  entry.extend_from_slice(&key.to_be_bytes());
+  entry.extend_from_slice(&raw_move.to_be_bytes());
+  entry.extend_from_slice(&weight.to_be_bytes());
+  entry.extend_from_slice(&0u32.to_be_bytes());
+  entry
+}
+
+#[test]
+fn test_opening_book()
+{
+  let start = Board::default();
+  assert_eq!(start.polyglot_key(), 0x463b96181691fc9c);
+  let castling = Board::from_fen(&Fen::from("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1".to_string())).unwrap();
+
+  let mut bytes = Vec::new();
+  // d2d4 without weight, e2e4, an illegal move a1a8, and castling encoded as the king taking the rook
+  bytes.extend(book_entry(castling.polyglot_key(), (4 << 6) | 7, 1));
+  bytes.extend(book_entry(start.polyglot_key(), (11 << 6) | 27, 0));
+  bytes.extend(book_entry(start.polyglot_key(), (12 << 6) | 28, 10));
+  bytes.extend(book_entry(start.polyglot_key(), 56, 100));
+
+  let book = ai::Book::from_bytes(&bytes).unwrap();
+  assert_eq!(book.len(), 4);
+  let moves : Vec<(String, u16)> = book.moves(&start).into_iter().map(|(m, weight)| (UCI::from(m).0, weight)).collect();
+  assert_eq!(moves, vec![("d2d4".to_string(), 0), ("e2e4".to_string(), 10)]);
+  for _ in 0..10
+  {
+    assert_eq!(UCI::from(book.choose(&start).unwrap()).0, "e2e4");
+  }
+  let book = book.with_mode(ai::BookMode::Best);
+  assert_eq!(UCI::from(book.choose(&start).unwrap()).0, "e2e4");
+  assert_eq!(UCI::from(book.choose(&castling).unwrap()).0, "e1g1");
+  assert!(book.choose(&start.make_move(UCI::from("e2e4")).unwrap()).is_none());
+  assert!(matches!(ai::Book::from_bytes(&[0; 15]), Err(Error::InvalidBook(_))));
+
+  // engine plays from the book
+  let path = std::env::temp_dir().join(format!("game_chess_book_{}.bin", std::process::id()));
+  std::fs::write(&path, &bytes).unwrap();
+  let mut engine = ai::Engine::new_with_depth("random".to_string(), 1).unwrap();
+  engine.set_book(Some(ai::Book::open(&path).unwrap().with_mode(ai::BookMode::Best)));
+  for _ in 0..10
+  {
+    assert_eq!(UCI::from(engine.best_move(start.clone())).0, "e2e4");
+  }
+
+  // book is serialized with the engine as its path and mode
+  let serialized = serde_json::to_string(&engine).unwrap();
+  assert!(serialized.contains(r#""mode":"Best""#));
+  let engine : ai::Engine = serde_json::from_str(&serialized).unwrap();
+  assert_eq!(engine.book().unwrap().len(), 4);
+  assert_eq!(engine.book().unwrap().mode(), ai::BookMode::Best);
+  std::fs::remove_file(&path).unwrap();
+
+  // book which can't be read any more is kept without entries
+  let engine : ai::Engine = serde_json::from_str(&serialized).unwrap();
+  let book = engine.book().unwrap();
+  assert!(!book.is_loaded());
+  assert!(book.is_empty());
+  assert_eq!(book.path(), path.as_path());
+  assert!(engine.book_move(&start).is_none());
+  assert_eq!(serde_json::to_string(&engine).unwrap(), serialized);
+}
+
+#[test]
+fn test_tablebase()
+{
+  let missing = std::env::temp_dir().join(format!("game_chess_no_tablebase_{}", std::process::id()));
+  assert!(matches!(Tablebase::open(&missing), Err(Error::Io(_))));
+  let empty = std::env::temp_dir().join(format!("game_chess_empty_tablebase_{}", std::process::id()));
+  std::fs::create_dir_all(&empty).unwrap();
+  assert!(matches!(Tablebase::open(&empty), Err(Error::Io(_))));
+  std::fs::remove_dir(&empty).unwrap();
+
+  // tablebase which can't be read any more is kept without tables
+  let board = Board::from_fen(&Fen::from("8/8/8/8/8/8/8/KQ5k w - - 0 1".to_string())).unwrap();
+  let json = r#"{"algorithm":"alpha_beta","depth":2,"evaluator":"default","tablebase":{"directory":"no_such_directory"}}"#;
+  let engine : ai::Engine = serde_json::from_str(json).unwrap();
+  let tablebase = engine.tablebase().unwrap();
+  assert!(!tablebase.is_loaded());
+  assert_eq!(tablebase.max_pieces(), 0);
+  assert!(board.tablebase_probe(tablebase).is_none());
+  assert!(engine.tablebase_move(&board).is_none());
+  assert_eq!(serde_json::to_string(&engine).unwrap(), json);
+}
+
+#[test]
+fn test_tablebase_probe()
+{
+  // tables KQvK.rtbw and KQvK.rtbz are read from SYZYGY_PATH or else from tests/syzygy
+  let directory = std::env::var("SYZYGY_PATH").unwrap_or_else(|_| concat!(env!("CARGO_MANIFEST_DIR"), "/tests/syzygy").to_string());
+  let tablebase = match Tablebase::open(&directory)
+  {
+    Ok(tablebase) => tablebase,
+    Err(_) =>
+    {
+      eprintln!("skipped: no Syzygy tables in '{}'", directory);
+      return;
+    }
+  };
+  assert!(tablebase.max_pieces() >= 3);
+
+  let win = Board::from_fen(&Fen::from("8/8/8/8/8/8/8/KQ5k w - - 0 1".to_string())).unwrap();
+  let probe = win.tablebase_probe(&tablebase).unwrap();
+  assert_eq!(probe.wdl, Wdl::Win);
+  assert!(probe.dtz > 0);
+  let after = win.make_move(UCI::from(probe.best_move.unwrap())).unwrap();
+  assert_eq!(tablebase.probe_wdl(&after), Some(Wdl::Loss));
+
+  // the half-move counter doesn't change the result
+  let late = Board::from_fen(&Fen::from("8/8/8/8/8/8/8/KQ5k w - - 90 120".to_string())).unwrap();
+  assert_eq!(tablebase.probe_wdl(&late), Some(Wdl::Win));
+
+  let loss = Board::from_fen(&Fen::from("8/8/8/8/8/8/Q7/K5k1 b - - 0 1".to_string())).unwrap();
+  assert_eq!(tablebase.probe_wdl(&loss), Some(Wdl::Loss));
+  assert!(tablebase.probe_dtz(&loss).unwrap() < 0);
+
+  // the black king takes the queen
+  let draw = Board::from_fen(&Fen::from("8/8/8/8/8/8/1Q6/1k5K b - - 0 1".to_string())).unwrap();
+  let probe = draw.tablebase_probe(&tablebase).unwrap();
+  assert_eq!(probe.wdl, Wdl::Draw);
+  assert_eq!(probe.dtz, 0);
+  assert_eq!(UCI::from(probe.best_move.unwrap()).0, "b1b2");
+
+  let mut engine = ai::Engine::new_with_depth("alpha_beta".to_string(), 2).unwrap();
+  engine.set_tablebase(Some(tablebase));
+  let best = engine.best_move(win.clone());
+  let after = win.make_move(UCI::from(best)).unwrap();
+  assert_eq!(engine.tablebase().unwrap().probe_wdl(&after), Some(Wdl::Loss));
+}
+
+#[cfg(unix)]
+#[test]
+fn test_external_engine()
+{
+  let script = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/scripted_uci.sh");
+
+  // handshake and options
+  let uci = ai::UciEngine::start("sh", &[script]).unwrap();
+  assert_eq!(uci.engine_name(), "Scripted Engine");
+  assert_eq!(uci.author(), "game_chess");
+  assert_eq!(uci.options().len(), 2);
+  let hash = ai::UciOption { name : "Hash".to_string(), kind : "spin".to_string(), default : Some("1".to_string()) };
+  assert_eq!(uci.options()[0], hash);
+  assert!(matches!(uci.set_option("Missing", "1"), Err(Error::Engine(_))));
+  uci.set_option("move", "d2d4").unwrap();
+  let engine = ai::Engine::with_algorithm(Box::new(uci), 3);
+  assert_eq!(UCI::from(engine.best_move(Board::default())).0, "d2d4");
+
+  // lines of the engine are reported
+  let analysis = engine.analyze(Board::default(), ai::Limits::depth(2), 1);
+  assert_eq!(analysis.depth, 2);
+  assert_eq!(analysis.nodes, 50);
+  assert_eq!(analysis.score(), Some(ai::Score::Mate(3)));
+  assert_eq!(analysis.best_move().stringify(), "d2d4");
+
+  // infinite search is answered after cancellation
+  let handle = engine.start(Board::default(), ai::Limits::infinite());
+  assert_eq!(handle.next_info().unwrap().depth, 1);
+  handle.cancel();
+  assert_eq!(UCI::from(handle.wait()).0, "d2d4");
+
+  // engine is created and serialized by its command
+  let command = format!("{}sh {}", ai::UCI_PREFIX, script);
+  let engine = ai::Engine::new(command.clone()).unwrap();
+  assert_eq!(engine.algorithm_name(), "uci");
+  assert_eq!(engine.algorithm_id(), command);
+  assert_eq!(engine.unstarted_command(), None);
+  let serialized = serde_json::to_string(&engine).unwrap();
+  let mut engine : ai::Engine = serde_json::from_str(&serialized).unwrap();
+  assert_eq!(engine.unstarted_command(), Some(&command[ai::UCI_PREFIX.len()..]));
+  assert_eq!(engine.algorithm_id(), command);
+  engine.start_external().unwrap();
+  assert_eq!(engine.unstarted_command(), None);
+  assert_eq!(UCI::from(engine.best_move(Board::default())).0, "e2e4");
+
+  // deserialization doesn't start processes
+  let missing = format!("{}game_chess_no_such_engine", ai::UCI_PREFIX);
+  let mut engine : ai::Engine = serde_json::from_value(serde_json::json!({ "algorithm" : missing, "depth" : 3 })).unwrap();
+  assert!(!engine.best_move(Board::default()).is_null());
+  assert!(matches!(engine.start_external(), Err(ai::CreationError::EngineFailed(Error::Io(_)))));
+
+  assert!(matches!(ai::Engine::new(missing), Err(ai::CreationError::EngineFailed(Error::Io(_)))));
+  assert!(matches!(ai::Engine::new(ai::UCI_PREFIX.to_string()), Err(ai::CreationError::EngineFailed(Error::Engine(_)))));
+}
+
+#[test]
+fn test_skill_levels()
+{
+  assert_eq!(ai::Skill::new(0).level(), ai::MIN_SKILL_LEVEL);
+  assert_eq!(ai::Skill::new(30).level(), ai::MAX_SKILL_LEVEL);
+  assert_eq!(ai::Skill::from_elo(0).level(), ai::MIN_SKILL_LEVEL);
+  assert!(ai::Skill::from_elo(3000).is_full_strength());
+  assert_eq!(ai::Skill::from_elo(1600).elo(), Some(1600));
+  assert!(ai::Skill::new(5).elo() < ai::Skill::new(15).elo());
+  assert!(ai::Skill::new(5).depth() < ai::Skill::new(15).depth());
+  assert!(ai::Skill::new(5).error_budget() > ai::Skill::new(15).error_budget());
+
+  // limits are narrowed, full strength doesn't limit
+  let limits = ai::Skill::new(3).limit(ai::Limits::infinite());
+  assert_eq!(limits.depth, Some(ai::Skill::new(3).depth()));
+  assert_eq!(limits.nodes, Some(ai::Skill::new(3).nodes()));
+  assert!(!limits.infinite);
+  assert_eq!(ai::Skill::default().limit(ai::Limits::infinite()), ai::Limits::infinite());
+
+  // moves are picked among the lines within the error budget
+  let moves = Board::default().legal_moves();
+  let line = |centipawns : i32, m : Move| ai::Line { score : ai::Score::Centipawns(centipawns), pv : vec![m] };
+  let lines = vec![line(50, moves[0]), line(40, moves[1]), line(-2000, moves[2])];
+  let mut picked = std::collections::HashSet::new();
+  for _ in 0..200
+  {
+    let m = ai::Skill::new(1).choose(&lines).unwrap();
+    assert_ne!(m, moves[2]);
+    picked.insert(m.stringify());
+    assert_eq!(ai::Skill::default().choose(&lines), Some(moves[0]));
+  }
+  assert_eq!(picked.len(), 2);
+  assert!(ai::Skill::new(1).choose(&[]).is_none());
+
+  // mate is never missed for a loss in centipawns
+  let mate = vec![ai::Line { score : ai::Score::Mate(1), pv : vec![moves[3]] }, line(900, moves[4])];
+  assert_eq!(ai::Skill::new(1).choose(&mate), Some(moves[3]));
+
+  // low skill deviates from the best move with the default algorithm and with algorithms which report no lines
+  for algorithm in ["iterative", "greedy"]
+  {
+    let mut engine = ai::Engine::new_with_depth(algorithm.to_string(), 2).unwrap();
+    let best = engine.best_move(Board::default());
+    engine.set_skill(Some(ai::Skill::new(1)));
+    let picked : std::collections::HashSet<String> = (0..40).map(|_| engine.best_move(Board::default()).stringify()).collect();
+    assert!(picked.len() > 1, "{}", algorithm);
+    assert!(picked.iter().any(|m| *m != best.stringify()), "{}", algorithm);
+  }
+
+  // skill is serialized with the engine
+  let mut engine = ai::Engine::new_with_depth("alpha_beta".to_string(), 4).unwrap();
+  engine.set_skill(Some(ai::Skill::new(3)));
+  let board = Board::default();
+  for _ in 0..5
+  {
+    assert!(board.legal_moves().contains(&engine.best_move(board.clone())));
+  }
+  let serialized = serde_json::to_string(&engine).unwrap();
+  assert!(serialized.contains(r#""skill":3"#));
+  let engine : ai::Engine = serde_json::from_str(&serialized).unwrap();
+  assert_eq!(engine.skill(), Some(ai::Skill::new(3)));
+  assert!(serde_json::from_str::<ai::Engine>(&serialized.replace(r#""skill":3"#, r#""skill":25"#)).is_err());
+}
+
+#[test]
+fn test_pondering()
+{
+  use std::time::{Duration, Instant};
+  let engine = ai::Engine::new_with_depth("alpha_beta".to_string(), 3).unwrap();
+  let board = Board::default().make_move("e2e4".into()).unwrap();
+
+  // expected reply is the second move of the principal variation
+  let mut infos = Vec::new();
+  let m = engine.search(Board::default(), ai::Limits::depth(3), ai::CancelHandle::new(), |info| infos.push(info.clone()));
+  let info = infos.iter().rev().find(|info| info.multipv == 1).unwrap();
+  assert_eq!(info.best_move(), m);
+  let reply = info.ponder_move().unwrap();
+  let after = Board::default().make_move(UCI::from(m)).unwrap();
+  assert!(after.legal_moves().contains(&reply));
+
+  // pondering search doesn't stop by time before the ponder hit
+  let handle = engine.start_ponder(board.clone(), ai::Limits::movetime(Duration::from_millis(20)));
+  std::thread::sleep(Duration::from_millis(200));
+  assert!(handle.is_pondering());
+  assert!(!handle.is_finished());
+  assert!(!handle.poll().is_empty());
+
+  // on the ponder hit the search keeps its work and stops by its limits
+  let hit = Instant::now();
+  handle.ponder_hit();
+  assert!(!handle.is_pondering());
+  let m = handle.wait();
+  assert!(hit.elapsed() < Duration::from_secs(5));
+  assert!(board.legal_moves().contains(&m));
+
+  // on a miss the pondering search is stopped and a new search is started
+  let handle = engine.start_ponder(board.clone(), ai::Limits::movetime(Duration::from_millis(20)));
+  let _ = handle.stop();
+  let other = Board::default().make_move("d2d4".into()).unwrap();
+  let m = engine.start(other.clone(), ai::Limits::depth(2)).wait();
+  assert!(other.legal_moves().contains(&m));
+
+  // search which doesn't ponder ignores the ponder hit
+  let handle = engine.start(board.clone(), ai::Limits::depth(2));
+  assert!(!handle.is_pondering());
+  assert!(handle.ponder_handle().is_none());
+  handle.ponder_hit();
+  assert!(board.legal_moves().contains(&handle.wait()));
+}
+
+#[test]
+fn test_game_pondering()
+{
+  let mut game = Game::default();
+  game.ai = Some(ai::Engine::new_with_depth("alpha_beta".to_string(), 3).unwrap());
+  assert_eq!(game.start_ponder(ai::Limits::default()), None);
+  game.make_move_san("e4").unwrap();
+  game.make_move_ai(ai::Limits::default()).unwrap();
+
+  // ponder hit: the engine answers the expected reply with the pondering search
+  let reply = game.expected_reply().unwrap();
+  assert_eq!(game.start_ponder(ai::Limits::default()), Some(reply));
+  assert!(game.is_pondering());
+  game.make_move(UCI::from(reply)).unwrap();
+  game.make_move_ai(ai::Limits::default()).unwrap();
+  assert!(!game.is_pondering());
+  assert_eq!(game.ply(), 4);
+
+  // ponder miss: the pondering is stopped and the engine searches again
+  let reply = game.start_ponder(ai::Limits::default()).unwrap();
+  let other = game.board().legal_moves().into_iter().find(|m| *m != reply).unwrap();
+  game.make_move(UCI::from(other)).unwrap();
+  game.make_move_ai(ai::Limits::default()).unwrap();
+  assert!(!game.is_pondering());
+  assert_eq!(game.ply(), 6);
+
+  // the expected reply belongs to the position after the move of the engine
+  game.undo().unwrap();
+  assert_eq!(game.expected_reply(), None);
+  assert_eq!(game.start_ponder(ai::Limits::default()), None);
+  game.stop_ponder();
+}
@@ -0,0 +1,46 @@
+#!/bin/sh
+#
+# Stand-in UCI engine for tests. Plays the move of its option Move, e2e4 by default,
+# and answers infinite search only after stop.
+#
+
+move=e2e4
+infinite=0
+while read -r line
+do
+  case "$line" in
+    uci)
+      echo "id name Scripted Engine"
+      echo "id author game_chess"
+      echo "option name Hash type spin default 1 min 1 max 64"
+      echo "option name Move type string default e2e4"
+      echo "uciok"
+      ;;
+    isready)
+      echo "readyok"
+      ;;
+    "setoption name Move value "*)
+      move="${line#setoption name Move value }"
+      ;;
+    "go infinite"*)
+      infinite=1
+      echo "info depth 1 score cp 12 nodes 20 time 1 pv $move"
+      ;;
+    go*)
+      echo "info string thinking"
+      echo "info depth 1 score cp 12 nodes 20 time 1 pv $move"
+      echo "info depth 2 score mate 3 nodes 50 time 2 pv $move"
+      echo "bestmove $move"
+      ;;
+    stop)
+      if [ "$infinite" = 1 ]
+      then
+        infinite=0
+        echo "bestmove $move"
+      fi
+      ;;
+    quit)
+      exit 0
+      ;;
+  esac
+done
@@ -8,11 +8,11 @@
 /*
 Commands
 
-.game.new - creates game with default board
+.game.new - creates game with default board or Chess960 start position
 .game.from.fen - creates game [feature: game from fen]
 [issue: implement command game.from.fen]
 
-.games.list - list games [feature: persistence][issue: implement persistency]
+.games.list - list games [feature: persistence]
 .game.open [id] - opens the game from storage [feature: persistence]
 .game.save - saves current game state [feature: persistence]
 
@@ -20,12 +20,15 @@ Commands
 [issue: prompt for quit]
 [issue: prompt for save][feature:persistence]
 
-.resume [issue: implement timer ][feature: timer]
-.pause [feature: timer]
+.resume - continue the clock [feature: timer]
+.pause - stop the clock for a while [feature: timer]
+.clock - print time left of players [feature: timer]
 
 .status - print board, current turn, last move
 [issue:extend status to print score][feature:board score]
 
+.analyze - print best lines of the position with scores
+
 .move a1a2 - make a move
 
 .moves.list - prints list of legal moves
@@ -112,16 +115,26 @@ pub async fn main()
       ".game.new" => game = Some(command_game_new()),
       ".game.new.ai" | ".new.ai" => game = command_game_new_ai(),
       ".game.save" => command_game_save(&game),
-      ".game.from.fen" => game = Some(command_game_from_fen()),
+      ".games.list" => command_games_list(),
+      ".game.open" => command_game_open(&mut game),
+      ".game.pgn" => command_game_pgn(&game),
+      ".game.from.fen" => game = command_game_from_fen(),
       ".move" | ".m" => command_move(&mut game),
       ".gg" => command_forfeit(&mut game),
+      ".draw" => command_claim_draw(&mut game),
       ".moves.list" => command_moves_list(&game),
       ".move.ai" => command_move_ai(&mut game),
+      ".move.undo" => command_move_undo(&mut game),
+      ".move.redo" => command_move_redo(&mut game),
       ".status" | ".s" => command_status(&game),
       ".moves.history" | ".m.h" => command_moves_history(&game),
       ".quit" => command_exit(&game),
       ".help" => command_help(),
       ".score" => command_score(&game),
+      ".analyze" => command_analyze(&game),
+      ".pause" => command_pause(&mut game),
+      ".resume" => command_resume(&mut game),
+      ".clock" => command_clock(&game),
 
       ".online.new" => multiplayer::command_game_new(&mut session, &mut remote_rpc).await,
       ".online.join" => multiplayer::command_game_join(&mut session, &mut remote_rpc).await,
@@ -144,15 +157,26 @@ pub fn command_help()
 {
   println!("\nCommands:\n");
 
-  println!(".game.new  => Create game with default board");
+  println!(".game.new  => Create game with default board or Chess960 start position");
   println!(".new.ai    => Create game with ai. Also shortcut for .game.new.ai");
   println!(".game.save => Save game to file");
+  println!(".games.list => List saved games");
+  println!(".game.open => Open saved game");
+  println!(".game.pgn  => Print game in PGN");
   println!(".game.from.fen => Load game from FEN");
-  println!(".move      => Make a move by providing move in UCI format: \"a2a4\" ");
+  println!(".move      => Make a move by providing move in SAN or UCI format: \"Nf3\", \"a2a4\" ");
   println!(".gg        => Forfeit the game ");
+  println!(".draw      => Claim a draw by threefold repetition or the fifty-move rule");
   println!(".moves.list=> Print all available moves in UCI format: \"a2a4\" ");
   println!(".move.ai   => Ask the AI to make a move for the player");
+  println!(".move.undo => Take back the last move");
+  println!(".move.redo => Make again the move taken back");
   println!(".status    => Print board, current turn, last move");
+  println!(".score     => Print evaluation of the position term by term");
+  println!(".analyze   => Print best lines of the position found by the alpha-beta searcher");
+  println!(".pause     => Pause the clock");
+  println!(".resume    => Resume the clock");
+  println!(".clock     => Print time left of players");
   println!(".moves.history => Print moves history");
   println!(".quit      => Exit from the game");
   println!(".help      => Print this help");
@@ -184,7 +208,11 @@ pub fn command_exit(game : &Option<Game>)
 
 pub fn command_game_new() -> Game
 {
-  let game = Game::default();
+  let mut game = ask_start_position();
+  if let Some(clock) = ask_clock()
+  {
+    game.set_clock(clock);
+  }
   println!();
   game.board_print();
   println!("Turn of {}", game.current_turn());
@@ -197,7 +225,7 @@ pub fn command_game_new() -> Game
 
 pub fn command_game_new_ai() -> Option<Game>
 {
-  let mut algorithm = wca::input::ask("\nPlease select the ai engine algorithm (default = iterative)");
+  let mut algorithm = wca::input::ask("\nPlease select the ai engine algorithm or uci:<path> of an external engine (default = iterative)");
   if algorithm.is_empty()
   {
     algorithm = String::from("iterative")
@@ -205,9 +233,9 @@ pub fn command_game_new_ai() -> Option<Game>
   let mut engine = match ai::Engine::new(algorithm)
   {
     Ok(engine) => engine,
-    Err(_) =>
+    Err(error) =>
     {
-      println!("Unknown engine type, please try again.");
+      println!("{}, please try again.", error);
       return None;
     }
   };
@@ -227,8 +255,67 @@ pub fn command_game_new_ai() -> Option<Game>
     }
   };
 
-  let mut game = Game::default();
+  let skill = wca::input::ask("\nPlease select the ai skill level from 1 to 20, or Elo above 20 (default = full strength)");
+  if !skill.is_empty()
+  {
+    match skill.trim().parse::<u32>()
+    {
+      Ok(level) if level <= ai::MAX_SKILL_LEVEL as u32 => engine.set_skill(Some(ai::Skill::new(level as u8))),
+      Ok(elo) => engine.set_skill(Some(ai::Skill::from_elo(elo))),
+      Err(_) =>
+      {
+        println!("Failed to parse number.");
+        return None;
+      }
+    }
+  }
+
+  let book = wca::input::ask("\nPlease enter path to Polyglot opening book (default = no book)");
+  if !book.is_empty()
+  {
+    let book = match ai::Book::open(book.trim())
+    {
+      Ok(book) => book,
+      Err(error) =>
+      {
+        println!("Failed to open the book : {}", error);
+        return None;
+      }
+    };
+    let mode = wca::input::ask("\nPlease select how book moves are picked: best or weighted (default = weighted)");
+    let mode = match mode.trim()
+    {
+      "" | "weighted" => ai::BookMode::Weighted,
+      "best" => ai::BookMode::Best,
+      _ =>
+      {
+        println!("Unknown mode, please try again.");
+        return None;
+      }
+    };
+    engine.set_book(Some(book.with_mode(mode)));
+  }
+
+  let tablebase = wca::input::ask("\nPlease enter directory of Syzygy tablebases (default = no tablebases)");
+  if !tablebase.is_empty()
+  {
+    match Tablebase::open(tablebase.trim())
+    {
+      Ok(tablebase) => engine.set_tablebase(Some(tablebase)),
+      Err(error) =>
+      {
+        println!("Failed to open the tablebases : {}", error);
+        return None;
+      }
+    }
+  }
+
+  let mut game = ask_start_position();
   game.ai = Some(engine);
+  if let Some(clock) = ask_clock()
+  {
+    game.set_clock(clock);
+  }
 
   println!();
   game.board_print();
@@ -261,6 +348,13 @@ pub fn command_status(game : &Option<Game>)
     Some(m) => println!("Last move: {}", m.0),
     _ => println!("Last move: None"),
   }
+
+  print_clock(game);
+  println!("Status: {:?}", game.status());
+  if let Some(draw) = game.draw_claimable()
+  {
+    println!("Draw can be claimed: {:?}. Use command: .draw", draw);
+  }
 }
 
 ///
@@ -277,9 +371,132 @@ pub fn command_game_save(game : &Option<Game>)
 
   let game = game.as_ref().unwrap();
 
-  let save_path = game.save();
+  match game.save()
+  {
+    Ok(save_path) => println!("Saved game to file: {}", save_path),
+    Err(error) => println!("\n\x1b[93mFailed to save game: {}\x1b[0m", error),
+  }
+}
+
+///
+/// Command to list saved games.
+///
+
+pub fn command_games_list()
+{
+  let saves = match SaveStore::default().list()
+  {
+    Ok(saves) => saves,
+    Err(error) =>
+    {
+      println!("\n\x1b[93mFailed to list games: {}\x1b[0m", error);
+      return;
+    }
+  };
+
+  if saves.is_empty()
+  {
+    println!("There are no saved games. Use command: .game.save");
+    return;
+  }
+
+  println!();
+  for save in saves
+  {
+    match save.header
+    {
+      Ok(header) => println!(
+        "{} : {} vs {}, {} half-moves, {:?}, started at {}",
+        save.id,
+        header.white.as_deref().unwrap_or("?"),
+        header.black.as_deref().unwrap_or("?"),
+        header.moves,
+        header.status,
+        header.date
+      ),
+      Err(error) => println!("{} : \x1b[93mcan't be read, {}\x1b[0m", save.id, error),
+    }
+  }
+}
+
+///
+/// Command to open saved game.
+///
+
+pub fn command_game_open(game : &mut Option<Game>)
+{
+  let id = wca::input::ask("Provide id of the game, see .games.list");
+  match SaveStore::default().load(id.trim())
+  {
+    Ok(mut loaded) =>
+    {
+      if let Some(engine) = &mut loaded.ai
+      {
+        start_external_engine(engine);
+        report_unloaded_files(engine);
+      }
+      *game = Some(loaded);
+      command_status(game);
+    }
+    Err(error) => println!("\n\x1b[93mFailed to open game: {}\x1b[0m", error),
+  }
+}
+
+///
+/// Asks whether to start the external engine of an opened game. Saves come from anywhere, so the command is shown first.
+/// Without the engine its moves are picked by the evaluator.
+///
+
+fn start_external_engine(engine : &mut ai::Engine)
+{
+  let command = match engine.unstarted_command()
+  {
+    Some(command) => command.to_string(),
+    None => return,
+  };
+  let answer = wca::input::ask(&format!("\nThe game uses the external engine `{}`. Do you want to start it?", command));
+  if matches!(answer.to_lowercase().trim(), "yes" | "y")
+  {
+    if let Err(error) = engine.start_external()
+    {
+      println!("\n\x1b[93mFailed to start the engine: {}\x1b[0m", error);
+    }
+  }
+}
+
+///
+/// Warns about the opening book and the tablebase of an opened game which can't be read. The engine plays without them.
+///
 
-  println!("Saved game to file: {}", save_path.unwrap());
+fn report_unloaded_files(engine : &ai::Engine)
+{
+  if let Some(book) = engine.book().filter(|book| !book.is_loaded())
+  {
+    println!("\n\x1b[93mFailed to read the opening book `{}`, the engine plays without it\x1b[0m", book.path().display());
+  }
+  if let Some(tablebase) = engine.tablebase().filter(|tablebase| !tablebase.is_loaded())
+  {
+    println!("\n\x1b[93mFailed to read the tablebase `{}`, the engine plays without it\x1b[0m", tablebase.directory().display());
+  }
+}
+
+///
+/// Command to print game in PGN.
+///
+
+pub fn command_game_pgn(game : &Option<Game>)
+{
+  if game.is_none()
+  {
+    println!("Create a game first. Use command: .game.new");
+    return;
+  }
+
+  match game.as_ref().unwrap().to_pgn()
+  {
+    Ok(pgn) => println!("\n{}", pgn),
+    Err(error) => println!("\n\x1b[93mFailed to export game: {}\x1b[0m", error),
+  }
 }
 
 ///
@@ -296,17 +513,29 @@ pub fn command_move(game : &mut Option<Game>)
 
   let game = game.as_mut().unwrap();
 
-  let uci_move = wca::input::ask("Provide move in UCI format, for example 'a2a4'");
-  if game.make_move(UCI(uci_move.clone()))
+  let input = wca::input::ask("Provide move in SAN or UCI format, for example 'Nf3' or 'a2a4'");
+  let result = if UCI(input.clone()).parse().is_ok()
   {
-    if game.has_ai()
-    {
-      game.make_move_ai();
-    }
+    game.make_move(UCI(input.clone()))
   }
   else
   {
-    println!("\n\x1b[93mFailed to apply move: '{}'. Try again!\x1b[0m", uci_move);
+    game.make_move_san(&input)
+  };
+  match result
+  {
+    Ok(_) =>
+    {
+      if game.has_ai()
+      {
+        match game.make_move_ai(ai::Limits::default())
+        {
+          Ok(_) => ponder(game),
+          Err(error) => println!("\n\x1b[93mAI failed to move: {}\x1b[0m", error),
+        }
+      }
+    }
+    Err(error) => println!("\n\x1b[93mFailed to apply move: '{}', {}. Try again!\x1b[0m", input, error),
   }
 
   println!();
@@ -332,10 +561,12 @@ pub fn command_forfeit(game : &mut Option<Game>)
     "yes" =>
     {
       let game = game.as_mut().unwrap();
-      game.forfeit();
-
       let player = game.current_turn();
-      println!("{:?} lose the game.", player);
+      match game.forfeit()
+      {
+        Ok(_) => println!("{:?} lose the game. {}.", player, game.result()),
+        Err(error) => println!("\n\x1b[93m{}\x1b[0m", error),
+      }
 
       println!("Exiting..");
       std::process::exit(0);
@@ -344,6 +575,25 @@ pub fn command_forfeit(game : &mut Option<Game>)
   }
 }
 
+///
+/// Command to claim a draw.
+///
+
+pub fn command_claim_draw(game : &mut Option<Game>)
+{
+  if game.is_none()
+  {
+    println!("Create a game first. Use command: .game.new");
+    return;
+  }
+
+  match game.as_mut().unwrap().claim_draw()
+  {
+    Ok(status) => println!("The game is drawn: {:?}", status),
+    Err(error) => println!("\n\x1b[93m{}\x1b[0m", error),
+  }
+}
+
 ///
 /// Wrapper and control flow
 ///
@@ -352,11 +602,87 @@ pub fn command_score(game : &Option<Game>)
 {
   match game
   {
-    Some(g) => println!("{}", g.count_score()),
+    Some(g) => println!("{}", g.evaluation()),
     None => println!("Game not found"),
   }
 }
 
+///
+/// Command to analyze the position. Prints each line as soon as the search finishes it, then the best lines.
+///
+
+pub fn command_analyze(game : &Option<Game>)
+{
+  let game = match game
+  {
+    Some(game) => game,
+    None =>
+    {
+      println!("Game not found");
+      return;
+    }
+  };
+
+  let mut seconds = wca::input::ask("\nPlease enter time of analysis in seconds (default = 5)");
+  if seconds.is_empty()
+  {
+    seconds = String::from("5");
+  }
+  let time = match seconds.parse::<f64>().ok().and_then(|seconds| std::time::Duration::try_from_secs_f64(seconds).ok())
+  {
+    Some(time) => time,
+    None =>
+    {
+      println!("Failed to parse number.");
+      return;
+    }
+  };
+
+  let mut lines = wca::input::ask("\nPlease enter number of lines (default = 1)");
+  if lines.is_empty()
+  {
+    lines = String::from("1");
+  }
+  let lines = match lines.parse::<usize>()
+  {
+    Ok(lines) => lines,
+    Err(_) =>
+    {
+      println!("Failed to parse number.");
+      return;
+    }
+  };
+
+  let board = game.board().clone();
+  let mut engine = ai::Engine::new(String::from("alpha_beta")).unwrap();
+  let tablebase = game.ai.as_ref().and_then(|ai| ai.tablebase()).cloned();
+  if let Some(probe) = tablebase.as_ref().and_then(|tablebase| board.tablebase_probe(tablebase))
+  {
+    let best_move = probe.best_move.and_then(|m| board.move_to_san(m).ok()).unwrap_or_default();
+    println!("Tablebase : {:?}, DTZ {}, best move {}", probe.wdl, probe.dtz, best_move);
+  }
+  engine.set_tablebase(tablebase);
+  let search = engine.start_analysis(board.clone(), ai::Limits::movetime(time), lines);
+  let mut best = Vec::new();
+  while let Some(info) = search.next_info()
+  {
+    let line = ai::Line { score : info.score, pv : info.pv };
+    println!("depth {:>2} line {} : {:>7} {}", info.depth, info.multipv, line.score, line.to_san(&board).join(" "));
+    if info.multipv == 1
+    {
+      best.clear();
+    }
+    best.push(line);
+  }
+  search.wait();
+
+  println!("\nBest lines:");
+  for (i, line) in best.iter().enumerate()
+  {
+    println!("{}. {:>7} {}", i + 1, line.score, line.to_san(&board).join(" "));
+  }
+}
+
 ///
 /// Command to print moves history.
 ///
@@ -397,14 +723,22 @@ pub fn command_moves_list(game : &Option<Game>)
 /// Load game from FEN
 ///
 
-pub fn command_game_from_fen() -> Game
+pub fn command_game_from_fen() -> Option<Game>
 {
   let line = wca::input::ask("Input FEN");
-  let game = Game::from_fen(&line);
+  let game = match Game::from_fen(&line)
+  {
+    Ok(game) => game,
+    Err(error) =>
+    {
+      println!("\n\x1b[93m{}\x1b[0m", error);
+      return None;
+    }
+  };
   println!();
   game.board_print();
   println!("Turn of {}", game.current_turn());
-  game
+  Some(game)
 }
 
 ///
@@ -420,8 +754,189 @@ pub fn command_move_ai(game : &mut Option<Game>)
   }
 
   let game = game.as_mut().unwrap();
-  game.make_move_ai();
+  match game.make_move_ai(ai::Limits::default())
+  {
+    Ok(_) => ponder(game),
+    Err(error) => println!("\n\x1b[93mAI failed to move: {}\x1b[0m", error),
+  }
   println!();
   game.board_print();
   println!("Turn of {}", game.current_turn());
 }
+
+///
+/// Lets AI think on the reply it expects while the player thinks, the next move of AI is faster if the player makes it.
+///
+
+fn ponder(game : &mut Game)
+{
+  if let Some(reply) = game.start_ponder(ai::Limits::default())
+  {
+    println!("AI expects {}", reply.stringify());
+  }
+}
+
+///
+/// Command to take back the last move
+///
+
+pub fn command_move_undo(game : &mut Option<Game>)
+{
+  if game.is_none()
+  {
+    println!("Create a game first. Use command: .game.new");
+    return;
+  }
+
+  let game = game.as_mut().unwrap();
+  if let Err(error) = game.undo()
+  {
+    println!("\n\x1b[93m{}\x1b[0m", error);
+  }
+  println!();
+  game.board_print();
+  println!("Turn of {}", game.current_turn());
+}
+
+///
+/// Command to make again the move taken back
+///
+
+pub fn command_move_redo(game : &mut Option<Game>)
+{
+  if game.is_none()
+  {
+    println!("Create a game first. Use command: .game.new");
+    return;
+  }
+
+  let game = game.as_mut().unwrap();
+  if let Err(error) = game.redo()
+  {
+    println!("\n\x1b[93m{}\x1b[0m", error);
+  }
+  println!();
+  game.board_print();
+  println!("Turn of {}", game.current_turn());
+}
+
+///
+/// Asks for start position of a new game: classical or Chess960 by index or random.
+///
+
+fn ask_start_position() -> Game
+{
+  loop
+  {
+    let position = wca::input::ask("\nPlease select Chess960 start position: number from 0 to 959 or 'random' (default = classical chess)");
+    let game = match position.trim()
+    {
+      "" => return Game::default(),
+      "random" => return Game::chess960_random(),
+      index => match index.parse::<u16>()
+      {
+        Ok(index) => Game::chess960(index).map_err(|error| error.to_string()),
+        Err(_) => Err(format!("'{}' is not a number", index)),
+      },
+    };
+    match game
+    {
+      Ok(game) => return game,
+      Err(error) => println!("\n\x1b[93m{}\x1b[0m", error),
+    }
+  }
+}
+
+///
+/// Asks for time control of a new game. Returns `None` for a game without clock.
+///
+
+fn ask_clock() -> Option<Clock>
+{
+  loop
+  {
+    let control = wca::input::ask("\nPlease select time control: minutes, then '+' increment, 'b' Bronstein or 'd' simple delay in seconds, for example '5+3' (default = no clock)");
+    if control.trim().is_empty()
+    {
+      return None;
+    }
+    match control.parse::<TimeControl>()
+    {
+      Ok(control) => return Some(Clock::new(control)),
+      Err(error) => println!("\n\x1b[93m{}\x1b[0m", error),
+    }
+  }
+}
+
+///
+/// Prints time left of players if the game has clock.
+///
+
+fn print_clock(game : &Game)
+{
+  if let Some(clock) = game.clock()
+  {
+    let format = |time : std::time::Duration| format!("{:02}:{:02}.{:02}", time.as_secs() / 60, time.as_secs() % 60, time.subsec_millis() / 10);
+    println!(
+      "Clock ({}): White {} | Black {}{}",
+      clock.control(),
+      format(clock.remaining(Player::White)),
+      format(clock.remaining(Player::Black)),
+      if clock.is_paused() { " (paused)" } else { "" }
+    );
+  }
+}
+
+///
+/// Command to pause the clock
+///
+
+pub fn command_pause(game : &mut Option<Game>)
+{
+  if game.is_none()
+  {
+    println!("Create a game first. Use command: .game.new");
+    return;
+  }
+
+  let game = game.as_mut().unwrap();
+  match game.pause()
+  {
+    Ok(_) => print_clock(game),
+    Err(error) => println!("\n\x1b[93m{}\x1b[0m", error),
+  }
+}
+
+///
+/// Command to resume the clock
+///
+
+pub fn command_resume(game : &mut Option<Game>)
+{
+  if game.is_none()
+  {
+    println!("Create a game first. Use command: .game.new");
+    return;
+  }
+
+  let game = game.as_mut().unwrap();
+  match game.resume()
+  {
+    Ok(_) => print_clock(game),
+    Err(error) => println!("\n\x1b[93m{}\x1b[0m", error),
+  }
+}
+
+///
+/// Command to print time left of players
+///
+
+pub fn command_clock(game : &Option<Game>)
+{
+  match game
+  {
+    None => println!("Create a game first. Use command: .game.new"),
+    Some(game) if game.clock().is_none() => println!("The game has no clock"),
+    Some(game) => print_clock(game),
+  }
+}
@@ -202,8 +202,9 @@ pub fn diagnostics_rect( commands : &mut Commands, materials : &mut ResMut< Asse
 pub fn core_setup( mut commands : Commands, mut game_state : ResMut< State< GameState > > )
 {
   let mut game = core::Game::default();
+  game.set_clock( core::Clock::new( core::TimeControl::SuddenDeath { base : std::time::Duration::from_secs( 10 * 60 ) } ) );
   game.board_print();
-  game.make_move( "a2a4".into() );
+  game.make_move( "a2a4".into() ).unwrap();
   game.board_print();
   commands.insert_resource( game );
 
@@ -263,12 +264,21 @@ pub fn egui_setup
   mut egui_context : ResMut< EguiContext >,
   mut materials : ResMut< Assets< ColorMaterial > >,
   materials_handles : Res< Materials >,
+  game : Option< Res< core::Game > >,
 )
 {
   egui::Window::new( "Timer" ).show( egui_context.ctx_mut(), | ui |
   {
     // add labels inside Egui window
-    ui.label( "Time: 00:00.00" );
+    match game.as_ref().and_then( | game | game.clock() )
+    {
+      Some( clock ) =>
+      {
+        ui.label( format!( "White: {}", time_format( clock.remaining( core::Player::White ) ) ) );
+        ui.label( format!( "Black: {}", time_format( clock.remaining( core::Player::Black ) ) ) );
+      }
+      None => { ui.label( "Time: 00:00.00" ); }
+    }
   });
 
   egui::SidePanel::left( "Menu" )
@@ -289,6 +299,15 @@ pub fn egui_setup
   });
 }
 
+///
+/// Formats time left on the clock as "mm:ss.cc".
+///
+
+fn time_format( time : std::time::Duration ) -> String
+{
+  format!( "{:02}:{:02}.{:02}", time.as_secs() / 60, time.as_secs() % 60, time.subsec_millis() / 10 )
+}
+
 #[ derive( Component ) ]
 struct GameTimer
 {
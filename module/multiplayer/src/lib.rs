@@ -1,11 +1,13 @@
 #[allow(non_camel_case_types)]
 pub mod generated;
 use generated::chess::GamePlayer;
+use game_chess_core::{GameResult, Player};
 
 use time::{OffsetDateTime, format_description};
 
 
 pub use generated::chess::MultiplayerGame;
+pub use generated::chess::GameEnd;
 
 ///
 /// Message.
@@ -89,3 +91,24 @@ impl MultiplayerGame
 
   pub fn add_opponent(&mut self, player : GamePlayer) { self.players.push(player) }
 }
+
+impl GameEnd
+{
+  ///
+  /// Message about the end of the game. `winner_id` is empty if the game is drawn.
+  ///
+  pub fn from_result(result : GameResult, white_id : &str, black_id : &str) -> Self
+  {
+    let winner_id = match result.winner()
+    {
+      Some(Player::White) => white_id,
+      Some(Player::Black) => black_id,
+      None => "",
+    };
+
+    Self {
+      winner_id : winner_id.to_string(),
+      reason : result.to_string(),
+    }
+  }
+}
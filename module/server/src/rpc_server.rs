@@ -13,7 +13,7 @@ use tonic::{Request, Response, Status};
 use tokio::sync::mpsc;
 
 use game_chess_core::{UCI, Player};
-use multiplayer::{MultiplayerStatus, MultiplayerMessage};
+use multiplayer::{MultiplayerStatus, MultiplayerMessage, GameEnd};
 use multiplayer::generated::chess::chess_server::Chess;
 use crate::store::GameStore;
 use multiplayer::generated::chess::{
@@ -312,36 +312,33 @@ impl Chess for ChessRpcServer
     let game_id = message.game_id;
     let player_id = message.player_id;
 
-    let _winner = {
+    let game_end = {
       let mut memory_store = self.store.lock().unwrap();
-      let mut winner = None;
-      let mut current_game = memory_store.get_game(&game_id).unwrap().clone();
+      let mut current_game = match memory_store.get_game(&game_id)
+      {
+        Some(game) => game.clone(),
+        None => return Err(Status::not_found(format!("No game found by the Game ID: {}", game_id))),
+      };
 
-      memory_store.update_game(&game_id, current_game.clone());
-      for player in &current_game.players
+      // The player who created the game plays white.
+      let white_id = current_game.players.first().map(|player| player.player_id.clone()).unwrap_or_default();
+      let black_id = current_game.players.get(1).map(|player| player.player_id.clone()).unwrap_or_default();
+      let player = match current_game.players.iter().position(|player| player.player_id == player_id)
       {
-        if player.player_id != player_id
-        {
-          winner = Some(player.clone());
-          break;
-        }
-      }
+        Some(0) => Player::White,
+        Some(1) => Player::Black,
+        _ => return Err(Status::invalid_argument(format!("Player {} doesn't play the game {}", player_id, game_id))),
+      };
+
+      let result = memory_store.resign(&game_id, player).map_err(Status::failed_precondition)?;
 
       current_game.status = MultiplayerStatus::Ended as i32;
-      winner
+      memory_store.update_game(&game_id, current_game);
+
+      GameEnd::from_result(result, &white_id, &black_id)
     };
-    // Will be moved from function push_mgs
-    //
-    // let request;
-    // if let Some(winner) = winner {
-    //   let msg = format!("The player {} gave up. The player {} is the winner!", player_id, winner.player_id);
-    //   request = Request::new(Msg {
-    //     player: Some(message),
-    //     text: msg.into(),
-    //   });
-    // }
-
-    // return self.push_mgs(request).await;
+
+    self.push_game_update(chess::game_update::GameUpdate::GameEnd(game_end)).await;
 
     Ok(Response::new(()))
   }
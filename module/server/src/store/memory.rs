@@ -6,7 +6,7 @@ use std::{collections::HashMap};
 
 #[allow(unused_imports)]
 use tonic::async_trait;
-use game_chess_core::{Game as GameInstance, UCI, Player, MoveList};
+use game_chess_core::{Game as GameInstance, UCI, Player, Move, GameResult};
 use multiplayer::{MultiplayerGame as Game, MultiplayerMessage as Chat};
 
 use crate::store::GameStore;
@@ -170,15 +170,28 @@ impl GameStore for MemoryStore
     let uci_move = UCI::from(r#move);
 
     // Assumes `game_id` has already been checked!
-    self.game_instances.get_mut(game_id).unwrap().make_move(uci_move)
+    self.game_instances.get_mut(game_id).unwrap().make_move(uci_move).is_ok()
   }
 
   ///
   /// Returns available moves on the board
   ///
-  fn moves_list(&self, game_id : &str) -> MoveList
+  fn moves_list(&self, game_id : &str) -> Vec<Move>
   {
     // Assumes `game_id` has already been checked!
     self.game_instances.get(game_id).unwrap().moves_list()
   }
+
+  ///
+  /// The player resigns. Returns result of the game.
+  ///
+  fn resign(&mut self, game_id : &str, player : Player) -> Result<GameResult, String>
+  {
+    let game = self
+      .game_instances
+      .get_mut(game_id)
+      .ok_or_else(|| format!("No game found by the Game ID: {}", game_id))?;
+    game.resign(player).map_err(|error| error.to_string())?;
+    Ok(game.result())
+  }
 }
@@ -1,6 +1,6 @@
 pub mod memory;
 
-use game_chess_core::{UCI, Player, MoveList};
+use game_chess_core::{UCI, Player, Move, GameResult};
 use multiplayer::{MultiplayerGame, MultiplayerMessage};
 
 ///
@@ -33,5 +33,7 @@ pub trait GameStore
   /// Makes a move on the board.
   fn make_move(&mut self, game_id : &str, r#move : &str) -> bool;
   /// Returns available moves on the board.
-  fn moves_list(&self, game_id : &str) -> MoveList;
+  fn moves_list(&self, game_id : &str) -> Vec<Move>;
+  /// The player resigns. Returns result of the game.
+  fn resign(&mut self, game_id : &str, player : Player) -> Result<GameResult, String>;
 }
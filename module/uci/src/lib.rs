@@ -0,0 +1,421 @@
+#![warn(missing_docs)]
+#![warn(missing_debug_implementations)]
+
+//!
+//! Universal Chess Interface ( UCI ) protocol of [ai::Engine]. Commands are read line by line,
+//! searches run in their own thread, so `stop`, `ponderhit` and `isready` are answered while the engine thinks.
+//!
+
+use game_chess_core::*;
+
+use std::io::{BufRead, Write};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+///
+/// Output shared by the protocol and the thread which reports the search.
+///
+
+type Output = Arc<Mutex<dyn Write + Send>>;
+
+///
+/// State of the engine between commands: the engine with its options and the position to search.
+///
+
+pub struct Uci
+{
+  engine : ai::Engine,
+  board : Board,
+  lines : usize,
+  book_mode : ai::BookMode,
+  output : Output,
+  search : Option<RunningSearch>,
+}
+
+///
+/// Search started by `go`. The reporter prints the information of the search and then the best move.
+///
+
+struct RunningSearch
+{
+  cancel : ai::CancelHandle,
+  ponder : Option<ai::PonderHandle>,
+  infinite : bool,
+  reporter : thread::JoinHandle<()>,
+}
+
+impl RunningSearch
+{
+  ///
+  /// True if the search doesn't finish by itself: it is infinite or ponders.
+  ///
+  fn waits_for_stop(&self) -> bool { waits_for_stop(self.infinite, self.ponder.as_ref()) }
+}
+
+impl Uci
+{
+  ///
+  /// Constructs engine with default options and the start position. Responses are written to the output.
+  ///
+  pub fn new(output : impl Write + Send + 'static) -> Self
+  {
+    Self {
+      engine : ai::Engine::new(String::from("alpha_beta")).expect("alpha_beta is a known algorithm"),
+      board : Board::default(),
+      lines : 1,
+      book_mode : ai::BookMode::default(),
+      output : Arc::new(Mutex::new(output)),
+      search : None,
+    }
+  }
+
+  ///
+  /// Handles a command. Returns `false` after `quit`. Unknown commands are ignored as the protocol demands.
+  ///
+  pub fn handle(&mut self, line : &str) -> bool
+  {
+    let tokens : Vec<&str> = line.split_whitespace().collect();
+    match tokens.first().copied()
+    {
+      Some("uci") => self.uci(),
+      Some("isready") => self.send("readyok"),
+      Some("ucinewgame") =>
+      {
+        self.stop();
+        self.board = Board::default();
+      }
+      Some("position") => self.position(&tokens[1..]),
+      Some("go") => self.go(&tokens[1..]),
+      Some("stop") => self.stop(),
+      Some("ponderhit") =>
+      {
+        if let Some(ponder) = self.search.as_ref().and_then(|search| search.ponder.as_ref())
+        {
+          ponder.hit();
+        }
+      }
+      Some("setoption") => self.set_option(&tokens[1..]),
+      Some("quit") =>
+      {
+        self.stop();
+        return false;
+      }
+      _ => (),
+    }
+    true
+  }
+
+  ///
+  /// Waits for the running search to finish. Infinite and pondering searches are stopped, they would never finish.
+  ///
+  pub fn finish(&mut self)
+  {
+    match &self.search
+    {
+      Some(search) if search.waits_for_stop() => self.stop(),
+      Some(_) =>
+      {
+        if let Some(search) = self.search.take()
+        {
+          let _ = search.reporter.join();
+        }
+      }
+      None => (),
+    }
+  }
+
+  fn uci(&self)
+  {
+    self.send(&format!("id name Game Chess {}", env!("CARGO_PKG_VERSION")));
+    self.send(&format!("id author {}", env!("CARGO_PKG_AUTHORS").replace(':', ", ")));
+    self.send("option name Algorithm type combo default alpha_beta var alpha_beta var iterative var min_max var greedy var random");
+    self.send("option name Evaluator type combo default default var default var pleco");
+    self.send(&format!("option name Depth type spin default {} min 1 max {}", ai::DEFAULT_DEPTH, ai::MAX_DEPTH));
+    self.send(&format!("option name Hash type spin default {} min 1 max {}", ai::DEFAULT_HASH_SIZE, ai::MAX_HASH_SIZE));
+    self.send("option name MultiPV type spin default 1 min 1 max 64");
+    self.send("option name Ponder type check default false");
+    self.send("option name Book type string default <empty>");
+    self.send("option name BookMode type combo default weighted var weighted var best");
+    self.send("option name SyzygyPath type string default <empty>");
+    self.send("uciok");
+  }
+
+  ///
+  /// `position startpos|fen <fen> [moves <move>...]`
+  ///
+  fn position(&mut self, tokens : &[&str])
+  {
+    let moves_at = tokens.iter().position(|token| *token == "moves").unwrap_or(tokens.len());
+    let (setup, moves) = tokens.split_at(moves_at);
+    let board = match setup.first().copied()
+    {
+      Some("startpos") => Ok(Board::default()),
+      Some("fen") => Board::from_fen(&Fen::from(setup[1..].join(" "))),
+      _ =>
+      {
+        self.send("info string position needs startpos or fen");
+        return;
+      }
+    };
+    let mut board = match board
+    {
+      Ok(board) => board,
+      Err(error) =>
+      {
+        self.send(&format!("info string {}", error));
+        return;
+      }
+    };
+    for m in moves.iter().skip(1)
+    {
+      board = match board.make_move(UCI::from(*m))
+      {
+        Ok(board) => board,
+        Err(error) =>
+        {
+          self.send(&format!("info string {}", error));
+          return;
+        }
+      };
+    }
+    self.board = board;
+  }
+
+  ///
+  /// `go [ponder] [depth <plies>] [nodes <count>] [movetime <ms>] [wtime <ms>] [btime <ms>] [winc <ms>] [binc <ms>] [movestogo <moves>] [infinite]`
+  ///
+  /// `go ponder` searches the position with the expected reply already played until `ponderhit` or `stop`.
+  ///
+  fn go(&mut self, tokens : &[&str])
+  {
+    self.stop();
+
+    let mut limits = ai::Limits::default();
+    let mut time = [None, None];
+    let mut increment = [0, 0];
+    let mut moves_to_go = None;
+    let mut ponder = false;
+    let mut tokens = tokens.iter();
+    while let Some(token) = tokens.next()
+    {
+      let mut value = || tokens.next().and_then(|value| value.parse::<i64>().ok()).unwrap_or(0).max(0) as u64;
+      match *token
+      {
+        "depth" => limits.depth = Some(value().min(ai::MAX_DEPTH as u64) as u16),
+        "nodes" => limits.nodes = Some(value()),
+        "movetime" => limits.movetime = Some(Duration::from_millis(value())),
+        "wtime" => time[0] = Some(value()),
+        "btime" => time[1] = Some(value()),
+        "winc" => increment[0] = value(),
+        "binc" => increment[1] = value(),
+        "movestogo" => moves_to_go = Some(value() as u32),
+        "infinite" => limits.infinite = true,
+        "ponder" => ponder = true,
+        _ => (),
+      }
+    }
+    let turn = if self.board.current_turn() == Player::White { 0 } else { 1 };
+    if let Some(remaining) = time[turn]
+    {
+      limits.clock = Some(ai::TimeLeft {
+        remaining : Duration::from_millis(remaining),
+        increment : Duration::from_millis(increment[turn]),
+        moves_to_go,
+      });
+    }
+
+    let handle = if ponder
+    {
+      self.engine.start_ponder(self.board.clone(), limits)
+    }
+    else if self.lines > 1
+    {
+      self.engine.start_analysis(self.board.clone(), limits, self.lines)
+    }
+    else
+    {
+      self.engine.start(self.board.clone(), limits)
+    };
+    let cancel = handle.cancel_handle();
+    let ponder = handle.ponder_handle();
+    let infinite = limits.infinite;
+    let output = self.output.clone();
+    let reporter =
+    {
+      let (cancel, ponder) = (cancel.clone(), ponder.clone());
+      thread::spawn(move ||
+      {
+        let mut expected = None;
+        while let Some(info) = handle.next_info()
+        {
+          if info.multipv == 1
+          {
+            expected = info.ponder_move().map(|reply| (info.best_move(), reply));
+          }
+          write_line(&output, &info_line(&info));
+        }
+        // the best move of infinite or pondering search is sent only after stop or ponder hit
+        while waits_for_stop(infinite, ponder.as_ref()) && !cancel.is_cancelled()
+        {
+          thread::sleep(Duration::from_millis(1));
+        }
+        let best_move = handle.wait();
+        match expected
+        {
+          Some((first, reply)) if first == best_move =>
+          {
+            write_line(&output, &format!("bestmove {} ponder {}", best_move.stringify(), reply.stringify()));
+          }
+          _ => write_line(&output, &format!("bestmove {}", best_move.stringify())),
+        }
+      })
+    };
+    self.search = Some(RunningSearch { cancel, ponder, infinite, reporter });
+  }
+
+  ///
+  /// Stops the running search and waits until its best move is sent.
+  ///
+  fn stop(&mut self)
+  {
+    if let Some(search) = self.search.take()
+    {
+      search.cancel.cancel();
+      let _ = search.reporter.join();
+    }
+  }
+
+  ///
+  /// `setoption name <name> [value <value>]`
+  ///
+  fn set_option(&mut self, tokens : &[&str])
+  {
+    let value_at = tokens.iter().position(|token| *token == "value").unwrap_or(tokens.len());
+    let name = tokens[..value_at].iter().skip(1).copied().collect::<Vec<&str>>().join(" ");
+    let value = tokens.get(value_at + 1..).map(|value| value.join(" ")).unwrap_or_default();
+
+    let result = match name.to_lowercase().as_str()
+    {
+      "algorithm" => self.engine.set_algorithm(value.clone()).map_err(|error| error.to_string()),
+      "evaluator" => ai::Engine::new_evaluator(&value)
+        .map(|evaluator| self.engine.set_evaluator(evaluator))
+        .map_err(|error| error.to_string()),
+      "depth" => value.parse().map(|depth : u16| self.engine.depth = depth.clamp(1, ai::MAX_DEPTH)).map_err(|error| error.to_string()),
+      "hash" => value.parse().map(|size : usize| self.engine.set_hash_size(size.clamp(1, ai::MAX_HASH_SIZE))).map_err(|error| error.to_string()),
+      "multipv" => value.parse().map(|lines : usize| self.lines = lines.max(1)).map_err(|error| error.to_string()),
+      "ponder" => value.parse::<bool>().map(|_| ()).map_err(|error| error.to_string()),
+      "book" if is_empty_path(&value) =>
+      {
+        self.engine.set_book(None);
+        Ok(())
+      }
+      "book" => ai::Book::open(&value)
+        .map(|book| self.engine.set_book(Some(book.with_mode(self.book_mode))))
+        .map_err(|error| error.to_string()),
+      "bookmode" => match value.to_lowercase().as_str()
+      {
+        "best" | "weighted" =>
+        {
+          self.book_mode = if value.eq_ignore_ascii_case("best") { ai::BookMode::Best } else { ai::BookMode::Weighted };
+          let book = self.engine.book().cloned().map(|book| book.with_mode(self.book_mode));
+          self.engine.set_book(book);
+          Ok(())
+        }
+        _ => Err(format!("unknown book mode '{}'", value)),
+      },
+      "syzygypath" if is_empty_path(&value) =>
+      {
+        self.engine.set_tablebase(None);
+        Ok(())
+      }
+      "syzygypath" => Tablebase::open(&value)
+        .map(|tablebase| self.engine.set_tablebase(Some(tablebase)))
+        .map_err(|error| error.to_string()),
+      _ => Err(format!("unknown option '{}'", name)),
+    };
+
+    if let Err(error) = result
+    {
+      self.send(&format!("info string {}", error));
+    }
+  }
+
+  fn send(&self, line : &str) { write_line(&self.output, line); }
+}
+
+impl std::fmt::Debug for Uci
+{
+  fn fmt(&self, f : &mut std::fmt::Formatter<'_>) -> std::fmt::Result
+  {
+    f.debug_struct("Uci")
+      .field("engine", &self.engine)
+      .field("board", &self.board.to_fen())
+      .field("lines", &self.lines)
+      .field("searching", &self.search.is_some())
+      .finish()
+  }
+}
+
+///
+/// Reads commands until `quit` or the end of the input. At the end of the input waits for the running search.
+///
+
+pub fn run(input : impl BufRead, output : impl Write + Send + 'static) -> std::io::Result<()>
+{
+  let mut uci = Uci::new(output);
+  for line in input.lines()
+  {
+    if !uci.handle(&line?)
+    {
+      return Ok(());
+    }
+  }
+  uci.finish();
+  Ok(())
+}
+
+///
+/// `info depth <plies> multipv <rank> score cp <centipawns>|mate <moves> nodes <count> nps <speed> time <ms> pv <moves>`
+///
+
+fn info_line(info : &ai::SearchInfo) -> String
+{
+  let score = match info.score
+  {
+    ai::Score::Centipawns(centipawns) => format!("cp {}", centipawns),
+    ai::Score::Mate(moves) => format!("mate {}", moves),
+  };
+  let milliseconds = info.time.as_millis() as u64;
+  let nps = (info.nodes * 1000).checked_div(milliseconds).unwrap_or(0);
+  let pv : Vec<String> = info.pv.iter().map(|m| m.stringify()).collect();
+  format!(
+    "info depth {} multipv {} score {} nodes {} nps {} time {} pv {}",
+    info.depth,
+    info.multipv,
+    score,
+    info.nodes,
+    nps,
+    milliseconds,
+    pv.join(" ")
+  )
+}
+
+///
+/// The best move of infinite search and of pondering search before the ponder hit is sent only after `stop`.
+///
+
+fn waits_for_stop(infinite : bool, ponder : Option<&ai::PonderHandle>) -> bool { infinite || ponder.is_some_and(|ponder| !ponder.is_hit()) }
+
+fn write_line(output : &Output, line : &str)
+{
+  let mut output = output.lock().unwrap();
+  let _ = writeln!(output, "{}", line);
+  let _ = output.flush();
+}
+
+///
+/// GUIs send "<empty>" or nothing to unset a path.
+///
+
+fn is_empty_path(value : &str) -> bool { value.is_empty() || value == "<empty>" }
@@ -0,0 +1,12 @@
+#![warn(missing_docs)]
+#![warn(missing_debug_implementations)]
+
+//!
+//! Chess engine speaking Universal Chess Interface ( UCI ) protocol over stdin and stdout.
+//!
+
+fn main() -> std::io::Result<()>
+{
+  let stdin = std::io::stdin();
+  game_chess_uci::run(stdin.lock(), std::io::stdout())
+}
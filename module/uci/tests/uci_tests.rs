@@ -0,0 +1,166 @@
+use std::io::Write;
+use std::sync::{Arc, Mutex};
+
+/*
+cargo test -p game_chess_uci -- --show-output
+*/
+
+///
+/// Output shared with the engine, so it can be read after the engine is dropped.
+///
+
+#[derive(Clone, Default)]
+struct Buffer(Arc<Mutex<Vec<u8>>>);
+
+impl Write for Buffer
+{
+  fn write(&mut self, buf : &[u8]) -> std::io::Result<usize>
+  {
+    self.0.lock().unwrap().extend_from_slice(buf);
+    Ok(buf.len())
+  }
+
+  fn flush(&mut self) -> std::io::Result<()> { Ok(()) }
+}
+
+impl Buffer
+{
+  fn lines(&self) -> Vec<String> { String::from_utf8_lossy(&self.0.lock().unwrap()).lines().map(String::from).collect() }
+}
+
+fn session(commands : &[&str]) -> Vec<String>
+{
+  let output = Buffer::default();
+  let input = commands.join("\n");
+  game_chess_uci::run(input.as_bytes(), output.clone()).unwrap();
+  output.lines()
+}
+
+fn best_move(lines : &[String]) -> &str
+{
+  let line = lines.iter().rev().find(|line| line.starts_with("bestmove ")).expect("no best move");
+  line.split_whitespace().nth(1).unwrap()
+}
+
+#[test]
+fn test_uci_handshake()
+{
+  let lines = session(&["uci", "isready", "quit"]);
+  assert!(lines[0].starts_with("id name "));
+  assert!(lines[1].starts_with("id author "));
+  assert!(lines.iter().any(|line| line.starts_with("option name Hash type spin")));
+  assert!(lines.iter().any(|line| line.starts_with("option name MultiPV type spin")));
+  let uciok = lines.iter().position(|line| line == "uciok").unwrap();
+  assert_eq!(lines[uciok + 1], "readyok");
+}
+
+#[test]
+fn test_uci_go_depth()
+{
+  let lines = session(&["ucinewgame", "position startpos moves e2e4 e7e5", "go depth 3"]);
+  assert!(lines.iter().any(|line| line.starts_with("info depth 3 multipv 1 score cp ")));
+  assert!(lines.last().unwrap().contains(" ponder "));
+  let m = best_move(&lines);
+  let board = game_chess_core::Board::default().make_move("e2e4".into()).unwrap().make_move("e7e5".into()).unwrap();
+  assert!(board.legal_moves().iter().any(|legal| legal.stringify() == m));
+}
+
+#[test]
+fn test_uci_mate_score()
+{
+  let lines = session(&["position fen 7k/8/8/8/8/8/R7/1R4K1 w - - 0 1", "go depth 4"]);
+  assert!(lines.iter().any(|line| line.contains(" score mate 2 ")));
+}
+
+#[test]
+fn test_uci_infinite_and_stop()
+{
+  let output = Buffer::default();
+  let mut uci = game_chess_uci::Uci::new(output.clone());
+  uci.handle("position startpos");
+  uci.handle("go infinite");
+  std::thread::sleep(std::time::Duration::from_millis(200));
+  assert!(output.lines().iter().all(|line| !line.starts_with("bestmove")));
+  uci.handle("stop");
+  let lines = output.lines();
+  assert!(lines.last().unwrap().starts_with("bestmove "));
+  assert_ne!(best_move(&lines), "0000");
+  assert!(uci.handle("isready"));
+  assert_eq!(output.lines().last().unwrap(), "readyok");
+  assert!(!uci.handle("quit"));
+}
+
+#[test]
+fn test_uci_clock()
+{
+  let lines = session(&["position startpos moves e2e4", "go wtime 100 btime 1000 winc 0 binc 0"]);
+  assert_eq!(lines.iter().filter(|line| line.starts_with("bestmove ")).count(), 1);
+  assert_ne!(best_move(&lines), "0000");
+}
+
+#[test]
+fn test_uci_multipv()
+{
+  let lines = session(&["setoption name MultiPV value 3", "position startpos", "go depth 2"]);
+  for rank in 1..=3
+  {
+    assert!(lines.iter().any(|line| line.starts_with(&format!("info depth 2 multipv {} ", rank))));
+  }
+  assert_ne!(best_move(&lines), "0000");
+}
+
+#[test]
+fn test_uci_errors()
+{
+  let lines = session(&[
+    "position fen not a fen",
+    "position startpos moves e2e5",
+    "setoption name Algorithm value unknown",
+    "setoption name Missing value 1",
+    "unknown command",
+  ]);
+  assert_eq!(lines.len(), 4);
+  assert!(lines.iter().all(|line| line.starts_with("info string ")));
+}
+
+#[test]
+fn test_uci_options()
+{
+  let lines = session(&[
+    "setoption name algorithm value greedy",
+    "setoption name Depth value 1",
+    "setoption name Hash value 1",
+    "setoption name Book value <empty>",
+    "setoption name BookMode value best",
+    "position startpos",
+    "go",
+  ]);
+  assert!(lines.iter().all(|line| !line.starts_with("info string ")));
+  assert_ne!(best_move(&lines), "0000");
+}
+
+#[test]
+fn test_uci_ponder()
+{
+  let output = Buffer::default();
+  let mut uci = game_chess_uci::Uci::new(output.clone());
+  uci.handle("setoption name Ponder value true");
+  uci.handle("position startpos moves e2e4 e7e5");
+  uci.handle("go ponder wtime 1000 btime 1000");
+  std::thread::sleep(std::time::Duration::from_millis(200));
+  assert!(output.lines().iter().all(|line| !line.starts_with("bestmove")));
+
+  // after the ponder hit the search finishes by the clock
+  uci.handle("ponderhit");
+  let start = std::time::Instant::now();
+  while output.lines().iter().all(|line| !line.starts_with("bestmove")) && start.elapsed() < std::time::Duration::from_secs(10)
+  {
+    std::thread::sleep(std::time::Duration::from_millis(10));
+  }
+  assert_ne!(best_move(&output.lines()), "0000");
+
+  // pondering on a missed move is stopped
+  let lines = session(&["position startpos moves e2e4 e7e5", "go ponder depth 30", "stop"]);
+  assert_eq!(lines.iter().filter(|line| line.starts_with("bestmove ")).count(), 1);
+  assert!(lines.iter().all(|line| !line.starts_with("info string")));
+}
@@ -0,0 +1,378 @@
+#![warn(missing_docs)]
+#![warn(missing_debug_implementations)]
+
+//!
+//! Chess Engine Communication Protocol ( CECP ) version 2 of [ai::Engine], the protocol of XBoard and WinBoard.
+//! The position, history and result are kept by [Game], so takebacks and results follow the rules of the game.
+//! The engine thinks while its command is handled, the next command is read after the move.
+//!
+
+use game_chess_core::*;
+
+use std::io::{BufRead, Write};
+use std::time::Duration;
+
+///
+/// Score which XBoard shows as mate, mate in N moves is sent as this score plus N.
+///
+
+const MATE_SCORE : i32 = 100_000;
+
+///
+/// Time control set by `level`.
+///
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Level
+{
+  /// Moves of a session, 0 if all the game is played in one session.
+  moves_per_session : u32,
+  /// Time of a session.
+  base : Duration,
+  /// Time added after each move.
+  increment : Duration,
+}
+
+///
+/// State of the engine between commands: the game, the side of the engine and the limits of its search.
+///
+
+pub struct Xboard
+{
+  game : Game,
+  engine_side : Option<Player>,
+  post : bool,
+  depth : Option<u16>,
+  move_time : Option<Duration>,
+  level : Option<Level>,
+  time_left : Option<Duration>,
+  output : Box<dyn Write>,
+}
+
+impl Xboard
+{
+  ///
+  /// Constructs engine with a new game, the engine plays black. Responses are written to the output.
+  ///
+  pub fn new(output : impl Write + 'static) -> Self
+  {
+    let mut game = Game::default();
+    game.ai = Some(ai::Engine::new(String::from("alpha_beta")).expect("alpha_beta is a known algorithm"));
+    Self {
+      game,
+      engine_side : Some(Player::Black),
+      post : false,
+      depth : None,
+      move_time : None,
+      level : None,
+      time_left : None,
+      output : Box::new(output),
+    }
+  }
+
+  ///
+  /// Game played with the frontend.
+  ///
+  pub fn game(&self) -> &Game { &self.game }
+
+  ///
+  /// Side played by the engine, `None` in force mode.
+  ///
+  pub fn engine_side(&self) -> Option<Player> { self.engine_side }
+
+  ///
+  /// Handles a command. Returns `false` after `quit`.
+  ///
+  pub fn handle(&mut self, line : &str) -> bool
+  {
+    let tokens : Vec<&str> = line.split_whitespace().collect();
+    let argument = |i : usize| tokens.get(i).copied().unwrap_or("");
+    match tokens.first().copied()
+    {
+      None => (),
+      Some("xboard" | "accepted" | "rejected" | "hard" | "easy" | "random" | "computer" | "name" | "rating" | "ics" | "otim" | "?") => (),
+      Some("protover") => self.features(),
+      Some("new") => self.new_game(),
+      Some("setboard") => self.set_board(&tokens[1..].join(" ")),
+      Some("usermove") => self.user_move(argument(1)),
+      Some("go") =>
+      {
+        self.engine_side = Some(self.game.current_turn());
+        self.think();
+      }
+      Some("playother") => self.engine_side = Some(self.game.current_turn().other_player()),
+      Some("force") => self.engine_side = None,
+      Some("level") => self.set_level(argument(1), argument(2), argument(3)),
+      Some("st") => self.move_time = parse_seconds(argument(1)),
+      Some("sd") => self.depth = argument(1).parse::<u16>().ok().map(|depth| depth.clamp(1, ai::MAX_DEPTH)),
+      Some("time") => self.time_left = argument(1).parse::<u64>().ok().map(|centiseconds| Duration::from_millis(centiseconds * 10)),
+      Some("memory") =>
+      {
+        if let (Ok(megabytes), Some(engine)) = (argument(1).parse::<usize>(), &mut self.game.ai)
+        {
+          engine.set_hash_size(megabytes.clamp(1, ai::MAX_HASH_SIZE));
+        }
+      }
+      Some("undo") => self.take_back(1, line),
+      Some("remove") => self.take_back(2, line),
+      Some("result") => self.result(argument(1)),
+      Some("post") => self.post = true,
+      Some("nopost") => self.post = false,
+      Some("ping") => self.send(&format!("pong {}", argument(1))),
+      Some("quit") => return false,
+      Some(command) if tokens.len() == 1 && self.game.move_is_valid(UCI::from(command)) => self.user_move(command),
+      Some(command) => self.send(&format!("Error (unknown command): {}", command)),
+    }
+    true
+  }
+
+  fn features(&mut self)
+  {
+    let features = [
+      format!("myname=\"Game Chess {}\"", env!("CARGO_PKG_VERSION")),
+      String::from("ping=1 setboard=1 usermove=1 playother=1 san=0 time=1 draw=0 sigint=0 sigterm=0"),
+      String::from("reuse=1 analyze=0 colors=0 memory=1 variants=\"normal\" done=1"),
+    ];
+    for feature in features
+    {
+      self.send(&format!("feature {}", feature));
+    }
+  }
+
+  ///
+  /// Starts a new game, the engine plays black. Time control is kept, the depth limit is removed.
+  ///
+  fn new_game(&mut self)
+  {
+    let engine = self.game.ai.take();
+    self.game = Game::default();
+    self.game.ai = engine;
+    self.engine_side = Some(Player::Black);
+    self.depth = None;
+    self.time_left = self.level.map(|level| level.base);
+  }
+
+  fn set_board(&mut self, fen : &str)
+  {
+    match Game::from_fen(fen)
+    {
+      Ok(mut game) =>
+      {
+        game.ai = self.game.ai.take();
+        self.game = game;
+      }
+      Err(error) => self.send(&format!("tellusererror Illegal position : {}", error)),
+    }
+  }
+
+  fn user_move(&mut self, m : &str)
+  {
+    match self.game.make_move(UCI::from(m))
+    {
+      Ok(()) =>
+      {
+        if !self.send_result()
+        {
+          self.think();
+        }
+      }
+      Err(Error::IllegalMove(_)) => self.send(&format!("Illegal move: {}", m)),
+      Err(error) => self.send(&format!("Illegal move ({}): {}", error, m)),
+    }
+  }
+
+  ///
+  /// `level <moves per session> <minutes>[:<seconds>] <increment in seconds>`
+  ///
+  fn set_level(&mut self, moves_per_session : &str, base : &str, increment : &str)
+  {
+    let base_time = match base.split_once(':')
+    {
+      Some((minutes, seconds)) => minutes.parse::<u64>().ok().zip(seconds.parse::<u64>().ok()).map(|(m, s)| Duration::from_secs(m * 60 + s)),
+      None => base.parse::<u64>().ok().map(|minutes| Duration::from_secs(minutes * 60)),
+    };
+    match (moves_per_session.parse(), base_time, parse_seconds(increment))
+    {
+      (Ok(moves_per_session), Some(base), Some(increment)) =>
+      {
+        self.level = Some(Level { moves_per_session, base, increment });
+        self.time_left = Some(base);
+        self.move_time = None;
+      }
+      _ => self.send(&format!("Error (bad time control): level {} {} {}", moves_per_session, base, increment)),
+    }
+  }
+
+  ///
+  /// Takes back the half-moves. The result of the game is cleared, so the game can continue.
+  ///
+  fn take_back(&mut self, plies : usize, command : &str)
+  {
+    let result = match self.game.ply().checked_sub(plies)
+    {
+      Some(ply) => self.game.goto_ply(ply),
+      None => Err(Error::NothingToUndo),
+    };
+    if let Err(error) = result
+    {
+      self.send(&format!("Error ({}): {}", error, command));
+    }
+  }
+
+  ///
+  /// Records the result sent by the frontend: a resignation for a win, an agreed draw for a draw.
+  /// The engine stops playing until a new game.
+  ///
+  fn result(&mut self, token : &str)
+  {
+    let _ = match token
+    {
+      "1-0" => self.game.resign(Player::Black),
+      "0-1" => self.game.resign(Player::White),
+      "1/2-1/2" => self.game.agree_draw(),
+      _ => Ok(()),
+    };
+    self.engine_side = None;
+  }
+
+  ///
+  /// Searches and makes the move if the engine is on move and the game is not finished.
+  ///
+  fn think(&mut self)
+  {
+    if self.engine_side != Some(self.game.current_turn()) || self.game.result().is_over()
+    {
+      return;
+    }
+    let limits = self.limits();
+    let board = self.game.board().clone();
+    let engine = match &self.game.ai
+    {
+      Some(engine) => engine,
+      None => return,
+    };
+    let post = self.post;
+    let output = &mut self.output;
+    let m = engine.search(board.clone(), limits, ai::CancelHandle::new(), |info|
+    {
+      if post && info.multipv == 1
+      {
+        let _ = writeln!(output, "{}", thinking_line(&board, info));
+        let _ = output.flush();
+      }
+    });
+    if m.is_null()
+    {
+      return;
+    }
+
+    let uci = UCI::from(m);
+    let text = uci.0.clone();
+    match self.game.make_move(uci)
+    {
+      Ok(()) =>
+      {
+        self.send(&format!("move {}", text));
+        self.send_result();
+      }
+      Err(error) => self.send(&format!("Error ({}): {}", error, text)),
+    }
+  }
+
+  ///
+  /// Limits of the search: fixed time per move of `st`, or else the time left of `time` with the time control of `level`.
+  /// Depth of `sd` limits both. Without limits the depth of the engine is used.
+  ///
+  fn limits(&self) -> ai::Limits
+  {
+    let mut limits = ai::Limits { depth : self.depth, movetime : self.move_time, ..ai::Limits::default() };
+    if let (None, Some(remaining)) = (self.move_time, self.time_left)
+    {
+      let level = self.level.unwrap_or(Level { moves_per_session : 0, base : remaining, increment : Duration::ZERO });
+      let moves_to_go = match level.moves_per_session
+      {
+        0 => None,
+        moves => Some(moves - (self.game.ply() / 2) as u32 % moves),
+      };
+      limits.clock = Some(ai::TimeLeft { remaining, increment : level.increment, moves_to_go });
+    }
+    limits
+  }
+
+  ///
+  /// Sends the result if the game is finished. Returns true if it is.
+  ///
+  fn send_result(&mut self) -> bool
+  {
+    let result = self.game.result();
+    if result.is_over()
+    {
+      self.send(&format!("{} {{{}}}", result.pgn_token(), result));
+    }
+    result.is_over()
+  }
+
+  fn send(&mut self, line : &str)
+  {
+    let _ = writeln!(self.output, "{}", line);
+    let _ = self.output.flush();
+  }
+}
+
+impl std::fmt::Debug for Xboard
+{
+  fn fmt(&self, f : &mut std::fmt::Formatter<'_>) -> std::fmt::Result
+  {
+    f.debug_struct("Xboard")
+      .field("game", &self.game)
+      .field("engine_side", &self.engine_side)
+      .field("post", &self.post)
+      .field("depth", &self.depth)
+      .field("move_time", &self.move_time)
+      .field("level", &self.level)
+      .field("time_left", &self.time_left)
+      .finish()
+  }
+}
+
+///
+/// Reads commands until `quit` or the end of the input.
+///
+
+pub fn run(input : impl BufRead, output : impl Write + 'static) -> std::io::Result<()>
+{
+  let mut xboard = Xboard::new(output);
+  for line in input.lines()
+  {
+    if !xboard.handle(&line?)
+    {
+      break;
+    }
+  }
+  Ok(())
+}
+
+///
+/// `<depth> <score in centipawns> <time in centiseconds> <nodes> <principal variation in SAN>`
+///
+
+fn thinking_line(board : &Board, info : &ai::SearchInfo) -> String
+{
+  let score = match info.score
+  {
+    ai::Score::Centipawns(centipawns) => centipawns,
+    ai::Score::Mate(moves) if moves > 0 => MATE_SCORE + moves,
+    ai::Score::Mate(moves) => -MATE_SCORE + moves,
+  };
+  let line = ai::Line { score : info.score, pv : info.pv.clone() };
+  format!("{} {} {} {} {}", info.depth, score, info.time.as_millis() / 10, info.nodes, line.to_san(board).join(" "))
+}
+
+///
+/// Seconds, possibly fractional, as duration. `None` if the text is not a non-negative number.
+///
+
+fn parse_seconds(text : &str) -> Option<Duration>
+{
+  text.parse::<f64>().ok().filter(|seconds| seconds.is_finite() && *seconds >= 0.0).map(Duration::from_secs_f64)
+}
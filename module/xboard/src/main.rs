@@ -0,0 +1,12 @@
+#![warn(missing_docs)]
+#![warn(missing_debug_implementations)]
+
+//!
+//! Chess engine speaking Chess Engine Communication Protocol ( CECP ) over stdin and stdout.
+//!
+
+fn main() -> std::io::Result<()>
+{
+  let stdin = std::io::stdin();
+  game_chess_xboard::run(stdin.lock(), std::io::stdout())
+}
@@ -0,0 +1,165 @@
+use game_chess_core::*;
+use game_chess_xboard::Xboard;
+
+use std::io::Write;
+use std::sync::{Arc, Mutex};
+
+/*
+cargo test -p game_chess_xboard -- --show-output
+*/
+
+///
+/// Output shared with the engine, so it can be read while the engine is alive.
+///
+
+#[derive(Clone, Default)]
+struct Buffer(Arc<Mutex<Vec<u8>>>);
+
+impl Write for Buffer
+{
+  fn write(&mut self, buf : &[u8]) -> std::io::Result<usize>
+  {
+    self.0.lock().unwrap().extend_from_slice(buf);
+    Ok(buf.len())
+  }
+
+  fn flush(&mut self) -> std::io::Result<()> { Ok(()) }
+}
+
+impl Buffer
+{
+  fn lines(&self) -> Vec<String> { String::from_utf8_lossy(&self.0.lock().unwrap()).lines().map(String::from).collect() }
+
+  fn take(&self) -> Vec<String>
+  {
+    let lines = self.lines();
+    self.0.lock().unwrap().clear();
+    lines
+  }
+}
+
+fn engine(commands : &[&str]) -> (Xboard, Buffer)
+{
+  let output = Buffer::default();
+  let mut xboard = Xboard::new(output.clone());
+  for command in commands
+  {
+    assert!(xboard.handle(command));
+  }
+  (xboard, output)
+}
+
+fn engine_moves(lines : &[String]) -> Vec<&str> { lines.iter().filter_map(|line| line.strip_prefix("move ")).collect() }
+
+#[test]
+fn test_xboard_handshake()
+{
+  let (mut xboard, output) = engine(&["xboard", "protover 2", "ping 7"]);
+  let lines = output.take();
+  assert!(lines.iter().all(|line| line.starts_with("feature ") || line == "pong 7"));
+  assert!(lines.iter().any(|line| line.contains("usermove=1")));
+  assert!(lines.iter().any(|line| line.contains("done=1")));
+  assert_eq!(lines.last().unwrap(), "pong 7");
+
+  // hash size of the engine is clamped
+  assert!(xboard.handle("memory 18446744073709551615"));
+  assert_eq!(xboard.game().ai.as_ref().unwrap().hash_size(), ai::MAX_HASH_SIZE);
+  assert!(xboard.handle("memory 0"));
+  assert_eq!(xboard.game().ai.as_ref().unwrap().hash_size(), 1);
+  assert!(!xboard.handle("quit"));
+}
+
+#[test]
+fn test_xboard_play()
+{
+  // engine plays black by default
+  let (mut xboard, output) = engine(&["new", "sd 2", "usermove e2e4"]);
+  let lines = output.take();
+  assert_eq!(engine_moves(&lines).len(), 1);
+  assert_eq!(xboard.game().ply(), 2);
+  assert_eq!(xboard.game().current_turn(), Player::White);
+
+  // moves in force mode are only recorded
+  xboard.handle("force");
+  xboard.handle("usermove d2d4");
+  xboard.handle("usermove e2e5");
+  assert_eq!(xboard.game().ply(), 3);
+  assert_eq!(output.take(), vec!["Illegal move: e2e5"]);
+
+  // go makes the engine play the side to move
+  xboard.handle("go");
+  assert_eq!(xboard.engine_side(), Some(Player::Black));
+  assert_eq!(engine_moves(&output.take()).len(), 1);
+  assert_eq!(xboard.game().ply(), 4);
+
+  // new game keeps the engine
+  xboard.handle("new");
+  assert_eq!(xboard.game().ply(), 0);
+  assert!(xboard.game().has_ai());
+}
+
+#[test]
+fn test_xboard_takeback()
+{
+  let (mut xboard, output) = engine(&["new", "force", "usermove e2e4", "usermove e7e5", "usermove g1f3"]);
+  xboard.handle("undo");
+  assert_eq!(xboard.game().ply(), 2);
+  xboard.handle("remove");
+  assert_eq!(xboard.game().ply(), 0);
+  assert!(output.take().is_empty());
+  xboard.handle("undo");
+  assert!(output.take()[0].starts_with("Error ("));
+  xboard.handle("usermove e2e4");
+  assert_eq!(xboard.game().ply(), 1);
+}
+
+#[test]
+fn test_xboard_result()
+{
+  // engine mates and reports the result
+  let (mut xboard, output) = engine(&["setboard 7k/R7/8/8/8/8/8/1R4K1 w - - 0 1", "sd 2", "go"]);
+  let lines = output.take();
+  assert_eq!(engine_moves(&lines), vec!["b1b8"]);
+  assert_eq!(lines.last().unwrap(), "1-0 {White wins by checkmate}");
+
+  // result of the frontend finishes the game
+  xboard.handle("new");
+  xboard.handle("force");
+  xboard.handle("usermove e2e4");
+  xboard.handle("result 0-1 {White resigns}");
+  assert_eq!(xboard.game().result(), GameResult::BlackWins(Termination::Resignation));
+  assert_eq!(xboard.engine_side(), None);
+  xboard.handle("usermove e7e5");
+  assert!(output.take()[0].starts_with("Illegal move ("));
+
+  // takeback continues the game
+  xboard.handle("undo");
+  assert_eq!(xboard.game().result(), GameResult::Ongoing);
+
+  xboard.handle("setboard not a fen");
+  assert!(output.take()[0].starts_with("tellusererror "));
+}
+
+#[test]
+fn test_xboard_post_and_time()
+{
+  let (_, output) = engine(&["new", "post", "level 40 0:30 0", "st 1", "sd 3", "usermove e2e4"]);
+  let lines = output.take();
+  let thinking : Vec<&String> = lines.iter().filter(|line| !line.starts_with("move ")).collect();
+  assert!(!thinking.is_empty());
+  for line in thinking
+  {
+    let fields : Vec<&str> = line.split_whitespace().collect();
+    assert!(fields.len() >= 5, "{}", line);
+    assert!(fields[..4].iter().all(|field| field.parse::<i64>().is_ok()), "{}", line);
+  }
+  assert_eq!(engine_moves(&lines).len(), 1);
+
+  let (_, output) = engine(&["new", "level 0 1 1", "time 1000", "otim 1000", "usermove e2e4"]);
+  assert_eq!(engine_moves(&output.take()).len(), 1);
+
+  let (_, output) = engine(&["level 40 x 0", "unknown"]);
+  let lines = output.take();
+  assert!(lines[0].starts_with("Error (bad time control)"));
+  assert_eq!(lines[1], "Error (unknown command): unknown");
+}
@@ -0,0 +1,87 @@
+/*
+How to compare AI algorithms: play a match between two engines of equal depth
+
+cargo run --release --bin sample_core_ai_match
+cargo run --release --bin sample_core_ai_match -- alpha_beta iterative 3
+*/
+
+use game_chess_core::{ai, Game, GameResult, Player, UCI};
+
+///
+/// Openings of the games, each one is played twice with swapped colors.
+///
+
+const OPENINGS : [&[&str]; 4] = [
+  &["e2e4", "e7e5"],
+  &["d2d4", "d7d5"],
+  &["e2e4", "c7c5"],
+  &["c2c4", "g8f6"],
+];
+
+///
+/// Games longer than this are adjudicated as draws.
+///
+
+const MAX_PLIES : usize = 200;
+
+pub fn main()
+{
+  let mut args = std::env::args().skip(1);
+  let first = args.next().unwrap_or_else(|| "alpha_beta".to_string());
+  let second = args.next().unwrap_or_else(|| "iterative".to_string());
+  let depth : u16 = args.next().and_then(|depth| depth.parse().ok()).unwrap_or(3).max(1);
+
+  let engines = [
+    ai::Engine::new_with_depth(first.clone(), depth).expect("Unknown algorithm"),
+    ai::Engine::new_with_depth(second.clone(), depth).expect("Unknown algorithm"),
+  ];
+
+  let mut points = [0.0, 0.0];
+  for opening in OPENINGS
+  {
+    for first_is_white in [true, false]
+    {
+      let white = if first_is_white { 0 } else { 1 };
+      let result = play(&engines, white, opening);
+      match result.winner()
+      {
+        Some(player) =>
+        {
+          let engine = if player == Player::White { white } else { 1 - white };
+          points[engine] += 1.0;
+        }
+        None =>
+        {
+          points[0] += 0.5;
+          points[1] += 0.5;
+        }
+      }
+      let (white_name, black_name) = if first_is_white { (&first, &second) } else { (&second, &first) };
+      println!("{} : {} - {} : {}", opening.join(" "), white_name, black_name, result);
+    }
+  }
+
+  println!("\n{} {} : {} {} at depth {}", first, second, points[0], points[1], depth);
+}
+
+///
+/// Plays a game from the opening. Unfinished game after `MAX_PLIES` is ongoing and scored as a draw.
+///
+
+fn play(engines : &[ai::Engine; 2], white : usize, opening : &[&str]) -> GameResult
+{
+  let mut game = Game::default();
+  for m in opening
+  {
+    game.make_move(UCI::from(*m)).expect("Illegal move of the opening");
+  }
+
+  while !game.result().is_over() && game.ply() < MAX_PLIES
+  {
+    let engine = if game.current_turn() == Player::White { &engines[white] } else { &engines[1 - white] };
+    let m = engine.best_move(game.board().clone());
+    game.make_move(UCI::from(m)).expect("Engine made illegal move");
+  }
+
+  game.result()
+}
@@ -0,0 +1,39 @@
+/*
+How to check move generation with perft: count positions reachable in a number of half-moves
+
+cargo run --bin sample_core_perft -- 4
+cargo run --bin sample_core_perft -- 3 "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1"
+*/
+
+use game_chess_core::{Board, Fen, UCI};
+
+pub fn main()
+{
+  /*
+  https://www.chessprogramming.org/Perft
+  https://www.chessprogramming.org/Perft_Results
+  */
+
+  let mut args = std::env::args().skip(1);
+  let depth : u16 = args.next().and_then(|depth| depth.parse().ok()).unwrap_or(3).max(1);
+  let board = match args.next()
+  {
+    Some(fen) => Board::from_fen(&Fen::from(fen)).expect("Invalid FEN"),
+    None => Board::default(),
+  };
+
+  board.print();
+
+  let start = std::time::Instant::now();
+  let divide = board.divide(depth); //Count of positions after each legal move
+  for (m, count) in &divide
+  {
+    println!("{} : {}", UCI::from(*m).0, count);
+  }
+  let nodes : u64 = divide.iter().map(|(_, count)| count).sum();
+  let elapsed = start.elapsed();
+
+  println!("\nMoves : {}", divide.len());
+  println!("Nodes : {}", nodes);
+  println!("Time : {:.3} s, {:.0} nodes per second", elapsed.as_secs_f64(), nodes as f64 / elapsed.as_secs_f64().max(1e-9));
+}